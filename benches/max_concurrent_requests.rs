@@ -0,0 +1,41 @@
+//! Compare the time to render a tile pyramid with different limits of concurrent requests.
+//!
+//! Every run starts with an empty in-memory cache, so all tiles are fetched from the network,
+//! although later runs may benefit from connections kept open by the earlier ones.
+//! The style and the zoom range can be changed with the `STYLE_URL` and `MAX_ZOOM` variables.
+
+use std::time::Instant;
+
+use maplibre_native::{set_max_concurrent_requests, ImageRendererOptions};
+
+fn main() {
+    let style = std::env::var("STYLE_URL")
+        .unwrap_or_else(|_| "https://demotiles.maplibre.org/style.json".to_string());
+    let max_zoom: u8 = std::env::var("MAX_ZOOM")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3);
+
+    for max_requests in [1, 4, 20, 64] {
+        // The limit applies to the network file source of the renderer built next
+        set_max_concurrent_requests(max_requests);
+        let mut opts = ImageRendererOptions::new();
+        opts.with_in_memory_cache();
+        let mut map = opts.build_tile_renderer();
+        map.set_style_url(&style).unwrap();
+
+        let start = Instant::now();
+        let mut tiles = 0;
+        for zoom in 0..=max_zoom {
+            for (_, _, image) in map.render_tile_range(zoom, .., ..) {
+                image.unwrap();
+                tiles += 1;
+            }
+        }
+        let elapsed = start.elapsed();
+        println!(
+            "max_concurrent_requests={max_requests:>3}: {tiles} tiles in {elapsed:.2?} ({:.1} tiles/s)",
+            f64::from(tiles) / elapsed.as_secs_f64()
+        );
+    }
+}
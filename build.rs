@@ -1,13 +1,14 @@
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::OnceLock;
 use std::{env, fs};
 
 // This allows build support to be unit-tested as well as packaged with the crate.
 #[path = "build_helper.rs"]
 mod build_helper;
 
-use build_helper::parse_deps;
+use build_helper::{parse_deps, revision_matches, submodules_complete};
 use walkdir::WalkDir;
 
 trait CfgBool {
@@ -31,6 +32,14 @@ enum GraphicsRenderingAPI {
     Vulkan,
 }
 impl GraphicsRenderingAPI {
+    /// The rendering API to build, see [`Self::from_selected_features`] and [`Self::with_fallback`].
+    ///
+    /// It is only selected once, so that any warnings are not repeated.
+    fn selected() -> Self {
+        static SELECTED: OnceLock<GraphicsRenderingAPI> = OnceLock::new();
+        *SELECTED.get_or_init(|| Self::from_selected_features().with_fallback())
+    }
+
     /// Selects the rendering API based on enabled cargo features and platform.
     ///
     /// - If one feature is enabled, it is used.
@@ -73,6 +82,27 @@ impl GraphicsRenderingAPI {
         }
     }
 }
+impl GraphicsRenderingAPI {
+    /// Falls back from Vulkan to OpenGL if `MLN_FALLBACK_OPENGL` is set,
+    /// and `pkg-config` cannot find the Vulkan development files,
+    /// e.g. on CI or server machines that only have OpenGL installed.
+    fn with_fallback(self) -> Self {
+        println!("cargo:rerun-if-env-changed=MLN_FALLBACK_OPENGL");
+        if self != Self::Vulkan || env::var_os("MLN_FALLBACK_OPENGL").is_none() {
+            return self;
+        }
+        let has_vulkan = Command::new("pkg-config")
+            .args(["--exists", "vulkan"])
+            .status()
+            .is_ok_and(|status| status.success());
+        if has_vulkan {
+            self
+        } else {
+            println!("cargo:warning=Vulkan was not found by pkg-config, building with OpenGL instead because MLN_FALLBACK_OPENGL is set");
+            Self::OpenGL
+        }
+    }
+}
 impl std::fmt::Display for GraphicsRenderingAPI {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -90,11 +120,13 @@ impl std::fmt::Display for GraphicsRenderingAPI {
 fn create_cmake_config(cpp_root: &Path) -> cmake::Config {
     let mut cfg = cmake::Config::new(cpp_root);
     cfg.generator("Ninja");
-    cfg.define("CMAKE_C_COMPILER_LAUNCHER", "ccache");
-    cfg.define("CMAKE_CXX_COMPILER_LAUNCHER", "ccache");
+    if !is_windows_target() {
+        cfg.define("CMAKE_C_COMPILER_LAUNCHER", "ccache");
+        cfg.define("CMAKE_CXX_COMPILER_LAUNCHER", "ccache");
+    }
     cfg.define_bool("MLN_DRAWABLE_RENDERER", true);
 
-    let rendering_backend = GraphicsRenderingAPI::from_selected_features();
+    let rendering_backend = GraphicsRenderingAPI::selected();
     cfg.define_bool(
         "MLN_WITH_OPENGL",
         rendering_backend == GraphicsRenderingAPI::OpenGL,
@@ -122,7 +154,10 @@ fn create_cmake_config(cpp_root: &Path) -> cmake::Config {
 
 /// If the dest dir is not empty, validate it.
 /// If it exists but empty, abort because we are doing local development without cloning submodules.
-fn validate_mln(dir: &Path, revision: &str) -> bool {
+/// A checkout at another revision is an error, unless `strict` is false, e.g. when the revision
+/// was overridden with `MLN_REVISION`, where the checkout is skipped with a warning instead.
+/// Returns whether the directory can be built.
+fn validate_mln(dir: &Path, revision: &str, strict: bool) -> bool {
     if !dir.is_dir() {
         return false;
     }
@@ -138,20 +173,33 @@ You may also set MLN_FROM_SOURCE to the path of the maplibre-native directory.
     );
 
     let dest_disp = dir.display();
+    let rev = git_revision(dir).expect("Failed to validate git repo");
+    if strict {
+        assert_eq!(
+            rev,
+            revision,
+            "Unexpected git revision in {dest_disp}, please update the build.rs with the new value '{rev}'",
+        );
+    } else if revision_matches(&rev, revision) != Some(true) {
+        println!("cargo:warning=Not using maplibre-native at {rev} in {dest_disp}, it is not at the requested {revision}");
+        return false;
+    }
+    true
+}
+
+/// Get the commit checked out in a git repo, or `None` if it is not a git repo.
+fn git_revision(dir: &Path) -> Option<String> {
     let rev = Command::new("git")
         .current_dir(dir)
         .arg("rev-parse")
         .arg("HEAD")
         .output()
         .expect("Failed to get git revision");
-    assert!(rev.status.success(), "Failed to validate git repo");
+    if !rev.status.success() {
+        return None;
+    }
     let rev = String::from_utf8(rev.stdout).expect("Failed to parse git rev response");
-    assert_eq!(
-        rev.trim_ascii(),
-        revision,
-        "Unexpected git revision in {dest_disp}, please update the build.rs with the new value '{rev}'",
-    );
-    true
+    Some(rev.trim_ascii().to_string())
 }
 
 fn clone_mln(dir: &Path, repo: &str, revision: &str) {
@@ -185,6 +233,11 @@ fn clone_mln(dir: &Path, repo: &str, revision: &str) {
     // # reset this repository's master branch to the commit of interest
     git(dir, ["reset", "--hard", "FETCH_HEAD"]);
     // # fetch submodules
+    update_submodules(dir);
+}
+
+/// Fetch the submodules that are missing, keeping those that are already checked out.
+fn update_submodules(dir: &Path) {
     git(
         dir,
         [
@@ -198,6 +251,66 @@ fn clone_mln(dir: &Path, repo: &str, revision: &str) {
     );
 }
 
+/// Whether all submodules of a clone are checked out at their recorded commits.
+fn has_all_submodules(dir: &Path) -> bool {
+    let status = Command::new("git")
+        .current_dir(dir)
+        .args(["submodule", "status", "--recursive"])
+        .output()
+        .expect("Failed to get git submodule status");
+    status.status.success() && submodules_complete(&String::from_utf8_lossy(&status.stdout))
+}
+
+/// Clone maplibre-native, and make sure the checkout is the requested commit.
+///
+/// An interrupted fetch or a stale clone left in `OUT_DIR` would otherwise only fail much later,
+/// deep in the C++ build or at link time. On a mismatch, the clone is removed and retried once.
+fn clone_verified_mln(dir: &Path, repo: &str, revision: &str) {
+    // Reuse a clone from a previous build if it is already at the requested commit.
+    // A build interrupted while fetching the submodules only fetches the missing ones,
+    // instead of cloning everything again. A partial git fetch itself cannot be resumed.
+    let existing = dir.is_dir().then(|| git_revision(dir)).flatten();
+    if existing.is_some_and(|rev| revision_matches(&rev, revision) == Some(true)) {
+        if has_all_submodules(dir) {
+            return;
+        }
+        println!(
+            "cargo:warning=Fetching missing submodules of the maplibre-native clone in {}",
+            dir.display()
+        );
+        update_submodules(dir);
+        if has_all_submodules(dir) {
+            return;
+        }
+    }
+    for _ in 0..2 {
+        if dir.exists() {
+            fs::remove_dir_all(dir)
+                .unwrap_or_else(|e| panic!("Failed to remove {}: {e}", dir.display()));
+        }
+        clone_mln(dir, repo, revision);
+        let actual = git_revision(dir).unwrap_or_default();
+        match revision_matches(&actual, revision) {
+            None => {
+                println!("cargo:warning=Cannot verify maplibre-native revision {revision}, it is not a commit hash");
+                return;
+            }
+            Some(true) if has_all_submodules(dir) => return,
+            Some(true) => println!(
+                "cargo:warning=Cloned maplibre-native in {} is missing submodules",
+                dir.display()
+            ),
+            Some(false) => println!(
+                "cargo:warning=Cloned maplibre-native is at '{actual}' instead of {revision}"
+            ),
+        }
+    }
+    panic!(
+        "Failed to check out maplibre-native {revision} from {repo} into {}, the fetched commit or its submodules do not match. Remove the directory and try again, or set MLN_FROM_SOURCE to a local checkout.",
+        dir.display()
+    );
+}
+
 fn git<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(dir: &Path, args: I) {
     fs::create_dir_all(dir).unwrap_or_else(|e| panic!("Failed to create {}: {e}", dir.display()));
 
@@ -237,6 +350,22 @@ fn git<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(dir: &Path, args: I) {
 const MLN_GIT_REPO: &str = "https://github.com/maplibre/maplibre-native.git";
 const MLN_REVISION: &str = "3fc93a0b024e34514dafcbb424db93593ff540be";
 
+/// The MapLibre Native revision to build, and whether it was overridden by the `MLN_REVISION`
+/// environment variable instead of the pinned [`MLN_REVISION`], e.g. to try a newer upstream version.
+fn mln_revision() -> (String, bool) {
+    println!("cargo:rerun-if-env-changed=MLN_REVISION");
+    match env::var("MLN_REVISION") {
+        Ok(revision) if !revision.trim().is_empty() => (revision.trim().to_string(), true),
+        _ => (MLN_REVISION.to_string(), false),
+    }
+}
+
+/// Find the MapLibre Native sources to build, in order of precedence:
+/// 1. the `MLN_FROM_SOURCE` directory,
+/// 2. the `maplibre-native` submodule, if it has been checked out, and is at the `MLN_REVISION` commit if that is set,
+/// 3. a clone in `OUT_DIR` at the pinned revision, or the one set by `MLN_REVISION`.
+///
+/// There are no prebuilt libraries to download, the sources are always compiled.
 fn clone_or_download(root: &Path) -> PathBuf {
     println!("cargo:rerun-if-env-changed=MLN_FROM_SOURCE");
     let cpp_root = env::var_os("MLN_FROM_SOURCE").map(PathBuf::from);
@@ -248,21 +377,29 @@ fn clone_or_download(root: &Path) -> PathBuf {
             "Directory {cpp_disp} does not contain maplibre-native"
         );
         println!("cargo:warning=Using maplibre-native at {cpp_disp}");
+        // Expose the actual revision to the crate, see `maplibre_version()`
+        let revision = git_revision(&cpp_root).unwrap_or_else(|| "unknown".to_string());
+        println!("cargo:rustc-env=MLN_REVISION={revision}");
         cpp_root
     } else {
+        let (revision, overridden) = mln_revision();
+        if overridden {
+            println!("cargo:warning=Building maplibre-native at {revision} set by MLN_REVISION");
+        }
         // Check if this is a local development with the submodule
         let mut cpp_root = root.join("maplibre-native");
-        if validate_mln(&cpp_root, MLN_REVISION) {
-            // Do not print any warnings - using the submodule directly
-            cpp_root
-        } else {
-            // Clone the repo into OUT_DIR - probably because this is part of dependency build
+        if !validate_mln(&cpp_root, &revision, !overridden) {
+            // Clone the repo into OUT_DIR - probably because this is part of dependency build,
+            // or the submodule is not at the revision set by MLN_REVISION
             // Warnings shouldn't show up in the final build output unless there's an error
             cpp_root = env::var_os("OUT_DIR").expect("OUT_DIR is not set").into();
             cpp_root.push("maplibre-native");
-            clone_mln(&cpp_root, MLN_GIT_REPO, MLN_REVISION);
-            cpp_root
+            clone_verified_mln(&cpp_root, MLN_GIT_REPO, &revision);
         }
+        // Expose the commit that is built, as MLN_REVISION may also be a branch or a tag name
+        let built = git_revision(&cpp_root).unwrap_or(revision);
+        println!("cargo:rustc-env=MLN_REVISION={built}");
+        cpp_root
     };
 
     let check_cmake_list = cpp_root.join("CMakeLists.txt");
@@ -290,12 +427,15 @@ fn add_link_targets(cpp_root: &Path) {
     }
 
     // FIXME:  These should not be manually set like this here
-    println!("cargo:rustc-link-lib=icuuc");
-    println!("cargo:rustc-link-lib=icui18n");
-    println!("cargo:rustc-link-lib=jpeg");
-    println!("cargo:rustc-link-lib=png");
-    println!("cargo:rustc-link-lib=z");
-    println!("cargo:rustc-link-lib=curl");
+    // On Windows, the libraries have other names, and are all listed in the deps file
+    if !is_windows_target() {
+        println!("cargo:rustc-link-lib=icuuc");
+        println!("cargo:rustc-link-lib=icui18n");
+        println!("cargo:rustc-link-lib=jpeg");
+        println!("cargo:rustc-link-lib=png");
+        println!("cargo:rustc-link-lib=z");
+        println!("cargo:rustc-link-lib=curl");
+    }
 }
 
 /// Build the actual "mbgl-core" static library target.
@@ -325,20 +465,34 @@ fn build_bridge(root: &Path, cpp_root: &Path) {
     }
 
     println!("cargo:rerun-if-changed=src/renderer/bridge.rs");
+    println!("cargo:rerun-if-changed=src/renderer/bridge.cpp");
     println!("cargo:rerun-if-changed=include/map_renderer.h");
     cxx_build::bridge("src/renderer/bridge.rs")
         .includes(&include_dirs)
         .file("src/renderer/bridge.cpp")
         .flag_if_supported("-std=c++20")
+        .flag_if_supported("/std:c++20")
         .compile("maplibre_rust_map_renderer_bindings");
 
     // Link mbgl-core after the bridge - or else `cargo test` won't be able to find the symbols.
     println!("cargo:rustc-link-lib=static=mbgl-core");
 }
 
+/// Whether the crate is compiled for Windows. Unlike `cfg!`, this checks the target, not the host.
+fn is_windows_target() -> bool {
+    env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os == "windows")
+}
+
 fn build_mln() {
     let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
     let cpp_root = clone_or_download(&root);
+    if is_windows_target() {
+        assert!(
+            GraphicsRenderingAPI::selected() != GraphicsRenderingAPI::Metal,
+            "The 'metal' feature is not supported on Windows, use 'opengl' or 'vulkan'"
+        );
+        println!("cargo:warning=Windows support is experimental, see the README for the required dependencies");
+    }
     if cpp_root.is_dir() {
         add_link_targets(&cpp_root);
         build_static_lib(&cpp_root);
@@ -349,11 +503,16 @@ fn build_mln() {
 }
 
 fn main() {
+    // Expose the selected backend to the crate, see `active_backend()`
+    let backend = GraphicsRenderingAPI::selected();
+    println!("cargo:rustc-env=MLN_GRAPHICS_BACKEND={backend}");
+
     println!("cargo:rerun-if-env-changed=DOCS_RS");
     if env::var("DOCS_RS").is_ok() {
         println!("cargo:warning=Skipping build.rs when building for docs.rs");
         println!("cargo::rustc-cfg=docsrs");
         println!("cargo:rustc-check-cfg=cfg(docsrs)");
+        println!("cargo:rustc-env=MLN_REVISION={MLN_REVISION}");
     } else {
         build_mln();
     }
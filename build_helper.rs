@@ -30,16 +30,27 @@ pub fn parse_deps(deps_contents: &str, static_lib_base: &Path, include_args: boo
             }
         } else if let Some(libname) = token.strip_prefix("-l") {
             instructions.push(format!("cargo:rustc-link-lib={libname}"));
-        } else if Path::new(token)
+        } else if let Some(is_msvc) = Path::new(token)
             .extension()
-            .is_some_and(|ext| ext.eq_ignore_ascii_case("a"))
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| match ext.to_ascii_lowercase().as_str() {
+                "a" => Some(false),
+                "lib" => Some(true),
+                _ => None,
+            })
         {
             let lib_path = Path::new(token);
             let file_stem = lib_path.file_stem().expect("Library file has no stem");
             let file_stem = file_stem
                 .to_str()
                 .expect("Library file stem is not valid UTF-8");
-            let lib_name = file_stem.strip_prefix("lib").unwrap_or(file_stem);
+            // MSVC libraries are linked by their file name, e.g. `libpng16.lib`,
+            // while Unix linkers add the `lib` prefix themselves.
+            let lib_name = if is_msvc {
+                file_stem
+            } else {
+                file_stem.strip_prefix("lib").unwrap_or(file_stem)
+            };
 
             let search_dir = match lib_path.parent() {
                 Some(parent) if !parent.as_os_str().is_empty() => static_lib_base.join(parent),
@@ -51,7 +62,12 @@ pub fn parse_deps(deps_contents: &str, static_lib_base: &Path, include_args: boo
                     search_dir.to_str().expect("Search path is not valid UTF-8")
                 ));
             }
-            instructions.push(format!("cargo:rustc-link-lib=static={lib_name}"));
+            if is_msvc {
+                // A `.lib` may also be the import library of a DLL, which cannot be linked statically
+                instructions.push(format!("cargo:rustc-link-lib={lib_name}"));
+            } else {
+                instructions.push(format!("cargo:rustc-link-lib=static={lib_name}"));
+            }
         } else if include_args {
             // FIXME: should not use args by default, maybe with a feature flag?
             instructions.push(format!("cargo:rustc-link-arg={token}"));
@@ -62,6 +78,37 @@ pub fn parse_deps(deps_contents: &str, static_lib_base: &Path, include_args: boo
     instructions
 }
 
+/// Checks that the commit checked out by git matches the requested revision.
+///
+/// The commit hash identifies the content of the source tree, including the commits its
+/// submodules are pinned to, so a match means the fetched sources are the requested ones.
+/// This is the only integrity check, as the sources are fetched with git, not downloaded as archives.
+/// `expected` may be a full or an abbreviated (at least 7 digits) commit hash.
+/// Returns `None` for branch and tag names, which cannot be verified this way.
+#[must_use]
+pub fn revision_matches(actual: &str, expected: &str) -> Option<bool> {
+    let expected = expected.trim().to_ascii_lowercase();
+    if expected.len() < 7 || expected.len() > 40 || !expected.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return None;
+    }
+    let actual = actual.trim().to_ascii_lowercase();
+    Some(actual.len() == 40 && actual.starts_with(&expected))
+}
+
+/// Checks the output of `git submodule status --recursive` for an incomplete checkout.
+///
+/// Each line starts with a space if the submodule is at the recorded commit,
+/// `-` if it is not initialized, e.g. after an interrupted fetch, `+` if it is at another commit,
+/// and `U` if it has merge conflicts. Only the first case counts as complete.
+#[must_use]
+pub fn submodules_complete(status: &str) -> bool {
+    status
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .all(|line| line.starts_with(' '))
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
@@ -88,6 +135,56 @@ mod tests {
         assert_eq!(instructions, expected);
     }
 
+    #[test]
+    fn test_revision_matches() {
+        let rev = "3fc93a0b024e34514dafcbb424db93593ff540be";
+        assert_eq!(revision_matches(rev, rev), Some(true));
+        assert_eq!(
+            revision_matches(&format!("{rev}\n"), "3FC93A0B"),
+            Some(true)
+        );
+        assert_eq!(revision_matches(rev, "3fc93a0"), Some(true));
+        assert_eq!(
+            revision_matches(rev, "4fc93a0b024e34514dafcbb424db93593ff540be"),
+            Some(false)
+        );
+        assert_eq!(revision_matches("3fc93a0", "3fc93a0"), Some(false));
+        assert_eq!(revision_matches(rev, "main"), None);
+        assert_eq!(revision_matches(rev, "3fc93a"), None);
+        assert_eq!(revision_matches(rev, "maplibre-native-v1.0"), None);
+    }
+
+    #[test]
+    fn test_submodules_complete() {
+        assert!(submodules_complete(""));
+        let complete = " 0b5f2ab6e7f3c5d6a7b8c9d0e1f2a3b4c5d6e7f8 vendor/boost (heads/main)\n 1c2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0d vendor/earcut.hpp (v2.2.4)\n";
+        assert!(submodules_complete(complete));
+        assert!(!submodules_complete(&format!(
+            "{complete}-2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0d1e vendor/glslang\n"
+        )));
+        assert!(!submodules_complete(
+            "+3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0d1e2f vendor/zlib (v1.3)"
+        ));
+        assert!(!submodules_complete(
+            "U4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0d1e2f3a vendor/zlib"
+        ));
+    }
+
+    #[test]
+    fn test_parse_msvc_deps() {
+        let deps_content = "mbgl-core.lib vendor/libpng16.lib zlib.lib";
+        let base_dir = PathBuf::from("/build_dir/build");
+        let instructions = parse_deps(deps_content, &base_dir, true);
+        let expected = [
+            "cargo:rustc-link-search=native=/build_dir/build",
+            "cargo:rustc-link-lib=mbgl-core",
+            "cargo:rustc-link-search=native=/build_dir/build/vendor",
+            "cargo:rustc-link-lib=libpng16",
+            "cargo:rustc-link-lib=zlib",
+        ];
+        assert_eq!(instructions, expected);
+    }
+
     #[test]
     fn long_parse() {
         let v = "-ffunction-sections -fdata-sections -fPIC -m64   libmbgl-core.a  libmbgl-vendor-parsedate.a  libmbgl-vendor-csscolorparser.a  vendor/glslang/glslang/libglslang.a  vendor/glslang/SPIRV/libSPIRV.a  vendor/glslang/glslang/libMachineIndependent.a  vendor/glslang/glslang/OSDependent/Unix/libOSDependent.a  vendor/glslang/glslang/libGenericCodeGen.a  vendor/glslang/glslang/libglslang-default-resource-limits.a  /usr/lib/x86_64-linux-gnu/libcurl.so  /usr/lib/x86_64-linux-gnu/libjpeg.so  -luv  -lpthread  -lrt  /usr/lib/x86_64-linux-gnu/libX11.so  /usr/lib/x86_64-linux-gnu/libXext.so  -lwebp  /usr/lib/x86_64-linux-gnu/libicui18n.so  /usr/lib/x86_64-linux-gnu/libicuuc.so  -ldl  /usr/lib/x86_64-linux-gnu/libpng.so  /usr/lib/x86_64-linux-gnu/libz.so  libmbgl-vendor-nunicode.a  libmbgl-vendor-sqlite.a  -lgcc  -lgcc_s  -lc  -lgcc  -lgcc_s  -lstdc++  -lm  -lgcc_s  -lgcc  -lc  -lgcc_s  -lgcc";
@@ -3,7 +3,7 @@ use std::path::PathBuf;
 use std::time::Instant;
 
 use clap::Parser;
-use maplibre_native::{Image, ImageRendererOptions, MapDebugOptions};
+use maplibre_native::{Error, Image, ImageRendererOptions, MapDebugOptions};
 
 /// Command-line tool to render a map via [`mapLibre-native`](https://github.com/maplibre/maplibre-native)
 #[derive(Parser, Debug)]
@@ -44,14 +44,22 @@ struct Args {
     #[arg(short = 'z', long = "zoom", default_value_t = 0)]
     zoom: u8,
 
-    /// Longitude
+    /// Tile column, only used in tile mode
     #[arg(short = 'x', long = "x", default_value_t = 0)]
     x: u32,
 
-    /// Latitude
+    /// Tile row, only used in tile mode
     #[arg(short = 'y', long = "y", default_value_t = 0)]
     y: u32,
 
+    /// Longitude of the map center in degrees, ignored in tile mode
+    #[arg(long = "lon", default_value_t = 0.0, allow_negative_numbers = true)]
+    lon: f64,
+
+    /// Latitude of the map center in degrees, ignored in tile mode
+    #[arg(long = "lat", default_value_t = 0.0, allow_negative_numbers = true)]
+    lat: f64,
+
     /// Bearing
     #[arg(short = 'b', long = "bearing", default_value_t = 0.0)]
     bearing: f64,
@@ -126,46 +134,57 @@ impl From<DebugMode> for MapDebugOptions {
 }
 
 impl Args {
-    fn render(self) -> Image {
+    fn render(self) -> Result<Image, Error> {
         let mut map = ImageRendererOptions::new();
         map.with_api_key(self.apikey.unwrap_or_default());
-        map.with_cache_path(self.cache.to_string_lossy().to_string());
-        map.with_asset_root(self.asset_root.to_string_lossy().to_string());
+        map.with_cache_path(self.cache);
+        map.with_asset_root(self.asset_root);
         map.with_pixel_ratio(self.ratio);
         map.with_size(self.width, self.height);
 
         match self.mode {
             Mode::Static => {
-                let mut map = map.build_static_renderer();
+                let mut map = map.try_build_static_renderer()?;
                 if let Some(debug) = self.debug {
-                    map.set_debug_flags(debug.into());
+                    map.set_debug_flags(MapDebugOptions::from(debug));
                 }
-                map.set_style_url(&self.style);
+                map.set_style_url(&self.style)?;
                 map.set_camera(
-                    f64::from(self.x),
-                    f64::from(self.y),
+                    self.lon,
+                    self.lat,
                     f64::from(self.zoom),
                     self.bearing,
                     self.pitch,
-                );
-                map.render_static()
+                )?;
+                Ok(map.render_static()?)
             }
             Mode::Tile => {
-                if self.bearing != 0.0 {
-                    println!("Warning: nonzero bearing is ignored in tile-mode");
-                }
-                if self.pitch != 0.0 {
-                    println!("Warning: nonzero pitch is ignored in tile-mode");
+                if self.bearing != 0.0 || self.pitch != 0.0 {
+                    println!(
+                        "Warning: rotated or tilted tiles do not line up with their neighbors"
+                    );
                 }
-                let mut map = map.build_tile_renderer();
-                map.set_style_url(&self.style);
+                let mut map = map.try_build_tile_renderer()?;
+                map.set_style_url(&self.style)?;
                 if let Some(debug) = self.debug {
-                    map.set_debug_flags(debug.into());
+                    map.set_debug_flags(MapDebugOptions::from(debug));
                 }
-                map.render_tile(self.zoom, self.x, self.y)
+                Ok(map.render_tile_oriented(self.zoom, self.x, self.y, self.bearing, self.pitch)?)
             }
             Mode::Continuous => {
-                todo!("not yet implemented in the wrapper")
+                let mut map = map.try_build_continuous_renderer()?;
+                if let Some(debug) = self.debug {
+                    map.set_debug_flags(MapDebugOptions::from(debug));
+                }
+                map.set_style_url(&self.style)?;
+                map.set_camera(
+                    self.lon,
+                    self.lat,
+                    f64::from(self.zoom),
+                    self.bearing,
+                    self.pitch,
+                )?;
+                Ok(map.render_frame()?)
             }
         }
     }
@@ -177,7 +196,14 @@ fn main() {
     let output = args.output.clone();
 
     let before_initalisation = Instant::now();
-    let data = args.render();
+    let data = match args.render() {
+        Ok(data) => data,
+        Err(e) => {
+            // E.g. a missing graphics driver, which is explained by the error message
+            eprintln!("Failed to render the map: {e}");
+            std::process::exit(1);
+        }
+    };
     println!(
         "Rendering successfull in {elapsed:?}, writing result to {output:?}",
         elapsed = before_initalisation.elapsed()
@@ -199,7 +225,7 @@ mod tests {
             mode: Mode::Static,
             ..Args::parse()
         };
-        let data = args.render();
+        let data = args.render().unwrap();
         assert!(!data.as_slice().is_empty());
 
         let args = Args {
@@ -208,7 +234,7 @@ mod tests {
             mode: Mode::Tile,
             ..Args::parse()
         };
-        let data = args.render();
+        let data = args.render().unwrap();
         assert!(!data.as_slice().is_empty());
     }
 }
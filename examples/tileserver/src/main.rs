@@ -0,0 +1,98 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use clap::Parser;
+use maplibre_native::tile::TileId;
+use maplibre_native::{ImageFormat, ImageRendererOptions, TileServer};
+
+/// Serve raster tiles of a style rendered via [`mapLibre-native`](https://github.com/maplibre/maplibre-native),
+/// e.g. `http://localhost:3000/1/0/0.png`
+#[derive(Parser, Debug)]
+struct Args {
+    /// API key
+    #[arg(short = 't', long = "apikey", env = "MLN_API_KEY")]
+    apikey: Option<String>,
+
+    /// Map stylesheet
+    #[arg(
+        short = 's',
+        long = "style",
+        default_value = "https://demotiles.maplibre.org/style.json"
+    )]
+    style: String,
+
+    /// Address to listen on
+    #[arg(short = 'l', long = "listen", default_value = "127.0.0.1:3000")]
+    listen: SocketAddr,
+
+    /// Number of tiles rendered at the same time
+    #[arg(short = 'w', long = "workers", default_value_t = 4)]
+    workers: usize,
+
+    /// Image scale factor
+    #[arg(short = 'r', long = "ratio", default_value_t = 1.0)]
+    ratio: f32,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let mut options = ImageRendererOptions::new();
+    options.with_pixel_ratio(args.ratio).with_in_memory_cache();
+    if let Some(apikey) = args.apikey {
+        options.with_api_key(apikey);
+    }
+    let server = match TileServer::new(&options, args.style, args.workers) {
+        Ok(server) => Arc::new(server),
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    let app = Router::new()
+        .route("/{z}/{x}/{tile}", get(tile))
+        .with_state(server);
+    let listener = tokio::net::TcpListener::bind(args.listen).await.unwrap();
+    println!(
+        "Serving tiles at http://{}/{{z}}/{{x}}/{{y}}.png",
+        args.listen
+    );
+    axum::serve(listener, app).await.unwrap();
+}
+
+/// Render the tile `z/x/y.ext`, where the extension selects the image format.
+async fn tile(
+    State(server): State<Arc<TileServer>>,
+    Path((z, x, tile)): Path<(String, String, String)>,
+) -> Response {
+    let Some((y, format, content_type)) = tile
+        .strip_suffix(".png")
+        .map(|y| (y, ImageFormat::Png, "image/png"))
+        .or_else(|| {
+            let y = tile.strip_suffix(".jpg")?;
+            Some((y, ImageFormat::Jpeg { quality: 90 }, "image/jpeg"))
+        })
+        .or_else(|| {
+            let y = tile.strip_suffix(".webp")?;
+            Some((y, ImageFormat::Webp { quality: 90 }, "image/webp"))
+        })
+    else {
+        return (StatusCode::NOT_FOUND, "Unsupported tile format").into_response();
+    };
+    let id = match TileId::parse(&format!("{z}/{x}/{y}")) {
+        Ok(id) => id,
+        Err(e) => return (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+    };
+    // Rendering blocks until a renderer is free, so keep it off the async runtime
+    match tokio::task::spawn_blocking(move || server.tile(id, format)).await {
+        Ok(Ok(data)) => ([(header::CONTENT_TYPE, content_type)], data).into_response(),
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
@@ -3,11 +3,30 @@
 #include <mbgl/gfx/headless_frontend.hpp>
 #include <mbgl/map/map.hpp>
 #include <mbgl/map/map_options.hpp>
+#include <mbgl/renderer/renderer.hpp>
+#include <mbgl/storage/database_file_source.hpp>
+#include <mbgl/storage/resource.hpp>
+#include <mbgl/storage/response.hpp>
+#include <mbgl/style/layers/background_layer.hpp>
+#include <mbgl/style/layers/hillshade_layer.hpp>
+#include <mbgl/style/layers/line_layer.hpp>
+#include <mbgl/style/layers/symbol_layer.hpp>
+#include <mbgl/style/sources/geojson_source.hpp>
 #include <mbgl/style/style.hpp>
+#include <mbgl/util/chrono.hpp>
+#include <mbgl/util/constants.hpp>
 #include <mbgl/util/image.hpp>
 #include <mbgl/util/run_loop.hpp>
 #include <mbgl/util/tile_server_options.hpp>
+#include <algorithm>
+#include <chrono>
+#include <cmath>
+#include <array>
+#include <functional>
+#include <future>
+#include <map>
 #include <memory>
+#include <optional>
 #include <vector>
 #include <stdexcept>
 #include "rust/cxx.h"
@@ -17,26 +36,255 @@ namespace bridge {
 
 using namespace mbgl;
 
-class MapRenderer {
+// Shared structs are defined by the CXX-generated header
+struct CameraState;
+struct CancellationToken;
+struct CollisionBox;
+struct EmbeddedAssets;
+struct FeatureInfo;
+enum class LayerStatus : uint8_t;
+struct Padding;
+struct RegionStats;
+enum class RenderStatus : uint8_t;
+enum class ResolveStatus : uint8_t;
+struct ProviderKey;
+struct ResourceObserver;
+struct SchemeResolver;
+struct Rgba;
+struct ScreenRect;
+
+/// Run loop of the current thread, shared by all renderers on it, and destroyed with the last of them.
+/// MapLibre schedules work on the current run loop of the thread, and a new loop takes over as the current one,
+/// so with a loop per renderer the renders of all but the most recently created renderer would never finish.
+inline std::shared_ptr<mbgl::util::RunLoop> threadRunLoop() {
+    thread_local std::weak_ptr<mbgl::util::RunLoop> current;
+    auto loop = current.lock();
+    if (!loop) {
+        loop = std::make_shared<mbgl::util::RunLoop>();
+        current = loop;
+    }
+    return loop;
+}
+
+/// Completion of a still render, shared with MapLibre's callback so that it can outlive a cancelled render
+struct StillRender {
+    bool done = false;
+    std::exception_ptr error;
+    mbgl::PremultipliedImage image;
+};
+
+class MapRenderer;
+/// Stop reporting resource responses to a renderer, resolving its URL schemes and adding its provider keys,
+/// see MapRenderer::resourceObserver, MapRenderer::schemeResolvers and MapRenderer::providerKeys
+void unobserveResources(const MapRenderer& renderer);
+/// Prefer the `name:{locale}` property over `name` in the text of all symbol layers, see MapRenderer::labelLocale
+void localizeLabels(mbgl::style::Style& style, const std::string& locale);
+
+class MapRenderer : public mbgl::MapObserver {
 public:
-    explicit MapRenderer(std::unique_ptr<mbgl::HeadlessFrontend> frontendInstance,
-                         std::unique_ptr<mbgl::Map> mapInstance)
-        : frontend(std::move(frontendInstance)),
-          map(std::move(mapInstance)) {}
-    ~MapRenderer() {}
+    explicit MapRenderer(double labelDensityValue)
+        : runLoop(threadRunLoop()),
+          labelDensity(labelDensityValue) {}
+    ~MapRenderer() {
+        // Stop issuing requests, then wait for the cache writes that are already queued,
+        // so that the database is consistent once the last renderer using it is gone
+        map.reset();
+        frontend.reset();
+        waitForDatabase();
+        if (resourceObserver || !schemeResolvers.empty() || providerKeys) {
+            unobserveResources(*this);
+        }
+    }
+
+    void onDidFailLoadingMap(mbgl::MapLoadError, const std::string& description) override {
+        styleError = description;
+    }
+
+    void onDidFinishLoadingStyle() override {
+        styleError.reset();
+        if (labelDensity != 1.0) {
+            applyLabelDensity(map->getStyle(), labelDensity);
+        }
+        if (graticule) {
+            addGraticule(map->getStyle());
+        }
+        if (fadeDuration) {
+            applyFadeDuration();
+        }
+        if (terrainExaggeration) {
+            applyTerrainExaggeration(map->getStyle(), *terrainExaggeration);
+        }
+        if (transparentBackground) {
+            hideBackgroundLayers(map->getStyle());
+        }
+        if (!labelLocale.empty()) {
+            localizeLabels(map->getStyle(), labelLocale);
+        }
+    }
+
+    /// Hide all background layers, so that the framebuffer is cleared to transparent,
+    /// which MapLibre does whenever no background layer is drawn.
+    static void hideBackgroundLayers(mbgl::style::Style& style) {
+        for (auto* layer : style.getLayers()) {
+            if (layer->as<mbgl::style::BackgroundLayer>()) {
+                layer->setVisibility(mbgl::style::VisibilityType::None);
+            }
+        }
+    }
+
+    /// MapLibre Native does not support 3D terrain, so relief is only shown by hillshade layers.
+    /// Override their exaggeration, which is also re-applied whenever a new style is loaded.
+    static void applyTerrainExaggeration(mbgl::style::Style& style, double factor) {
+        for (auto* layer : style.getLayers()) {
+            if (auto* hillshade = layer->as<mbgl::style::HillshadeLayer>()) {
+                hillshade->setHillshadeExaggeration(static_cast<float>(factor));
+            }
+        }
+    }
 
 public:
-    mbgl::util::RunLoop runLoop;
+    // The run loop must be created before, and destroyed after the frontend and the map
+    std::shared_ptr<mbgl::util::RunLoop> runLoop;
+    /// Cache database shared by all renderers with the same cache path, kept open until all of them are gone
+    std::shared_ptr<mbgl::DatabaseFileSource> databaseSource;
+    /// Files served for asset:// URLs instead of the asset root, if set
+    std::shared_ptr<const rust::Box<EmbeddedAssets>> embeddedAssets;
+    /// Resource loader used by the map, unique to this renderer, while the file sources it wraps are shared
+    std::shared_ptr<mbgl::FileSource> resourceLoader;
+    /// Reports every response received by this renderer's resource loader
+    std::function<void(const mbgl::Resource&, const mbgl::Response&)> resourceObserver;
+    /// Serve requests for URLs of these lowercase schemes instead of the resource loader
+    std::map<std::string, std::shared_ptr<const rust::Box<SchemeResolver>>> schemeResolvers;
+    /// Query parameters added to the requests of this renderer's resource loader, if any
+    std::shared_ptr<const std::vector<ProviderKey>> providerKeys;
     // Due to CXX limitations, make all these public and access them from the regular functions below
     std::unique_ptr<mbgl::HeadlessFrontend> frontend;
     std::unique_ptr<mbgl::Map> map;
+    double labelDensity;
+    /// Description of the last style loading failure, reported by MapLibre via the observer
+    std::optional<std::string> styleError;
+    /// Overrides the exaggeration of all hillshade layers if set
+    std::optional<double> terrainExaggeration;
+    /// Hides the style's background layers whenever a new style is loaded
+    bool transparentBackground = false;
+    /// Language of the `name:*` property shown by labels whenever a new style is loaded, empty for the style's own
+    std::string labelLocale;
+    /// JSON definitions of sources added at runtime, used to serialize the style
+    std::map<std::string, std::string> addedSources;
+    /// Overrides the duration of the style's transitions and symbol fades if set
+    std::optional<mbgl::Duration> fadeDuration;
+    /// Generated GeoJSON lines and their color, added on top of the style when it is loaded
+    std::optional<std::pair<mbgl::GeoJSON, mbgl::Color>> graticule;
+    /// Viewport padding set by MapRenderer_setPadding, re-applied by every camera change
+    mbgl::EdgeInsets viewPadding;
+    /// Longest time a continuous render waits for the map to be fully loaded
+    mbgl::Duration frameTimeout = std::chrono::minutes(1);
+    /// Still render abandoned by a cancellation, which must complete before MapLibre accepts the next one
+    std::shared_ptr<StillRender> cancelledStill;
+    /// Premultiplied RGBA color painted outside of the world's horizontal extent
+    std::optional<std::array<uint8_t, 4>> letterboxColor;
+    /// Premultiplied RGBA color shown wherever the style leaves the framebuffer transparent
+    std::optional<std::array<uint8_t, 4>> clearColor;
+
+    /// Block until all operations queued on the cache database so far have completed.
+    /// Database operations run in order on a dedicated thread, so any operation acts as a barrier.
+    void waitForDatabase() {
+        if (!databaseSource) {
+            return;
+        }
+        std::promise<void> done;
+        databaseSource->listOfflineRegions([&done](auto) { done.set_value(); });
+        done.get_future().wait();
+    }
+
+    void applyFadeDuration() {
+        auto& style = map->getStyle();
+        auto transition = style.getTransitionOptions();
+        transition.duration = *fadeDuration;
+        if (*fadeDuration == mbgl::Duration::zero()) {
+            transition.delay = mbgl::Duration::zero();
+        }
+        style.setTransitionOptions(transition);
+    }
+
+    /// The headless frontend always clears the framebuffer to transparent, so composite the image
+    /// over the clear color instead, which gives the same result for premultiplied pixels.
+    void applyClearColor(mbgl::PremultipliedImage& image) const {
+        auto* pixel = image.data.get();
+        const auto* end = pixel + image.bytes();
+        for (; pixel != end; pixel += 4) {
+            const auto uncovered = 255 - pixel[3];
+            for (size_t channel = 0; channel < 4; channel++) {
+                pixel[channel] = static_cast<uint8_t>(pixel[channel] + ((*clearColor)[channel] * uncovered + 127) / 255);
+            }
+        }
+    }
+
+    /// Paint the areas left and right of the world, e.g. when a wide viewport shows the whole world.
+    /// Assumes the map is not rotated.
+    void applyLetterbox(mbgl::PremultipliedImage& image) const {
+        // The image may be rendered at another ratio than the frontend's, see MapRenderer_setRenderRatio,
+        // and the map options hold the resource pixel ratio, so derive the ratio from the logical map width
+        const auto pixelRatio = static_cast<double>(image.size.width) / map->getMapOptions().size().width;
+        const auto west = map->pixelForLatLng(mbgl::LatLng{0, -180}).x * pixelRatio;
+        const auto east = map->pixelForLatLng(mbgl::LatLng{0, 180}).x * pixelRatio;
+        const auto width = static_cast<double>(image.size.width);
+        const auto left = static_cast<uint32_t>(std::clamp(west, 0.0, width));
+        const auto right = static_cast<uint32_t>(std::clamp(east, 0.0, width));
+        for (uint32_t y = 0; y < image.size.height; y++) {
+            auto* row = image.data.get() + static_cast<size_t>(y) * image.stride();
+            for (uint32_t x = 0; x < image.size.width; x++) {
+                if (x < left || x >= right) {
+                    std::copy(letterboxColor->begin(), letterboxColor->end(), row + x * 4);
+                }
+            }
+        }
+    }
+
+private:
+    void addGraticule(mbgl::style::Style& style) const {
+        auto source = std::make_unique<mbgl::style::GeoJSONSource>("graticule");
+        source->setGeoJSON(graticule->first);
+        style.addSource(std::move(source));
+        auto layer = std::make_unique<mbgl::style::LineLayer>("graticule", "graticule");
+        layer->setLineColor(graticule->second);
+        layer->setLineWidth(1.0f);
+        style.addLayer(std::move(layer));
+    }
+
+    /// MapLibre has no notion of label density, so approximate it by scaling
+    /// the constant spacing and padding of all symbol layers. Expressions are left untouched.
+    static void applyLabelDensity(mbgl::style::Style& style, double density) {
+        const auto factor = static_cast<float>(1.0 / density);
+        for (auto* layer : style.getLayers()) {
+            auto* symbol = layer->as<mbgl::style::SymbolLayer>();
+            if (!symbol) {
+                continue;
+            }
+            auto spacing = symbol->getSymbolSpacing();
+            if (spacing.isUndefined()) {
+                spacing = mbgl::style::SymbolLayer::getDefaultSymbolSpacing();
+            }
+            if (spacing.isConstant()) {
+                symbol->setSymbolSpacing(spacing.asConstant() * factor);
+            }
+            auto padding = symbol->getTextPadding();
+            if (padding.isUndefined()) {
+                padding = mbgl::style::SymbolLayer::getDefaultTextPadding();
+            }
+            if (padding.isConstant()) {
+                symbol->setTextPadding(padding.asConstant() * factor);
+            }
+        }
+    }
 };
 
-inline std::unique_ptr<MapRenderer> MapRenderer_new(
+std::unique_ptr<MapRenderer> MapRenderer_new(
             mbgl::MapMode mapMode,
             uint32_t width,
             uint32_t height,
             float pixelRatio,
+            float resourcePixelRatio,
             const rust::Str cachePath,
             const rust::Str assetRoot,
             const rust::Str apiKey,
@@ -49,65 +297,208 @@ inline std::unique_ptr<MapRenderer> MapRenderer_new(
             const rust::Str glyphsTemplate,
             const rust::Str tileTemplate,
             const rust::Str defaultStyleUrl,
-            bool requiresApiKey
+            bool requiresApiKey,
+            const rust::Slice<const ProviderKey> providerKeys,
+            double labelDensity,
+            mbgl::ConstrainMode constrainMode,
+            const rust::Str localFontFamily,
+            const rust::Str eglPlatform,
+            rust::Box<EmbeddedAssets> embeddedAssets,
+            bool hasEmbeddedAssets,
+            uint32_t maxConcurrentRequests,
+            rust::String& error);
 
-) {
+void MapRenderer_setLetterboxColor(MapRenderer& self, Rgba color);
+void MapRenderer_setClearColor(MapRenderer& self, Rgba color);
+void MapRenderer_setGraticule(MapRenderer& self, const rust::Str geojson, Rgba color);
+rust::Vec<CollisionBox> MapRenderer_collisionBoxes(const MapRenderer& self);
+LayerStatus MapRenderer_layerVisibility(const MapRenderer& self, const rust::Str layerId, bool& visible);
+LayerStatus MapRenderer_setLayerVisibility(MapRenderer& self, const rust::Str layerId, bool visible);
+LayerStatus MapRenderer_addSource(MapRenderer& self, const rust::Str sourceId, const rust::Str json, rust::String& error);
+LayerStatus MapRenderer_addGeoJsonSource(
+    MapRenderer& self, const rust::Str sourceId, const rust::Str geojson, rust::String& error);
+LayerStatus MapRenderer_updateGeoJsonSource(
+    MapRenderer& self, const rust::Str sourceId, const rust::Str geojson, rust::String& error);
+LayerStatus MapRenderer_addLayer(MapRenderer& self, const rust::Str json, const rust::Str beforeLayerId, rust::String& error);
+LayerStatus MapRenderer_removeLayer(MapRenderer& self, const rust::Str layerId);
+LayerStatus MapRenderer_setLayerProperty(
+    MapRenderer& self, const rust::Str layerId, const rust::Str name, const rust::Str json, rust::String& error);
+rust::String MapRenderer_styleJson(const MapRenderer& self);
+bool MapRenderer_flush(MapRenderer& self, rust::String& error);
+bool MapRenderer_setStyleJson(MapRenderer& self, const rust::Str json, rust::String& error);
+rust::Vec<rust::String> validateStyle(const rust::Str json, rust::Vec<rust::String>& missingSources);
+LayerStatus MapRenderer_setFeatureState(MapRenderer& self,
+                                        const rust::Str sourceId,
+                                        const rust::Str sourceLayer,
+                                        const rust::Str featureId,
+                                        const rust::Str json,
+                                        rust::String& error);
+LayerStatus MapRenderer_setLineDash(MapRenderer& self, const rust::Str layerId, const rust::Slice<const double> dashes);
+void MapRenderer_addImage(MapRenderer& self,
+                          const rust::Str id,
+                          const rust::Slice<const uint8_t> rgba,
+                          uint32_t width,
+                          uint32_t height,
+                          float pixelRatio,
+                          bool sdf);
+bool MapRenderer_featureScreenBounds(const MapRenderer& self,
+                                     const rust::Str source,
+                                     const rust::Str sourceLayer,
+                                     const rust::Str featureId,
+                                     ScreenRect& bounds);
+rust::Vec<FeatureInfo> MapRenderer_queryRenderedFeatures(const MapRenderer& self, double x, double y);
+void MapRenderer_setCameraWithOptions(
+    MapRenderer& self, CameraState camera, Padding padding, bool anchored, double anchorX, double anchorY);
+void MapRenderer_setCameraToBounds(
+    MapRenderer& self, double west, double south, double east, double north, Padding padding);
+CameraState MapRenderer_getCamera(const MapRenderer& self);
+void MapRenderer_setPadding(MapRenderer& self, Padding padding);
+void MapRenderer_setResourceObserver(MapRenderer& self, rust::Box<ResourceObserver> observer);
+void MapRenderer_setSchemeResolver(MapRenderer& self, const rust::Str scheme, rust::Box<SchemeResolver> resolver);
+bool MapRenderer_hasSchemeResolver(const MapRenderer& self, const rust::Str scheme);
+RenderStatus MapRenderer_downloadRegion(MapRenderer& self,
+                                        double west,
+                                        double south,
+                                        double east,
+                                        double north,
+                                        double minZoom,
+                                        double maxZoom,
+                                        RegionStats& stats,
+                                        rust::String& error);
 
-    mbgl::Size size = {width, height};
+void MapRenderer_setRequestTimeout(MapRenderer& self, uint64_t millis);
+void MapRenderer_setFrameTimeout(MapRenderer& self, uint64_t millis);
+std::unique_ptr<std::string> MapRenderer_render(MapRenderer& self, RenderStatus& status, rust::String& error);
+std::unique_ptr<std::string> MapRenderer_renderCancellable(MapRenderer& self,
+                                                           const CancellationToken& token,
+                                                           RenderStatus& status,
+                                                           rust::String& error);
+std::unique_ptr<std::string> MapRenderer_renderContinuous(MapRenderer& self, RenderStatus& status, rust::String& error);
+RenderStatus MapRenderer_renderRawInto(MapRenderer& self, rust::Slice<uint8_t> pixels, rust::String& error);
+std::unique_ptr<std::string> MapRenderer_renderRaw(
+    MapRenderer& self, RenderStatus& status, rust::String& error, uint32_t& width, uint32_t& height);
+RenderStatus MapRenderer_waitUntilIdle(MapRenderer& self, rust::String& error);
 
-    auto frontend = std::make_unique<mbgl::HeadlessFrontend>(size, pixelRatio);
+void installLogObserver();
 
-    std::vector<mbgl::util::DefaultStyle> styles{
-         mbgl::util::DefaultStyle((std::string)defaultStyleUrl, "Basic", 1)};
+inline rust::Vec<rust::String> MapRenderer_layerIds(const MapRenderer& self) {
+    rust::Vec<rust::String> ids;
+    for (const auto* layer : self.map->getStyle().getLayers()) {
+        ids.push_back(rust::String(layer->getID()));
+    }
+    return ids;
+}
 
-    TileServerOptions options = TileServerOptions()
-        .withBaseURL((std::string)baseUrl)
-        .withUriSchemeAlias((std::string)uriSchemeAlias)
-        .withApiKeyParameterName((std::string)apiKeyParameterName)
-        .withSourceTemplate((std::string)sourceTemplate, "", {})
-        .withStyleTemplate((std::string)styleTemplate, "maps", {})
-        .withSpritesTemplate((std::string)spritesTemplate, "", {})
-        .withGlyphsTemplate((std::string)glyphsTemplate, "fonts", {})
-        .withTileTemplate((std::string)tileTemplate, "tiles", {})
-        .withDefaultStyles(styles)
-        .withDefaultStyle("Basic")
-        .setRequiresApiKey(requiresApiKey);
+inline void MapRenderer_setDebugFlags(MapRenderer& self, uint32_t debugFlags) {
+    // MapLibre's debug options are a bit set, so any combination of the enum values is valid
+    self.map->setDebug(static_cast<mbgl::MapDebugOptions>(debugFlags));
+}
 
-    ResourceOptions resourceOptions;
-    resourceOptions
-        .withCachePath((std::string)cachePath)
-        .withAssetPath((std::string)assetRoot)
-        .withApiKey((std::string)apiKey)
-        .withTileServerOptions(options);
+inline void MapRenderer_setSize(MapRenderer& self, uint32_t width, uint32_t height) {
+    // The frontend owns the framebuffer, scaled by its pixel ratio, while the map owns the viewport
+    const mbgl::Size size{width, height};
+    self.frontend->setSize(size);
+    self.map->setSize(size);
+}
 
-    MapOptions mapOptions;
-    mapOptions.withMapMode(mapMode).withSize(size).withPixelRatio(pixelRatio);
+inline void MapRenderer_setRenderRatio(MapRenderer& self, uint32_t width, uint32_t height, float ratio) {
+    // The framebuffer is the frontend size scaled by the frontend's pixel ratio, while the map keeps
+    // its logical size, so resizing only the frontend draws the same viewport at another ratio
+    const float scale = ratio / self.frontend->getPixelRatio();
+    const auto scaled = [scale](uint32_t value) {
+        return static_cast<uint32_t>(std::max(1L, std::lround(static_cast<float>(value) * scale)));
+    };
+    self.frontend->setSize(mbgl::Size{scaled(width), scaled(height)});
+}
 
-    auto map = std::make_unique<mbgl::Map>(*frontend, MapObserver::nullObserver(), mapOptions, resourceOptions);
+inline void MapRenderer_getZoomRange(const MapRenderer& self, double& minZoom, double& maxZoom) {
+    const auto bounds = self.map->getBounds();
+    minZoom = bounds.minZoom.value_or(mbgl::util::MIN_ZOOM);
+    maxZoom = bounds.maxZoom.value_or(mbgl::util::DEFAULT_MAX_ZOOM);
+}
+
+inline void MapRenderer_setZoomRange(MapRenderer& self, double minZoom, double maxZoom) {
+    // Unset bound options are left unchanged, and the camera is constrained right away
+    self.map->setBounds(mbgl::BoundOptions().withMinZoom(minZoom).withMaxZoom(maxZoom));
+}
+
+inline void MapRenderer_setLatLngBounds(MapRenderer& self, double west, double south, double east, double north) {
+    self.map->setBounds(
+        mbgl::BoundOptions().withLatLngBounds(mbgl::LatLngBounds::hull({south, west}, {north, east})));
+}
+
+inline void MapRenderer_project(const MapRenderer& self, double lon, double lat, double& x, double& y) {
+    const auto pixel = self.map->pixelForLatLng(mbgl::LatLng{lat, lon});
+    x = pixel.x;
+    y = pixel.y;
+}
+
+inline void MapRenderer_unproject(const MapRenderer& self, double x, double y, double& lon, double& lat) {
+    // Positions beyond the antimeridian are not wrapped, so that extents stay continuous
+    const auto latLng = self.map->latLngForPixel(mbgl::ScreenCoordinate{x, y});
+    lon = latLng.longitude();
+    lat = latLng.latitude();
+}
+
+inline bool MapRenderer_isFullyLoaded(const MapRenderer& self) {
+    return self.map->isFullyLoaded();
+}
+
+inline void MapRenderer_getSize(const MapRenderer& self, uint32_t& width, uint32_t& height) {
+    const auto size = self.frontend->getSize();
+    width = size.width;
+    height = size.height;
+}
+
+inline float MapRenderer_getPixelRatio(const MapRenderer& self) {
+    return self.frontend->getPixelRatio();
+}
+
+inline void MapRenderer_getImageSize(const MapRenderer& self, uint32_t& width, uint32_t& height) {
+    // Same rounding as the framebuffer allocated by the headless frontend
+    const auto size = self.frontend->getSize();
+    const auto ratio = self.frontend->getPixelRatio();
+    width = static_cast<uint32_t>(static_cast<float>(size.width) * ratio);
+    height = static_cast<uint32_t>(static_cast<float>(size.height) * ratio);
+}
+
+inline void MapRenderer_setFadeDuration(MapRenderer& self, uint64_t micros) {
+    self.fadeDuration = std::chrono::duration_cast<mbgl::Duration>(std::chrono::microseconds(micros));
+    self.applyFadeDuration();
+}
 
-    return std::make_unique<MapRenderer>(std::move(frontend), std::move(map));
+inline void MapRenderer_setLabelLocale(MapRenderer& self, const rust::Str locale) {
+    self.labelLocale = (std::string)locale;
 }
 
-inline std::unique_ptr<std::string> MapRenderer_render(MapRenderer& self) {
-    auto image = encodePNG(self.frontend->render(*self.map).image);
-    return std::make_unique<std::string>(image);
+inline void MapRenderer_setTerrainExaggeration(MapRenderer& self, double factor) {
+    self.terrainExaggeration = factor;
+    MapRenderer::applyTerrainExaggeration(self.map->getStyle(), factor);
 }
 
-inline void MapRenderer_setDebugFlags(MapRenderer& self, mbgl::MapDebugOptions debugFlags) {
-    self.map->setDebug(debugFlags);
+inline void MapRenderer_setTransparentBackground(MapRenderer& self, bool transparent) {
+    self.transparentBackground = transparent;
+    if (transparent) {
+        MapRenderer::hideBackgroundLayers(self.map->getStyle());
+    }
 }
 
 inline void MapRenderer_setCamera(
-    MapRenderer& self, double lat, double lon, double zoom, double bearing, double pitch) {
+    MapRenderer& self, double lon, double lat, double zoom, double bearing, double pitch) {
     // TODO: decide if this is the right approach,
     //       or if we want to cache camera options in the instance,
     //       and have several setters for each property.
     mbgl::CameraOptions cameraOptions;
-    cameraOptions.withCenter(mbgl::LatLng{lat, lon}).withZoom(zoom).withBearing(bearing).withPitch(pitch);
+    cameraOptions.withCenter(mbgl::LatLng{lat, lon})
+        .withPadding(self.viewPadding)
+        .withZoom(zoom)
+        .withBearing(bearing)
+        .withPitch(pitch);
     self.map->jumpTo(cameraOptions);
 }
 
 inline void MapRenderer_setStyleUrl(MapRenderer& self, const rust::Str styleUrl) {
+    self.styleError.reset();
     self.map->getStyle().loadURL((std::string)styleUrl);
 }
 
@@ -0,0 +1,64 @@
+use std::fmt;
+
+/// Graphics rendering API that MapLibre Native was compiled with.
+///
+/// The backend is chosen by the build script from the `metal`, `opengl`, and `vulkan` cargo features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GraphicsBackend {
+    /// [Apple's Metal API](https://developer.apple.com/metal/) (macOS/iOS only)
+    Metal,
+    /// [OpenGL API](https://www.opengl.org/)
+    OpenGL,
+    /// [Vulkan API](https://www.vulkan.org/)
+    Vulkan,
+}
+
+impl fmt::Display for GraphicsBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Metal => f.write_str("metal"),
+            Self::OpenGL => f.write_str("opengl"),
+            Self::Vulkan => f.write_str("vulkan"),
+        }
+    }
+}
+
+/// All graphics backends compiled into this build.
+///
+/// MapLibre Native currently supports only one backend per build, so this always contains just the
+/// [`active_backend`], but this may change in the future.
+#[must_use]
+pub fn compiled_backends() -> &'static [GraphicsBackend] {
+    match active_backend() {
+        GraphicsBackend::Metal => &[GraphicsBackend::Metal],
+        GraphicsBackend::OpenGL => &[GraphicsBackend::OpenGL],
+        GraphicsBackend::Vulkan => &[GraphicsBackend::Vulkan],
+    }
+}
+
+/// The graphics backend used for rendering, as selected by the build script.
+///
+/// The backend is fixed at build time by the `metal`, `opengl`, or `vulkan` cargo feature,
+/// or by the OpenGL fallback of the build script if `MLN_FALLBACK_OPENGL` is set,
+/// so this is mostly useful to log it, or to branch on backend-specific behavior.
+#[must_use]
+#[doc(alias = "current_backend")]
+pub fn active_backend() -> GraphicsBackend {
+    match env!("MLN_GRAPHICS_BACKEND") {
+        "metal" => GraphicsBackend::Metal,
+        "opengl" => GraphicsBackend::OpenGL,
+        "vulkan" => GraphicsBackend::Vulkan,
+        v => unreachable!("Unexpected graphics backend {v} set by the build script"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_is_compiled() {
+        assert!(compiled_backends().contains(&active_backend()));
+        assert_eq!(active_backend().to_string(), env!("MLN_GRAPHICS_BACKEND"));
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::{
+    BuildError, CameraValueError, GraphicsBackend, RenderError, SetStyleError, StyleError,
+    StyleUrlError,
+};
+
+/// Crate-wide error, which any error returned by this crate's methods converts into,
+/// so that callers can use `?` across them.
+#[derive(Debug)]
+pub enum Error {
+    /// The style URL has no scheme, or its scheme is not supported.
+    InvalidStyleUrl(StyleUrlError),
+    /// A path passed to MapLibre is not valid UTF-8.
+    NonUtf8Path(PathBuf),
+    /// The map could not be rendered.
+    Render(RenderError),
+    /// The graphics context could not be created or used.
+    Gpu(String),
+    /// No renderer could be built, because an option is invalid, see
+    /// [`ImageRendererOptions::validate`](crate::ImageRendererOptions::validate).
+    Build(BuildError),
+    /// No renderer could be built, because the graphics backend has no working driver or device,
+    /// see [`ImageRendererOptions::try_build_static_renderer`](crate::ImageRendererOptions::try_build_static_renderer).
+    BackendUnavailable {
+        backend: GraphicsBackend,
+        detail: String,
+    },
+    /// A resource needed by the style could not be fetched.
+    Network(String),
+    /// A file could not be read or written.
+    Io(std::io::Error),
+    /// The style, or a change to it, is invalid.
+    Style(StyleError),
+    /// A value passed to the renderer is out of its valid range.
+    InvalidValue(CameraValueError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidStyleUrl(e) => fmt::Display::fmt(e, f),
+            Self::NonUtf8Path(path) => write!(f, "Path {} is not valid UTF-8", path.display()),
+            Self::Render(e) => fmt::Display::fmt(e, f),
+            Self::Gpu(e) => write!(f, "Graphics context error: {e}"),
+            Self::Build(e) => fmt::Display::fmt(e, f),
+            Self::BackendUnavailable { backend, detail } => write!(
+                f,
+                "The {backend} graphics backend is unavailable ({detail}), \
+                 check that a {backend} driver is installed and a GPU or software renderer is accessible"
+            ),
+            Self::Network(e) => write!(f, "Unable to load resource: {e}"),
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::Style(e) => fmt::Display::fmt(e, f),
+            Self::InvalidValue(e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidStyleUrl(e) => Some(e),
+            Self::Render(e) => Some(e),
+            Self::Io(e) => Some(e),
+            Self::Style(e) => Some(e),
+            Self::Build(e) => Some(e),
+            Self::InvalidValue(e) => Some(e),
+            Self::NonUtf8Path(_)
+            | Self::Gpu(_)
+            | Self::BackendUnavailable { .. }
+            | Self::Network(_) => None,
+        }
+    }
+}
+
+impl From<StyleUrlError> for Error {
+    fn from(value: StyleUrlError) -> Self {
+        Self::InvalidStyleUrl(value)
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(value: BuildError) -> Self {
+        Self::Build(value)
+    }
+}
+
+impl From<RenderError> for Error {
+    fn from(value: RenderError) -> Self {
+        match value {
+            RenderError::GpuContext(e) => Self::Gpu(e),
+            RenderError::Network(e) => Self::Network(e),
+            RenderError::InvalidValue(e) => Self::InvalidValue(e),
+            e => Self::Render(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<StyleError> for Error {
+    fn from(value: StyleError) -> Self {
+        Self::Style(value)
+    }
+}
+
+impl From<CameraValueError> for Error {
+    fn from(value: CameraValueError) -> Self {
+        Self::InvalidValue(value)
+    }
+}
+
+impl From<SetStyleError> for Error {
+    fn from(value: SetStyleError) -> Self {
+        match value {
+            SetStyleError::Url(e) => Self::InvalidStyleUrl(e),
+            SetStyleError::File(path) => Self::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Style file {} was not found", path.display()),
+            )),
+            SetStyleError::Json(e) => Self::Style(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::ImageRendererOptions;
+
+    #[test]
+    fn conversions() {
+        assert!(matches!(
+            Error::from(RenderError::GpuContext("no device".to_string())),
+            Error::Gpu(e) if e == "no device"
+        ));
+        assert!(matches!(
+            Error::from(RenderError::Network("404".to_string())),
+            Error::Network(_)
+        ));
+        assert!(matches!(
+            Error::from(RenderError::Empty),
+            Error::Render(RenderError::Empty)
+        ));
+        let err = Error::BackendUnavailable {
+            backend: GraphicsBackend::Vulkan,
+            detail: "no device".to_string(),
+        };
+        assert!(err.to_string().starts_with(
+            "The vulkan graphics backend is unavailable (no device), check that a vulkan driver"
+        ));
+        let err = Error::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert!(std::error::Error::source(&err).is_some());
+        assert!(matches!(
+            Error::from(SetStyleError::Json(StyleError::Invalid("[]".to_string()))),
+            Error::Style(StyleError::Invalid(_))
+        ));
+        let err = Error::from(CameraValueError {
+            name: "zoom",
+            value: 30.0,
+            range: Some(0.0..=24.0),
+        });
+        assert_eq!(
+            err.to_string(),
+            "Invalid zoom 30, expected a value in [0, 24]"
+        );
+        assert!(std::error::Error::source(&err).is_some());
+        assert!(matches!(
+            Error::from(SetStyleError::File(PathBuf::from("style.json"))),
+            Error::Io(e) if e.kind() == std::io::ErrorKind::NotFound
+        ));
+    }
+
+    #[test]
+    fn question_mark() {
+        fn load(url: &str, path: &Path) -> Result<(), Error> {
+            let mut map = ImageRendererOptions::new().build_static_renderer();
+            map.set_style_url(url)?;
+            map.set_style_path(path)?;
+            Ok(())
+        }
+        let style = std::env::temp_dir().join("mln_question_mark.json");
+        std::fs::write(&style, "{}").unwrap();
+        assert!(load("maplibre://maps/basic", &style).is_ok());
+        assert!(matches!(
+            load("style.json", &style),
+            Err(Error::InvalidStyleUrl(_))
+        ));
+        assert!(matches!(
+            load("maplibre://maps/basic", Path::new("no-such-style.json")),
+            Err(Error::Io(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_path() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"style-\xff.json"));
+        let mut map = ImageRendererOptions::new().build_static_renderer();
+        let err = map.set_style_path(path).err().unwrap();
+        assert!(matches!(&err, Error::NonUtf8Path(p) if p == path));
+        assert!(err.to_string().starts_with("Path style-"));
+    }
+}
@@ -1,6 +1,21 @@
 // FIXME: Remove this before merging
 #![allow(unused)]
 
+mod backend;
+mod error;
+mod output_format;
+mod pool;
 mod renderer;
+mod service;
+pub mod tile;
+mod tile_server;
+mod version;
 
+pub use backend::{active_backend, compiled_backends, GraphicsBackend};
+pub use error::Error;
+pub use output_format::{supported_output_formats, ImageFormat, OutputFormat};
+pub use pool::{PooledRenderer, RendererPool};
 pub use renderer::*;
+pub use service::{RenderRequest, RenderService, RenderWorker};
+pub use tile_server::TileServer;
+pub use version::maplibre_version;
@@ -0,0 +1,72 @@
+use std::fmt;
+
+/// Image format a rendered map can be encoded into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputFormat {
+    Png,
+    Jpeg,
+    WebP,
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Png => f.write_str("png"),
+            Self::Jpeg => f.write_str("jpeg"),
+            Self::WebP => f.write_str("webp"),
+        }
+    }
+}
+
+/// Encoding of a rendered image, including the quality of lossy formats.
+///
+/// Quality ranges from `0` (smallest) to `100` (best), larger values are treated as `100`.
+/// All formats are encoded in Rust, so they are available in every build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    /// JPEG has no alpha channel, so transparent areas are rendered black.
+    Jpeg { quality: u8 },
+    /// WebP, preserving the alpha channel. The encoder only supports lossless compression,
+    /// so the quality is currently ignored.
+    Webp { quality: u8 },
+}
+
+impl ImageFormat {
+    /// The output format, without the encoding parameters.
+    #[must_use]
+    pub fn output_format(self) -> OutputFormat {
+        match self {
+            Self::Png => OutputFormat::Png,
+            Self::Jpeg { .. } => OutputFormat::Jpeg,
+            Self::Webp { .. } => OutputFormat::WebP,
+        }
+    }
+}
+
+/// Image output formats supported by this build.
+///
+/// All formats are encoded in Rust from the rendered pixels, so they do not depend
+/// on the libraries MapLibre Native was linked with.
+#[must_use]
+pub fn supported_output_formats() -> &'static [OutputFormat] {
+    &[OutputFormat::Png, OutputFormat::Jpeg, OutputFormat::WebP]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_formats_supported() {
+        assert_eq!(
+            supported_output_formats()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(","),
+            "png,jpeg,webp"
+        );
+    }
+}
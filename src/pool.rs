@@ -0,0 +1,148 @@
+//! Reuse of initialized renderers across requests on a single thread.
+//!
+//! Creating a renderer sets up a graphics context and a file source, which is expensive
+//! compared to rendering a small image. [`RendererPool`] builds a fixed number of renderers once,
+//! and lends them out for individual requests. As renderers are bound to the thread that created
+//! them, the pool is neither `Send` nor `Sync`. To share warm renderers between threads,
+//! use a [`RenderService`](crate::RenderService), which keeps one renderer per worker thread.
+
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+
+use crate::{ImageRenderer, ImageRendererOptions, MapDebugOptions, Static};
+
+/// Style loaded into returned renderers, so that no state of the previous request leaks
+/// into the next one. It has no sources, so loading it requires no network access.
+const EMPTY_STYLE: &str = r#"{"version": 8, "sources": {}, "layers": []}"#;
+
+/// A fixed set of pre-built static renderers, lent out by [`acquire`](Self::acquire).
+pub struct RendererPool {
+    renderers: RefCell<Vec<ImageRenderer<Static>>>,
+}
+
+impl RendererPool {
+    /// Build `size` static renderers from `options`.
+    #[must_use]
+    pub fn new(options: &ImageRendererOptions, size: usize) -> Self {
+        assert!(size > 0, "Renderer pool needs at least one renderer");
+        let renderers = (0..size)
+            .map(|_| options.clone().build_static_renderer())
+            .collect();
+        Self {
+            renderers: RefCell::new(renderers),
+        }
+    }
+
+    /// Borrow a renderer until the returned guard is dropped,
+    /// or `None` if all renderers are currently in use.
+    ///
+    /// Once the guard is dropped, the renderer is reset before it is returned to the pool:
+    /// its style is replaced with an empty one, and the camera and debug flags are reset.
+    /// Settings configured by [`ImageRendererOptions`] are kept, as well as overrides
+    /// that persist across style changes, e.g. [`ImageRenderer::set_terrain_exaggeration`].
+    #[must_use]
+    pub fn acquire(&self) -> Option<PooledRenderer<'_>> {
+        let renderer = self.renderers.borrow_mut().pop()?;
+        Some(PooledRenderer {
+            pool: self,
+            renderer: Some(renderer),
+        })
+    }
+
+    /// Number of renderers not currently in use.
+    #[must_use]
+    pub fn available(&self) -> usize {
+        self.renderers.borrow().len()
+    }
+}
+
+/// A renderer borrowed from a [`RendererPool`], returned to it on drop.
+pub struct PooledRenderer<'a> {
+    pool: &'a RendererPool,
+    renderer: Option<ImageRenderer<Static>>,
+}
+
+impl Deref for PooledRenderer<'_> {
+    type Target = ImageRenderer<Static>;
+
+    fn deref(&self) -> &Self::Target {
+        self.renderer
+            .as_ref()
+            .expect("Renderer is only removed on drop")
+    }
+}
+
+impl DerefMut for PooledRenderer<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.renderer
+            .as_mut()
+            .expect("Renderer is only removed on drop")
+    }
+}
+
+impl Drop for PooledRenderer<'_> {
+    fn drop(&mut self) {
+        let Some(mut renderer) = self.renderer.take() else {
+            return;
+        };
+        renderer
+            .set_style_json(EMPTY_STYLE)
+            .expect("Empty style must be valid");
+        renderer
+            .set_camera_unchecked(0.0, 0.0, 0.0, 0.0, 0.0)
+            .set_debug_flags(MapDebugOptions::NoDebug);
+        self.pool.renderers.borrow_mut().push(renderer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn render(renderer: &mut ImageRenderer<Static>) {
+        renderer
+            .set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        renderer.set_camera(10.0, 50.0, 3.0, 0.0, 0.0).unwrap();
+        assert!(!renderer.render_static().unwrap().as_slice().is_empty());
+    }
+
+    #[test]
+    fn reuse_renderers() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256);
+
+        let start = Instant::now();
+        let mut renderer = opts.clone().build_static_renderer();
+        let cold_init = start.elapsed();
+        render(&mut renderer);
+
+        let pool = RendererPool::new(&opts, 2);
+        let mut warm_init = Duration::ZERO;
+        for _ in 0..4 {
+            let start = Instant::now();
+            let mut renderer = pool.acquire().unwrap();
+            warm_init += start.elapsed();
+            render(&mut renderer);
+        }
+        assert!(
+            warm_init < cold_init,
+            "Acquiring 4 renderers took {warm_init:?}, building one took {cold_init:?}"
+        );
+    }
+
+    #[test]
+    fn exhausted_pool() {
+        let pool = RendererPool::new(&ImageRendererOptions::new(), 2);
+        let first = pool.acquire().unwrap();
+        let second = pool.acquire().unwrap();
+        assert!(pool.acquire().is_none());
+        assert_eq!(pool.available(), 0);
+        drop(first);
+        assert_eq!(pool.available(), 1);
+        drop(second);
+        assert_eq!(pool.available(), 2);
+    }
+}
@@ -0,0 +1,305 @@
+use std::fmt;
+
+use crate::renderer::bridge::ffi::CameraState;
+use crate::renderer::camera::check_center;
+use crate::renderer::retile::decode_rgba;
+use crate::renderer::{Continuous, Image, ImageRenderer, RenderError};
+
+/// Container of an animation encoded by [`encode_animation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AnimationFormat {
+    /// Animated PNG, lossless, and shown as a still image by viewers without APNG support.
+    #[default]
+    Apng,
+    /// Animated WebP with losslessly compressed frames, usually smaller than APNG.
+    WebP,
+}
+
+/// Errors returned by [`encode_animation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnimationError {
+    /// No frames were given.
+    NoFrames,
+    /// A frame could not be decoded as an 8-bit RGBA PNG.
+    Decode(String),
+    /// A frame has a different size than the first one.
+    SizeMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    /// The encoder failed.
+    Encode(String),
+}
+
+impl fmt::Display for AnimationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoFrames => f.write_str("An animation needs at least one frame"),
+            Self::Decode(e) => write!(f, "Unable to decode frame: {e}"),
+            Self::SizeMismatch { expected, actual } => write!(
+                f,
+                "Frame of {}x{} pixels does not match the animation size of {}x{} pixels",
+                actual.0, actual.1, expected.0, expected.1
+            ),
+            Self::Encode(e) => write!(f, "Unable to encode animation: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for AnimationError {}
+
+impl ImageRenderer<Continuous> {
+    /// Render a frame for each of `cameras`, in order, e.g. the steps of a camera flythrough
+    /// to be passed to [`encode_animation`].
+    ///
+    /// Tiles stay loaded between frames, so consecutive cameras close to each other are cheap.
+    /// The camera is left at the last entry.
+    ///
+    /// # Errors
+    /// Returns the error of the first frame that failed, see [`render_frame`](Self::render_frame),
+    /// or [`RenderError::InvalidValue`] for a camera with an invalid center.
+    pub fn record_frames(&mut self, cameras: &[CameraState]) -> Result<Vec<Image>, RenderError> {
+        cameras
+            .iter()
+            .map(|camera| {
+                check_center(camera.lon, camera.lat).map_err(RenderError::InvalidValue)?;
+                self.set_camera_unchecked(
+                    camera.lon,
+                    camera.lat,
+                    camera.zoom,
+                    camera.bearing,
+                    camera.pitch,
+                );
+                self.render_frame()
+            })
+            .collect()
+    }
+}
+
+/// Encode PNG `frames` into an animation playing at `fps` frames per second, looping forever.
+///
+/// Encoding is done in Rust, so both formats are available regardless of the image libraries
+/// MapLibre Native was linked with. All frames must have the same size.
+///
+/// # Errors
+/// Returns an error if there are no frames, if a frame is not a PNG,
+/// or if the frames differ in size.
+pub fn encode_animation(
+    frames: &[Image],
+    fps: u8,
+    format: AnimationFormat,
+) -> Result<Vec<u8>, AnimationError> {
+    assert!(fps > 0, "An animation needs at least one frame per second");
+    let mut decoded = Vec::with_capacity(frames.len());
+    for frame in frames {
+        let (pixels, width, height) =
+            decode_rgba(frame.as_slice()).map_err(|e| AnimationError::Decode(e.to_string()))?;
+        if let Some((_, first_width, first_height)) = decoded.first() {
+            if (width, height) != (*first_width, *first_height) {
+                return Err(AnimationError::SizeMismatch {
+                    expected: (*first_width, *first_height),
+                    actual: (width, height),
+                });
+            }
+        }
+        decoded.push((pixels, width, height));
+    }
+    let Some(&(_, width, height)) = decoded.first() else {
+        return Err(AnimationError::NoFrames);
+    };
+    let frames = decoded.into_iter().map(|(pixels, _, _)| pixels);
+    match format {
+        AnimationFormat::Apng => encode_apng(frames, width, height, fps),
+        AnimationFormat::WebP => encode_webp(frames, width, height, fps),
+    }
+    .map_err(AnimationError::Encode)
+}
+
+fn encode_apng(
+    frames: impl ExactSizeIterator<Item = Vec<u8>>,
+    width: u32,
+    height: u32,
+    fps: u8,
+) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let count = u32::try_from(frames.len()).map_err(|_| "Too many frames".to_string())?;
+    encoder
+        .set_animated(count, 0)
+        .and_then(|()| encoder.set_frame_delay(1, u16::from(fps)))
+        .map_err(|e| e.to_string())?;
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    for pixels in frames {
+        writer
+            .write_image_data(&pixels)
+            .map_err(|e| e.to_string())?;
+    }
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+/// Build an extended WebP file from individually encoded lossless frames.
+///
+/// See <https://developers.google.com/speed/webp/docs/riff_container> for the chunk layout.
+fn encode_webp(
+    frames: impl Iterator<Item = Vec<u8>>,
+    width: u32,
+    height: u32,
+    fps: u8,
+) -> Result<Vec<u8>, String> {
+    const ANIMATION: u8 = 0x02;
+    const ALPHA: u8 = 0x10;
+    // Frames replace the canvas instead of being blended over the previous one
+    const NO_BLEND: u8 = 0x02;
+
+    let duration = (1000 + u32::from(fps) / 2) / u32::from(fps);
+    let mut body = b"WEBP".to_vec();
+
+    let mut header = vec![ALPHA | ANIMATION, 0, 0, 0];
+    header.extend_from_slice(&le_u24(width - 1));
+    header.extend_from_slice(&le_u24(height - 1));
+    push_chunk(&mut body, *b"VP8X", &header);
+    // Transparent background, looping forever
+    push_chunk(&mut body, *b"ANIM", &[0, 0, 0, 0, 0, 0]);
+
+    for pixels in frames {
+        let mut frame = Vec::new();
+        image_webp::WebPEncoder::new(&mut frame)
+            .encode(&pixels, width, height, image_webp::ColorType::Rgba8)
+            .map_err(|e| e.to_string())?;
+        // Offsets are zero, as every frame covers the whole canvas
+        let mut anmf = vec![0; 6];
+        anmf.extend_from_slice(&le_u24(width - 1));
+        anmf.extend_from_slice(&le_u24(height - 1));
+        anmf.extend_from_slice(&le_u24(duration));
+        anmf.push(NO_BLEND);
+        anmf.extend_from_slice(bitstream_chunks(&frame)?);
+        push_chunk(&mut body, *b"ANMF", &anmf);
+    }
+
+    let size = u32::try_from(body.len()).map_err(|_| "Animation exceeds 4 GiB".to_string())?;
+    let mut data = b"RIFF".to_vec();
+    data.extend_from_slice(&size.to_le_bytes());
+    data.extend_from_slice(&body);
+    Ok(data)
+}
+
+/// The image chunks of a simple WebP file, without the RIFF header.
+fn bitstream_chunks(file: &[u8]) -> Result<&[u8], String> {
+    match file.get(12..16) {
+        Some(b"VP8L" | b"VP8 ") => Ok(&file[12..]),
+        _ => Err("Unexpected WebP frame layout".to_string()),
+    }
+}
+
+fn push_chunk(data: &mut Vec<u8>, fourcc: [u8; 4], payload: &[u8]) {
+    // Chunk payloads are limited to 24-bit sizes in practice, as WebP images are at most 16384px
+    #[allow(clippy::cast_possible_truncation)]
+    let size = payload.len() as u32;
+    data.extend_from_slice(&fourcc);
+    data.extend_from_slice(&size.to_le_bytes());
+    data.extend_from_slice(payload);
+    if payload.len() % 2 == 1 {
+        data.push(0);
+    }
+}
+
+fn le_u24(value: u32) -> [u8; 3] {
+    let [a, b, c, _] = value.to_le_bytes();
+    [a, b, c]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::image_renderer::ImageData;
+    use crate::renderer::retile::encode_rgba;
+    use crate::renderer::ImageRendererOptions;
+
+    fn frame(width: u32, height: u32, color: [u8; 4]) -> Image {
+        let pixels = color.repeat((width * height) as usize);
+        Image(ImageData::Vec(encode_rgba(&pixels, width, height).unwrap()))
+    }
+
+    #[test]
+    fn apng() {
+        let frames = [frame(3, 2, [255, 0, 0, 255]), frame(3, 2, [0, 0, 255, 128])];
+        let data = encode_animation(&frames, 10, AnimationFormat::Apng).unwrap();
+
+        let mut reader = png::Decoder::new(data.as_slice()).read_info().unwrap();
+        let control = reader.info().animation_control().unwrap();
+        assert_eq!((control.num_frames, control.num_plays), (2, 0));
+        let mut buf = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut buf).unwrap();
+        assert_eq!(&buf[..4], [255, 0, 0, 255]);
+        let delay = reader.info().frame_control().unwrap();
+        assert_eq!((delay.delay_num, delay.delay_den), (1, 10));
+        reader.next_frame(&mut buf).unwrap();
+        assert_eq!(&buf[..4], [0, 0, 255, 128]);
+    }
+
+    #[test]
+    fn webp() {
+        let frames = [frame(3, 2, [255, 0, 0, 255]), frame(3, 2, [0, 0, 255, 128])];
+        let data = encode_animation(&frames, 25, AnimationFormat::WebP).unwrap();
+        assert_eq!(&data[..4], b"RIFF");
+        assert_eq!(
+            data[4..8],
+            u32::try_from(data.len() - 8).unwrap().to_le_bytes()
+        );
+        assert_eq!(&data[12..16], b"VP8X");
+        assert_eq!(&data[30..34], b"ANIM");
+        assert_eq!(data.windows(4).filter(|w| w == b"ANMF").count(), 2);
+        let anmf = data.windows(4).position(|w| w == b"ANMF").unwrap();
+        // 40 ms per frame
+        assert_eq!(data[anmf + 20..anmf + 23], [40, 0, 0]);
+        assert_eq!(Image(ImageData::Vec(data)).dimensions(), Some((3, 2)));
+    }
+
+    #[test]
+    fn invalid_frames() {
+        assert_eq!(
+            encode_animation(&[], 10, AnimationFormat::Apng),
+            Err(AnimationError::NoFrames)
+        );
+        let frames = [frame(3, 2, [0; 4]), frame(2, 3, [0; 4])];
+        assert_eq!(
+            encode_animation(&frames, 10, AnimationFormat::WebP),
+            Err(AnimationError::SizeMismatch {
+                expected: (3, 2),
+                actual: (2, 3)
+            })
+        );
+        let frames = [Image(ImageData::Vec(b"not a png".to_vec()))];
+        assert!(matches!(
+            encode_animation(&frames, 10, AnimationFormat::Apng),
+            Err(AnimationError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn record_frames() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(128, 128);
+        let mut map = opts.build_continuous_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        let cameras: Vec<_> = (0..3)
+            .map(|i| CameraState {
+                lon: f64::from(i) * 30.0,
+                zoom: 1.0,
+                ..CameraState::default()
+            })
+            .collect();
+        let frames = map.record_frames(&cameras).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert!((map.get_camera().lon - 60.0).abs() < 1e-9);
+
+        let data = encode_animation(&frames, 5, AnimationFormat::Apng).unwrap();
+        let reader = png::Decoder::new(data.as_slice()).read_info().unwrap();
+        assert_eq!(reader.info().animation_control().unwrap().num_frames, 3);
+        assert_eq!(reader.info().size(), (128, 128));
+    }
+}
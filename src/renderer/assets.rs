@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Files registered with [`ImageRendererOptions::with_embedded_assets`](crate::ImageRendererOptions::with_embedded_assets),
+/// shared with the C++ asset file source, which may read them from its own thread.
+pub struct EmbeddedAssets(pub(crate) Arc<HashMap<String, Vec<u8>>>);
+
+/// Called by the C++ asset file source for every `asset://` request of a renderer with embedded assets.
+///
+/// `path` is the part of the URL after the scheme. Sets `found` to false if there is no such asset.
+pub fn embedded_asset<'a>(assets: &'a EmbeddedAssets, path: &str, found: &mut bool) -> &'a [u8] {
+    let data = assets.0.get(path.trim_start_matches('/'));
+    *found = data.is_some();
+    data.map_or(&[], Vec::as_slice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::image_renderer::tests::decode;
+    use crate::renderer::{ImageRendererOptions, RenderError};
+
+    #[test]
+    fn lookup() {
+        let assets = EmbeddedAssets(Arc::new(HashMap::from([(
+            "fonts/a.pbf".to_string(),
+            vec![1, 2, 3],
+        )])));
+        let mut found = false;
+        assert_eq!(
+            embedded_asset(&assets, "fonts/a.pbf", &mut found),
+            [1, 2, 3]
+        );
+        assert!(found);
+        assert_eq!(
+            embedded_asset(&assets, "/fonts/a.pbf", &mut found),
+            [1, 2, 3]
+        );
+        assert!(found);
+        assert!(embedded_asset(&assets, "fonts/b.pbf", &mut found).is_empty());
+        assert!(!found);
+    }
+
+    #[test]
+    fn render_embedded_style() {
+        let style = r##"{
+            "version": 8,
+            "sources": {},
+            "layers": [{"id": "bg", "type": "background", "paint": {"background-color": "#f0f"}}]
+        }"##;
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64).with_embedded_assets(HashMap::from([(
+            "styles/magenta.json".to_string(),
+            style.as_bytes().to_vec(),
+        )]));
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("asset://styles/magenta.json").unwrap();
+        let pixels = decode(&map.render_static().unwrap());
+        assert_eq!(pixels[32 * 64 + 32], [255, 0, 255, 255]);
+
+        map.set_style_url("asset://styles/missing.json").unwrap();
+        assert!(matches!(
+            map.render_static(),
+            Err(RenderError::StyleLoad(_))
+        ));
+    }
+}
@@ -1,10 +1,1545 @@
 #include "map_renderer.h"
 #include "maplibre_native/src/renderer/bridge.rs.h"
 
+#include <mapbox/geometry/for_each_point.hpp>
+#include <mbgl/gfx/backend_scope.hpp>
+#include <mbgl/renderer/query.hpp>
+#include <mbgl/storage/file_source.hpp>
+#include <mbgl/storage/file_source_manager.hpp>
+#include <mbgl/storage/offline.hpp>
+#include <mbgl/storage/online_file_source.hpp>
+#include <mbgl/style/conversion/filter.hpp>
+#include <mbgl/style/conversion/geojson.hpp>
+#include <mbgl/style/conversion/json.hpp>
+#include <mbgl/style/conversion/layer.hpp>
+#include <mbgl/style/conversion/source.hpp>
+#include <mbgl/style/conversion_impl.hpp>
+#include <mbgl/style/image.hpp>
+#include <mbgl/style/sources/geojson_source.hpp>
+#include <mbgl/util/enum.hpp>
+#include <mbgl/util/event.hpp>
+#include <mbgl/util/exception.hpp>
+#include <mbgl/util/logging.hpp>
+#include <mbgl/util/premultiply.hpp>
+#include <mbgl/util/string.hpp>
+#include <mbgl/util/timer.hpp>
+#include <rapidjson/error/en.h>
+#include <rapidjson/stringbuffer.h>
+#include <rapidjson/writer.h>
+
+#include <atomic>
+#include <cctype>
+#include <cstdlib>
+#include <map>
+#include <mutex>
+#include <set>
+#include <string_view>
+#include <thread>
+
+
 //
-// This file is unused at the moment, but we may need it later so keeping it for now
+// Bridge functions that need the complete definitions of the CXX shared types
 //
 
 namespace mln {
-namespace bridge {} // namespace bridge
+namespace bridge {
+
+namespace {
+
+/// Renderers of the current thread with a resource observer.
+/// Responses are delivered on the thread that requested them, which is the thread of the renderer.
+thread_local std::vector<MapRenderer*> observedRenderers;
+
+/// Renderers of the current thread with a URL scheme resolver, only consulted by their own resource loader.
+/// Requests are issued on the thread of the renderer, like responses are delivered.
+thread_local std::vector<MapRenderer*> resolvingRenderers;
+
+/// Renderers of the current thread with provider keys.
+/// Keys are added by the resource loader of the renderer rather than the shared network file source,
+/// and each renderer has its own loader, so they are never sent with the requests of other renderers.
+thread_local std::vector<MapRenderer*> keyedRenderers;
+
+/// Property of ObservedFileSource holding the request timeout in milliseconds, zero for none
+constexpr const char* requestTimeoutProperty = "mln-request-timeout-ms";
+
+/// Prefix of the error message of requests cancelled by the timeout, see isTimeout
+constexpr std::string_view timeoutMessage = "Request timed out after ";
+
+/// Request that fails with a timeout error unless the inner request responds in time
+class TimedRequest : public AsyncRequest {
+public:
+    std::unique_ptr<AsyncRequest> inner;
+    util::Timer timer;
+};
+
+/// Request answered from memory, whose response is dropped if it is cancelled before delivery
+class DeferredRequest : public AsyncRequest {
+public:
+    ~DeferredRequest() override { *cancelled = true; }
+    const std::shared_ptr<bool> cancelled = std::make_shared<bool>(false);
+};
+
+/// Deliver a response that is already known.
+/// Callers expect responses to arrive after the request returns, like other file sources.
+std::unique_ptr<AsyncRequest> respondLater(Response response, FileSource::Callback callback) {
+    auto request = std::make_unique<DeferredRequest>();
+    util::RunLoop::Get()->invoke(
+        [cancelled = request->cancelled, callback = std::move(callback), response = std::move(response)] {
+            if (!*cancelled) {
+                callback(response);
+            }
+        });
+    return request;
+}
+
+/// Lowercase scheme of a URL, or an empty string if it has none
+std::string urlScheme(const std::string& url) {
+    const auto end = url.find("://");
+    if (end == std::string::npos) {
+        return {};
+    }
+    std::string scheme = url.substr(0, end);
+    std::transform(scheme.begin(), scheme.end(), scheme.begin(), [](unsigned char c) { return std::tolower(c); });
+    return scheme;
+}
+
+/// Resource loader of a single renderer, see MapRenderer_new, that reports every response to its observer,
+/// serves URLs of the schemes it resolves, adds its provider keys to requests,
+/// and fails requests that take longer than its timeout
+class ObservedFileSource : public FileSource {
+public:
+    explicit ObservedFileSource(std::unique_ptr<FileSource> inner_)
+        : inner(std::move(inner_)) {}
+
+    std::unique_ptr<AsyncRequest> request(const Resource& resource, Callback callback) override {
+        std::optional<Response> resolved;
+        if (const auto resolver = schemeResolver(resource.url)) {
+            rust::String redirect;
+            rust::Vec<uint8_t> data;
+            resolved.emplace();
+            switch (resolve_scheme(**resolver, resource.url, redirect, data)) {
+                case ResolveStatus::Redirect: {
+                    Resource redirected = resource;
+                    redirected.url = (std::string)redirect;
+                    return request(redirected, std::move(callback));
+                }
+                case ResolveStatus::Data:
+                    resolved->data = std::make_shared<const std::string>(reinterpret_cast<const char*>(data.data()),
+                                                                         data.size());
+                    break;
+                default:
+                    resolved->error = std::make_unique<Response::Error>(
+                        Response::Error::Reason::NotFound, "No resource resolved for " + resource.url);
+                    break;
+            }
+        }
+        auto deliver = [this, resource, callback = std::move(callback)](const Response& response) {
+            // Copy the list, as an observer may build or drop other renderers
+            const auto renderers = observedRenderers;
+            for (auto* renderer : renderers) {
+                if (renderer->resourceLoader.get() == this) {
+                    renderer->resourceObserver(resource, response);
+                }
+            }
+            callback(response);
+        };
+        if (resolved) {
+            return respondLater(std::move(*resolved), std::move(deliver));
+        }
+        // Observers are told about the requested URL, which does not contain the keys
+        const Resource keyed = withProviderKeys(resource);
+        if (timeout == Milliseconds::zero()) {
+            return inner->request(keyed, std::move(deliver));
+        }
+        auto request = std::make_unique<TimedRequest>();
+        auto* timed = request.get();
+        // Any response, including a cached one that is revalidated later, stops the timer
+        timed->inner = inner->request(keyed, [timed, deliver](const Response& response) {
+            timed->timer.stop();
+            deliver(response);
+        });
+        timed->timer.start(timeout, Duration::zero(), [timed, deliver, millis = timeout.count()] {
+            timed->inner.reset();
+            Response response;
+            response.error = std::make_unique<Response::Error>(
+                Response::Error::Reason::Connection, std::string(timeoutMessage) + util::toString(millis) + "ms");
+            deliver(response);
+        });
+        return request;
+    }
+
+    void forward(const Resource& resource, const Response& response, std::function<void()> callback) override {
+        inner->forward(resource, response, std::move(callback));
+    }
+    bool supportsCacheOnlyRequests() const override { return inner->supportsCacheOnlyRequests(); }
+    bool canRequest(const Resource& resource) const override { return inner->canRequest(resource); }
+    void pause() override { inner->pause(); }
+    void resume() override { inner->resume(); }
+    void setProperty(const std::string& key, const mapbox::base::Value& value) override {
+        if (key == requestTimeoutProperty) {
+            timeout = Milliseconds(value.getUint() ? *value.getUint() : 0);
+        } else {
+            inner->setProperty(key, value);
+        }
+    }
+    mapbox::base::Value getProperty(const std::string& key) const override {
+        if (key == requestTimeoutProperty) {
+            return static_cast<uint64_t>(timeout.count());
+        }
+        return inner->getProperty(key);
+    }
+    void setResourceOptions(ResourceOptions options) override { inner->setResourceOptions(std::move(options)); }
+    ResourceOptions getResourceOptions() override { return inner->getResourceOptions(); }
+    void setClientOptions(ClientOptions options) override { inner->setClientOptions(std::move(options)); }
+    ClientOptions getClientOptions() override { return inner->getClientOptions(); }
+
+private:
+    /// Resolver registered for the scheme of `url` by a renderer using this loader, if any
+    std::shared_ptr<const rust::Box<SchemeResolver>> schemeResolver(const std::string& url) const {
+        if (resolvingRenderers.empty()) {
+            return nullptr;
+        }
+        const auto scheme = urlScheme(url);
+        for (auto* renderer : resolvingRenderers) {
+            if (renderer->resourceLoader.get() != this) {
+                continue;
+            }
+            auto it = renderer->schemeResolvers.find(scheme);
+            if (it != renderer->schemeResolvers.end()) {
+                return it->second;
+            }
+        }
+        return nullptr;
+    }
+
+    /// Copy of `resource` with the provider keys of the renderer using this loader added to its URL
+    Resource withProviderKeys(const Resource& resource) const {
+        Resource keyed = resource;
+        for (auto* renderer : keyedRenderers) {
+            if (renderer->resourceLoader.get() == this) {
+                const auto& keys = *renderer->providerKeys;
+                const rust::Slice<const ProviderKey> slice(keys.data(), keys.size());
+                keyed.url = (std::string)apply_provider_keys(slice, resource.url);
+                break;
+            }
+        }
+        return keyed;
+    }
+
+    const std::unique_ptr<FileSource> inner;
+    Milliseconds timeout = Milliseconds::zero();
+};
+
+/// Whether a failure was caused by a request cancelled by the timeout
+bool isTimeout(const std::string& message) {
+    return message.find(timeoutMessage) != std::string::npos;
+}
+
+/// Wrap the resource loaders created for maps, so that their responses can be observed.
+/// This must happen before the first map is created, as loaders are only created once per renderer.
+/// The platform context only makes the loader of each renderer unique, see MapRenderer_new,
+/// so it is dropped before creating the wrapped loader, whose file sources stay shared between renderers.
+void wrapResourceLoaders() {
+    static std::once_flag wrapped;
+    std::call_once(wrapped, [] {
+        auto* manager = FileSourceManager::get();
+        auto factory = manager->unRegisterFileSourceFactory(FileSourceType::ResourceLoader);
+        manager->registerFileSourceFactory(
+            FileSourceType::ResourceLoader,
+            [factory = std::move(factory)](const ResourceOptions& resourceOptions, const ClientOptions& clientOptions)
+                -> std::unique_ptr<FileSource> {
+                ResourceOptions sharedOptions = resourceOptions.clone();
+                sharedOptions.withPlatformContext(nullptr);
+                return std::make_unique<ObservedFileSource>(factory(sharedOptions, clientOptions));
+            });
+    });
+}
+
+/// Embedded assets of renderers by their unique asset path, see MapRenderer_new.
+/// Only weak references are kept, the renderers and their asset file sources own the assets.
+std::mutex embeddedAssetsMutex;
+std::map<std::string, std::weak_ptr<const rust::Box<EmbeddedAssets>>> embeddedAssetsByPath;
+
+constexpr std::string_view assetScheme = "asset://";
+
+/// Asset file source that serves asset:// URLs from memory instead of a directory
+class EmbeddedFileSource : public FileSource {
+public:
+    explicit EmbeddedFileSource(std::shared_ptr<const rust::Box<EmbeddedAssets>> assets_,
+                                const ResourceOptions& resourceOptions_,
+                                const ClientOptions& clientOptions_)
+        : assets(std::move(assets_)),
+          resourceOptions(resourceOptions_.clone()),
+          clientOptions(clientOptions_.clone()) {}
+
+    std::unique_ptr<AsyncRequest> request(const Resource& resource, Callback callback) override {
+        const auto path = resource.url.substr(assetScheme.size());
+        bool found = false;
+        const auto data = embedded_asset(**assets, path, found);
+        Response response;
+        if (found) {
+            response.data = std::make_shared<const std::string>(reinterpret_cast<const char*>(data.data()),
+                                                                data.size());
+        } else {
+            response.error = std::make_unique<Response::Error>(Response::Error::Reason::NotFound,
+                                                               "Embedded asset " + path + " not found");
+        }
+        return respondLater(std::move(response), std::move(callback));
+    }
+
+    bool canRequest(const Resource& resource) const override { return resource.url.starts_with(assetScheme); }
+    void setResourceOptions(ResourceOptions options) override { resourceOptions = std::move(options); }
+    ResourceOptions getResourceOptions() override { return resourceOptions.clone(); }
+    void setClientOptions(ClientOptions options) override { clientOptions = std::move(options); }
+    ClientOptions getClientOptions() override { return clientOptions.clone(); }
+
+private:
+    const std::shared_ptr<const rust::Box<EmbeddedAssets>> assets;
+    ResourceOptions resourceOptions;
+    ClientOptions clientOptions;
+};
+
+/// Serve the assets of renderers with embedded assets from memory, identified by their asset path.
+/// Asset file sources of other renderers are created by the default factory.
+void wrapAssetFileSources() {
+    static std::once_flag wrapped;
+    std::call_once(wrapped, [] {
+        auto* manager = FileSourceManager::get();
+        auto factory = manager->unRegisterFileSourceFactory(FileSourceType::Asset);
+        manager->registerFileSourceFactory(
+            FileSourceType::Asset,
+            [factory = std::move(factory)](const ResourceOptions& resourceOptions, const ClientOptions& clientOptions)
+                -> std::unique_ptr<FileSource> {
+                std::shared_ptr<const rust::Box<EmbeddedAssets>> assets;
+                {
+                    std::lock_guard lock(embeddedAssetsMutex);
+                    auto it = embeddedAssetsByPath.find(resourceOptions.assetPath());
+                    if (it != embeddedAssetsByPath.end()) {
+                        assets = it->second.lock();
+                    }
+                }
+                if (assets) {
+                    return std::make_unique<EmbeddedFileSource>(std::move(assets), resourceOptions, clientOptions);
+                }
+                return factory(resourceOptions, clientOptions);
+            });
+    });
+}
+
+/// Register embedded assets under a new asset path, so that file sources are not shared with other renderers
+std::string registerEmbeddedAssets(const std::shared_ptr<const rust::Box<EmbeddedAssets>>& assets) {
+    static std::atomic<uint64_t> nextId{0};
+    std::string path = "embedded-assets:" + std::to_string(nextId++);
+    std::lock_guard lock(embeddedAssetsMutex);
+    std::erase_if(embeddedAssetsByPath, [](const auto& entry) { return entry.second.expired(); });
+    embeddedAssetsByPath.emplace(path, assets);
+    return path;
+}
+
+ResourceKind toResourceKind(Resource::Kind kind) {
+    switch (kind) {
+        case Resource::Kind::Style:
+            return ResourceKind::Style;
+        case Resource::Kind::Source:
+            return ResourceKind::Source;
+        case Resource::Kind::Tile:
+            return ResourceKind::Tile;
+        case Resource::Kind::Glyphs:
+            return ResourceKind::Glyphs;
+        case Resource::Kind::SpriteImage:
+            return ResourceKind::SpriteImage;
+        case Resource::Kind::SpriteJSON:
+            return ResourceKind::SpriteJson;
+        case Resource::Kind::Image:
+            return ResourceKind::Image;
+        default:
+            return ResourceKind::Unknown;
+    }
+}
+
+} // namespace
+
+void unobserveResources(const MapRenderer& renderer) {
+    std::erase(observedRenderers, &renderer);
+    std::erase(resolvingRenderers, &renderer);
+    std::erase(keyedRenderers, &renderer);
+}
+
+std::unique_ptr<MapRenderer> MapRenderer_new(
+            mbgl::MapMode mapMode,
+            uint32_t width,
+            uint32_t height,
+            float pixelRatio,
+            float resourcePixelRatio,
+            const rust::Str cachePath,
+            const rust::Str assetRoot,
+            const rust::Str apiKey,
+            const rust::Str baseUrl,
+            const rust::Str uriSchemeAlias,
+            const rust::Str apiKeyParameterName,
+            const rust::Str sourceTemplate,
+            const rust::Str styleTemplate,
+            const rust::Str spritesTemplate,
+            const rust::Str glyphsTemplate,
+            const rust::Str tileTemplate,
+            const rust::Str defaultStyleUrl,
+            bool requiresApiKey,
+            const rust::Slice<const ProviderKey> providerKeys,
+            double labelDensity,
+            mbgl::ConstrainMode constrainMode,
+            const rust::Str localFontFamily,
+            const rust::Str eglPlatform,
+            rust::Box<EmbeddedAssets> embeddedAssets,
+            bool hasEmbeddedAssets,
+            uint32_t maxConcurrentRequests,
+            rust::String& error
+
+) {
+
+    mbgl::Size size = {width, height};
+
+    if (!eglPlatform.empty()) {
+        // Mesa picks the EGL platform of the default display from the environment.
+        // Set it only once, as setenv races with any thread reading the environment,
+        // and the default display is shared by all renderers anyway.
+        static std::once_flag eglPlatformSet;
+        std::call_once(eglPlatformSet, [&] { setenv("EGL_PLATFORM", ((std::string)eglPlatform).c_str(), 1); });
+    }
+
+    std::optional<std::string> fontFamily;
+    if (!localFontFamily.empty()) {
+        fontFamily = (std::string)localFontFamily;
+    }
+
+    auto renderer = std::make_unique<MapRenderer>(labelDensity);
+    // Exceptions must not reach the CXX boundary, which would terminate the process,
+    // so report backends without a working driver or device as an error instead
+    try {
+        renderer->frontend = std::make_unique<mbgl::HeadlessFrontend>(
+            size, pixelRatio, gfx::HeadlessBackend::SwapBehaviour::NoFlush, gfx::ContextMode::Unique, fontFamily);
+        // The context is otherwise created by the first render, activate it now to report failures early
+        gfx::BackendScope scope{*renderer->frontend->getBackend()};
+    } catch (const std::exception& e) {
+        error = rust::String(e.what());
+        return nullptr;
+    } catch (...) {
+        error = rust::String("Unknown error while initializing the graphics backend");
+        return nullptr;
+    }
+    // Keep the placement results of each render to report them via MapRenderer_collisionBoxes
+    renderer->frontend->getRenderer()->collectPlacedSymbolData(true);
+
+    std::vector<mbgl::util::DefaultStyle> styles{
+         mbgl::util::DefaultStyle((std::string)defaultStyleUrl, "Basic", 1)};
+
+    TileServerOptions options = TileServerOptions()
+        .withBaseURL((std::string)baseUrl)
+        .withUriSchemeAlias((std::string)uriSchemeAlias)
+        .withApiKeyParameterName((std::string)apiKeyParameterName)
+        .withSourceTemplate((std::string)sourceTemplate, "", {})
+        .withStyleTemplate((std::string)styleTemplate, "maps", {})
+        .withSpritesTemplate((std::string)spritesTemplate, "", {})
+        .withGlyphsTemplate((std::string)glyphsTemplate, "fonts", {})
+        .withTileTemplate((std::string)tileTemplate, "tiles", {})
+        .withDefaultStyles(styles)
+        .withDefaultStyle("Basic")
+        .setRequiresApiKey(requiresApiKey);
+
+    std::string assetPath = (std::string)assetRoot;
+    if (hasEmbeddedAssets) {
+        renderer->embeddedAssets = std::make_shared<const rust::Box<EmbeddedAssets>>(std::move(embeddedAssets));
+        assetPath = registerEmbeddedAssets(renderer->embeddedAssets);
+    }
+
+    ResourceOptions resourceOptions;
+    resourceOptions
+        .withCachePath((std::string)cachePath)
+        .withAssetPath(assetPath)
+        .withApiKey((std::string)apiKey)
+        .withTileServerOptions(options);
+
+    // The frontend renders at the image pixel ratio, while the map uses the resource pixel ratio
+    // to pick sprites, glyphs, and raster tiles
+    MapOptions mapOptions;
+    mapOptions.withMapMode(mapMode)
+        .withSize(size)
+        .withPixelRatio(resourcePixelRatio)
+        .withConstrainMode(constrainMode);
+
+    // File sources are shared by all renderers with the same resource options.
+    // The platform context is unused by headless maps, and makes the resource loader unique to this renderer,
+    // so that its observer, scheme resolvers, provider keys, and timeout do not apply to other renderers.
+    ResourceOptions loaderOptions = resourceOptions.clone();
+    loaderOptions.withPlatformContext(renderer.get());
+
+    wrapResourceLoaders();
+    wrapAssetFileSources();
+    renderer->map = std::make_unique<mbgl::Map>(*renderer->frontend, *renderer, mapOptions, loaderOptions);
+    renderer->resourceLoader = FileSourceManager::get()->getFileSource(
+        FileSourceType::ResourceLoader, loaderOptions, ClientOptions());
+    renderer->databaseSource = std::static_pointer_cast<DatabaseFileSource>(
+        FileSourceManager::get()->getFileSource(FileSourceType::Database, resourceOptions, ClientOptions()));
+
+    if (maxConcurrentRequests > 0) {
+        // The limit is global, see set_max_concurrent_requests, as the network file source is shared
+        auto fileSource = FileSourceManager::get()->getFileSource(
+            FileSourceType::Network, resourceOptions, ClientOptions());
+        fileSource->setProperty(MAX_CONCURRENT_REQUESTS_KEY, static_cast<uint64_t>(maxConcurrentRequests));
+    }
+
+    if (!providerKeys.empty()) {
+        // Copy the keys, as the slice passed from Rust does not outlive this call
+        renderer->providerKeys = std::make_shared<const std::vector<ProviderKey>>(providerKeys.begin(),
+                                                                                  providerKeys.end());
+        keyedRenderers.push_back(renderer.get());
+    }
+
+    return renderer;
+}
+
+/// Thrown by a still render once its cancellation token is set
+struct RenderCancelled : std::runtime_error {
+    RenderCancelled()
+        : std::runtime_error("Render was cancelled") {}
+};
+
+/// Thrown by a continuous render if the map is not fully loaded within MapRenderer::frameTimeout
+struct FrameTimedOut : std::runtime_error {
+    explicit FrameTimedOut(mbgl::Duration timeout)
+        : std::runtime_error("Map was not fully loaded after " +
+                             util::toString(std::chrono::duration_cast<Milliseconds>(timeout).count()) + "ms") {}
+};
+
+/// Thrown by a still render once MapLibre reports its failure, e.g. because the style or a resource
+/// could not be loaded. Any other exception of a render is raised by the graphics backend.
+struct StillRenderFailed : std::runtime_error {
+    StillRenderFailed(RenderStatus status_, const std::string& message)
+        : std::runtime_error(message),
+          status(status_) {}
+    const RenderStatus status;
+};
+
+/// Classify the error reported by MapLibre to the callback of a still render
+static StillRenderFailed stillRenderFailed(const std::exception_ptr& error) {
+    try {
+        std::rethrow_exception(error);
+    } catch (const util::StyleParseException& e) {
+        return {RenderStatus::StyleLoad, e.what()};
+    } catch (const util::StyleLoadException& e) {
+        return {RenderStatus::StyleLoad, e.what()};
+    } catch (const std::exception& e) {
+        return {isTimeout(e.what()) ? RenderStatus::Timeout : RenderStatus::Network, e.what()};
+    } catch (...) {
+        return {RenderStatus::Network, "Unknown error while loading the map"};
+    }
+}
+
+/// Run a render and classify any failure. Returns an empty optional if the render failed.
+template <typename Render>
+static std::optional<PremultipliedImage> renderImage(
+    MapRenderer& self, RenderStatus& status, rust::String& error, Render&& render) {
+    const auto fail = [&](RenderStatus value, const std::string& message) {
+        status = value;
+        error = rust::String(message);
+        return std::optional<PremultipliedImage>();
+    };
+    PremultipliedImage image;
+    try {
+        image = render();
+    } catch (const RenderCancelled& e) {
+        return fail(RenderStatus::Cancelled, e.what());
+    } catch (const FrameTimedOut& e) {
+        return fail(RenderStatus::Timeout, e.what());
+    } catch (const StillRenderFailed& e) {
+        // The style may have failed to load before the resource reported here, e.g. if it could not be fetched
+        if (self.styleError) {
+            return fail(isTimeout(*self.styleError) ? RenderStatus::Timeout : RenderStatus::StyleLoad,
+                        *self.styleError);
+        }
+        return fail(e.status, e.what());
+    } catch (const std::exception& e) {
+        return fail(RenderStatus::GpuContext, e.what());
+    } catch (...) {
+        return fail(RenderStatus::GpuContext, "Unknown error while rendering");
+    }
+    if (self.styleError) {
+        return fail(isTimeout(*self.styleError) ? RenderStatus::Timeout : RenderStatus::StyleLoad,
+                    *self.styleError);
+    }
+    if (!image.valid()) {
+        return fail(RenderStatus::Empty, "Render produced no image");
+    }
+    if (self.clearColor) {
+        self.applyClearColor(image);
+    }
+    if (self.letterboxColor) {
+        self.applyLetterbox(image);
+    }
+    status = RenderStatus::Ok;
+    return image;
+}
+
+static PremultipliedImage renderStill(MapRenderer& self, const CancellationToken* token = nullptr) {
+    const auto cancelled = [&] { return token && cancellation_requested(*token); };
+    // Wake up the run loop regularly to check the token, even while no response arrives
+    util::Timer poll;
+    if (token) {
+        poll.start(Milliseconds(10), Milliseconds(10), [] {});
+    }
+    // MapLibre only accepts one still render at a time, so let a cancelled one finish first
+    while (self.cancelledStill && !self.cancelledStill->done) {
+        if (cancelled()) {
+            throw RenderCancelled();
+        }
+        self.runLoop->runOnce();
+    }
+    self.cancelledStill.reset();
+
+    // Unlike HeadlessFrontend::render, tell the failures reported by MapLibre apart from
+    // the exceptions thrown by the graphics backend while the run loop renders the frame
+    auto still = std::make_shared<StillRender>();
+    self.map->renderStill([still, frontend = self.frontend.get()](const std::exception_ptr& error) {
+        // The frame is read right away, as the framebuffer is reused by the next render
+        if (error) {
+            still->error = error;
+        } else {
+            still->image = frontend->readStillImage();
+        }
+        still->done = true;
+    });
+    while (!still->done) {
+        if (cancelled()) {
+            self.cancelledStill = still;
+            throw RenderCancelled();
+        }
+        self.runLoop->runOnce();
+    }
+    if (still->error) {
+        throw stillRenderFailed(still->error);
+    }
+    return std::move(still->image);
+}
+
+static void renderUntilLoaded(MapRenderer& self) {
+    // Unlike still renders, continuous frames are produced whenever the run loop processes an update,
+    // so keep pumping it until all resources for the current camera are loaded and rendered.
+    // Failed requests are not reported to continuous maps, so give up after the frame timeout.
+    const auto deadline = mbgl::Clock::now() + self.frameTimeout;
+    do {
+        self.runLoop->runOnce();
+        self.frontend->renderFrame();
+        if (!self.map->isFullyLoaded()) {
+            if (mbgl::Clock::now() >= deadline) {
+                throw FrameTimedOut(self.frameTimeout);
+            }
+            std::this_thread::sleep_for(std::chrono::milliseconds(1));
+        }
+    } while (!self.map->isFullyLoaded() && !self.styleError);
+}
+
+static PremultipliedImage renderContinuous(MapRenderer& self) {
+    renderUntilLoaded(self);
+    return self.frontend->readStillImage();
+}
+
+static std::unique_ptr<std::string> encode(std::optional<PremultipliedImage> image) {
+    return std::make_unique<std::string>(image ? encodePNG(*image) : std::string());
+}
+
+std::unique_ptr<std::string> MapRenderer_render(MapRenderer& self, RenderStatus& status, rust::String& error) {
+    return encode(renderImage(self, status, error, [&] { return renderStill(self); }));
+}
+
+std::unique_ptr<std::string> MapRenderer_renderCancellable(MapRenderer& self,
+                                                           const CancellationToken& token,
+                                                           RenderStatus& status,
+                                                           rust::String& error) {
+    return encode(renderImage(self, status, error, [&] { return renderStill(self, &token); }));
+}
+
+std::unique_ptr<std::string> MapRenderer_renderContinuous(MapRenderer& self, RenderStatus& status, rust::String& error) {
+    return encode(renderImage(self, status, error, [&] { return renderContinuous(self); }));
+}
+
+RenderStatus MapRenderer_renderRawInto(MapRenderer& self, rust::Slice<uint8_t> pixels, rust::String& error) {
+    RenderStatus status = RenderStatus::Ok;
+    auto image = renderImage(self, status, error, [&] { return renderStill(self); });
+    if (!image) {
+        return status;
+    }
+    if (image->bytes() != pixels.size()) {
+        error = rust::String("Rendered image does not match the buffer size");
+        return RenderStatus::Empty;
+    }
+    // Unpremultiply straight into the caller's buffer, like util::unpremultiply does in place
+    const auto* src = image->data.get();
+    auto* dst = pixels.data();
+    for (size_t i = 0; i < pixels.size(); i += 4) {
+        const uint8_t alpha = src[i + 3];
+        for (size_t channel = 0; channel < 3; channel++) {
+            dst[i + channel] = alpha ? static_cast<uint8_t>(255 * src[i + channel] / alpha) : 0;
+        }
+        dst[i + 3] = alpha;
+    }
+    return status;
+}
+
+RenderStatus MapRenderer_waitUntilIdle(MapRenderer& self, rust::String& error) {
+    RenderStatus status = RenderStatus::Ok;
+    // Classify failures like a render, without reading back the frame
+    renderImage(self, status, error, [&] {
+        renderUntilLoaded(self);
+        return PremultipliedImage({1, 1});
+    });
+    return status;
+}
+
+std::unique_ptr<std::string> MapRenderer_renderRaw(
+    MapRenderer& self, RenderStatus& status, rust::String& error, uint32_t& width, uint32_t& height) {
+    auto image = renderImage(self, status, error, [&] {
+        return self.map->getMapOptions().mapMode() == MapMode::Continuous ? renderContinuous(self) : renderStill(self);
+    });
+    if (!image) {
+        return std::make_unique<std::string>();
+    }
+    width = image->size.width;
+    height = image->size.height;
+    auto pixels = util::unpremultiply(std::move(*image));
+    return std::make_unique<std::string>(reinterpret_cast<const char*>(pixels.data.get()), pixels.bytes());
+}
+
+void MapRenderer_setRequestTimeout(MapRenderer& self, uint64_t millis) {
+    // The resource loader is unique to this renderer, so other renderers keep their own timeout
+    self.resourceLoader->setProperty(requestTimeoutProperty, millis);
+}
+
+void MapRenderer_setFrameTimeout(MapRenderer& self, uint64_t millis) {
+    self.frameTimeout = Milliseconds(millis);
+}
+
+void MapRenderer_setLetterboxColor(MapRenderer& self, Rgba color) {
+    const auto premultiply = [&](uint8_t channel) {
+        return static_cast<uint8_t>(channel * color.a / 255);
+    };
+    self.letterboxColor = {premultiply(color.r), premultiply(color.g), premultiply(color.b), color.a};
+}
+
+void MapRenderer_setClearColor(MapRenderer& self, Rgba color) {
+    const auto premultiply = [&](uint8_t channel) {
+        return static_cast<uint8_t>(channel * color.a / 255);
+    };
+    self.clearColor = {premultiply(color.r), premultiply(color.g), premultiply(color.b), color.a};
+}
+
+rust::Vec<CollisionBox> MapRenderer_collisionBoxes(const MapRenderer& self) {
+    rust::Vec<CollisionBox> result;
+    const auto* renderer = self.frontend->getRenderer();
+    if (!renderer) {
+        return result;
+    }
+    const auto push = [&](const PlacedSymbolData& symbol,
+                          const mapbox::geometry::box<float>& box,
+                          CollisionBoxKind kind,
+                          bool placed) {
+        result.push_back(CollisionBox{
+            rust::String(symbol.key.data(), symbol.key.size()),
+            rust::String(symbol.layer),
+            kind,
+            placed,
+            symbol.intersectsTileBorder,
+            box.min.x,
+            box.min.y,
+            box.max.x,
+            box.max.y,
+        });
+    };
+    for (const auto& symbol : renderer->getPlacedSymbolsData()) {
+        if (symbol.textCollisionBox) {
+            push(symbol, *symbol.textCollisionBox, CollisionBoxKind::Text, symbol.textPlaced);
+        }
+        if (symbol.iconCollisionBox) {
+            push(symbol, *symbol.iconCollisionBox, CollisionBoxKind::Icon, symbol.iconPlaced);
+        }
+    }
+    return result;
+}
+
+LayerStatus MapRenderer_layerVisibility(const MapRenderer& self, const rust::Str layerId, bool& visible) {
+    const auto* layer = self.map->getStyle().getLayer((std::string)layerId);
+    if (!layer) {
+        return LayerStatus::NotFound;
+    }
+    visible = layer->getVisibility() == style::VisibilityType::Visible;
+    return LayerStatus::Ok;
+}
+
+LayerStatus MapRenderer_setLayerVisibility(MapRenderer& self, const rust::Str layerId, bool visible) {
+    auto* layer = self.map->getStyle().getLayer((std::string)layerId);
+    if (!layer) {
+        return LayerStatus::NotFound;
+    }
+    layer->setVisibility(visible ? style::VisibilityType::Visible : style::VisibilityType::None);
+    return LayerStatus::Ok;
+}
+
+LayerStatus MapRenderer_addSource(MapRenderer& self, const rust::Str sourceId, const rust::Str json, rust::String& error) {
+    auto& style = self.map->getStyle();
+    const auto id = (std::string)sourceId;
+    if (style.getSource(id)) {
+        error = rust::String("Source \"" + id + "\" already exists");
+        return LayerStatus::Invalid;
+    }
+    style::conversion::Error conversionError;
+    auto source = style::conversion::convertJSON<std::unique_ptr<style::Source>>(
+        (std::string)json, conversionError, id);
+    if (!source) {
+        error = rust::String(conversionError.message);
+        return LayerStatus::Invalid;
+    }
+    style.addSource(std::move(*source));
+    self.addedSources[id] = (std::string)json;
+    return LayerStatus::Ok;
+}
+
+LayerStatus MapRenderer_addGeoJsonSource(
+    MapRenderer& self, const rust::Str sourceId, const rust::Str geojson, rust::String& error) {
+    auto& style = self.map->getStyle();
+    const auto id = (std::string)sourceId;
+    if (style.getSource(id)) {
+        error = rust::String("Source \"" + id + "\" already exists");
+        return LayerStatus::Invalid;
+    }
+    style::conversion::Error conversionError;
+    auto data = style::conversion::convertJSON<GeoJSON>((std::string)geojson, conversionError);
+    if (!data) {
+        error = rust::String(conversionError.message);
+        return LayerStatus::Invalid;
+    }
+    auto source = std::make_unique<style::GeoJSONSource>(id);
+    source->setGeoJSON(*data);
+    style.addSource(std::move(source));
+    self.addedSources[id] = R"({"type":"geojson","data":)" + (std::string)geojson + "}";
+    return LayerStatus::Ok;
+}
+
+LayerStatus MapRenderer_updateGeoJsonSource(
+    MapRenderer& self, const rust::Str sourceId, const rust::Str geojson, rust::String& error) {
+    const auto id = (std::string)sourceId;
+    auto* source = self.map->getStyle().getSource(id);
+    if (!source) {
+        return LayerStatus::NotFound;
+    }
+    auto* geojsonSource = source->as<style::GeoJSONSource>();
+    if (!geojsonSource) {
+        return LayerStatus::WrongType;
+    }
+    style::conversion::Error conversionError;
+    auto data = style::conversion::convertJSON<GeoJSON>((std::string)geojson, conversionError);
+    if (!data) {
+        error = rust::String(conversionError.message);
+        return LayerStatus::Invalid;
+    }
+    geojsonSource->setGeoJSON(*data);
+
+    // Keep the definition used by MapRenderer_styleJson in sync, preserving the other source options
+    if (auto added = self.addedSources.find(id); added != self.addedSources.end()) {
+        JSDocument definition;
+        definition.Parse<0>(added->second.c_str());
+        JSDocument value;
+        value.Parse<0>(((std::string)geojson).c_str());
+        if (!definition.HasParseError() && definition.IsObject() && !value.HasParseError()) {
+            JSValue dataValue(value, definition.GetAllocator());
+            definition.RemoveMember("data");
+            definition.AddMember("data", dataValue, definition.GetAllocator());
+            rapidjson::StringBuffer buffer;
+            rapidjson::Writer<rapidjson::StringBuffer> writer(buffer);
+            definition.Accept(writer);
+            added->second = std::string(buffer.GetString(), buffer.GetSize());
+        }
+    }
+    return LayerStatus::Ok;
+}
+
+LayerStatus MapRenderer_addLayer(MapRenderer& self, const rust::Str json, const rust::Str beforeLayerId, rust::String& error) {
+    auto& style = self.map->getStyle();
+    std::optional<std::string> before;
+    if (!beforeLayerId.empty()) {
+        before = (std::string)beforeLayerId;
+        if (!style.getLayer(*before)) {
+            return LayerStatus::NotFound;
+        }
+    }
+    style::conversion::Error conversionError;
+    auto layer = style::conversion::convertJSON<std::unique_ptr<style::Layer>>((std::string)json, conversionError);
+    if (!layer) {
+        error = rust::String(conversionError.message);
+        return LayerStatus::Invalid;
+    }
+    if (style.getLayer((*layer)->getID())) {
+        error = rust::String("Layer \"" + (*layer)->getID() + "\" already exists");
+        return LayerStatus::Invalid;
+    }
+    style.addLayer(std::move(*layer), before);
+    return LayerStatus::Ok;
+}
+
+LayerStatus MapRenderer_removeLayer(MapRenderer& self, const rust::Str layerId) {
+    return self.map->getStyle().removeLayer((std::string)layerId) ? LayerStatus::Ok : LayerStatus::NotFound;
+}
+
+LayerStatus MapRenderer_setLayerProperty(
+    MapRenderer& self, const rust::Str layerId, const rust::Str name, const rust::Str json, rust::String& error) {
+    auto* layer = self.map->getStyle().getLayer((std::string)layerId);
+    if (!layer) {
+        return LayerStatus::NotFound;
+    }
+    JSDocument document;
+    document.Parse<0>(((std::string)json).c_str());
+    if (document.HasParseError()) {
+        error = rust::String("Invalid JSON value: " + (std::string)json);
+        return LayerStatus::Invalid;
+    }
+    const JSValue* value = &document;
+    if (auto conversionError = layer->setProperty((std::string)name, style::conversion::Convertible(value))) {
+        error = rust::String(conversionError->message);
+        return LayerStatus::Invalid;
+    }
+    return LayerStatus::Ok;
+}
+
+LayerStatus MapRenderer_setFeatureState(MapRenderer& self,
+                                        const rust::Str sourceId,
+                                        const rust::Str sourceLayer,
+                                        const rust::Str featureId,
+                                        const rust::Str json,
+                                        rust::String& error) {
+    const auto id = (std::string)sourceId;
+    auto* renderer = self.frontend->getRenderer();
+    if (!renderer || !self.map->getStyle().getSource(id)) {
+        return LayerStatus::NotFound;
+    }
+    JSDocument document;
+    document.Parse<0>(((std::string)json).c_str());
+    const JSValue* value = &document;
+    const auto state = document.HasParseError() ? std::nullopt
+                                                : style::conversion::toValue(style::conversion::Convertible(value));
+    if (!state || !state->getObject()) {
+        error = rust::String("Feature state must be a JSON object: " + (std::string)json);
+        return LayerStatus::Invalid;
+    }
+    std::optional<std::string> layer;
+    if (!sourceLayer.empty()) {
+        layer = (std::string)sourceLayer;
+    }
+    // The state is kept by the renderer, and applied to the feature by the next render
+    renderer->setFeatureState(id, layer, (std::string)featureId, *state->getObject());
+    return LayerStatus::Ok;
+}
+
+using JSWriter = rapidjson::Writer<rapidjson::StringBuffer>;
+
+static void writeValue(JSWriter& writer, const Value& value) {
+    if (const auto* v = value.getBool()) {
+        writer.Bool(*v);
+    } else if (const auto* v = value.getUint()) {
+        writer.Uint64(*v);
+    } else if (const auto* v = value.getInt()) {
+        writer.Int64(*v);
+    } else if (const auto* v = value.getDouble()) {
+        writer.Double(*v);
+    } else if (const auto* v = value.getString()) {
+        writer.String(v->data(), static_cast<rapidjson::SizeType>(v->size()));
+    } else if (const auto* values = value.getArray()) {
+        writer.StartArray();
+        for (const auto& item : *values) {
+            writeValue(writer, item);
+        }
+        writer.EndArray();
+    } else if (const auto* values = value.getObject()) {
+        writer.StartObject();
+        for (const auto& [key, item] : *values) {
+            writer.Key(key.data(), static_cast<rapidjson::SizeType>(key.size()));
+            writeValue(writer, item);
+        }
+        writer.EndObject();
+    } else {
+        writer.Null();
+    }
+}
+
+/// Message of an exception reported by a cache database operation
+static std::string describeDatabaseError(const std::exception_ptr& failure) {
+    try {
+        std::rethrow_exception(failure);
+    } catch (const std::exception& e) {
+        return e.what();
+    } catch (...) {
+        return "Unknown cache database error";
+    }
+}
+
+bool MapRenderer_flush(MapRenderer& self, rust::String& error) {
+    if (!self.databaseSource) {
+        return true;
+    }
+    // Packing is queued after all pending writes, and rewrites the file once they are stored
+    std::promise<std::exception_ptr> done;
+    self.databaseSource->packDatabase([&done](std::exception_ptr failure) { done.set_value(failure); });
+    if (auto failure = done.get_future().get()) {
+        error = rust::String(describeDatabaseError(failure));
+        return false;
+    }
+    return true;
+}
+
+void localizeLabels(style::Style& style, const std::string& locale) {
+    // Token strings like "{name}" are converted to expressions when the style is parsed,
+    // so a single replacement covers both. Each lookup falls back to `name` for features
+    // without a localized name, and text built from other properties is left as is.
+    const std::string name = "[\"get\",\"name\"]";
+    const std::string localized = "[\"coalesce\",[\"get\",\"name:" + locale + "\"]," + name + "]";
+    for (auto* layer : style.getLayers()) {
+        auto* symbol = layer->as<style::SymbolLayer>();
+        if (!symbol) {
+            continue;
+        }
+        const auto property = symbol->getProperty("text-field");
+        if (property.getKind() != style::StyleProperty::Kind::Expression) {
+            continue;
+        }
+        rapidjson::StringBuffer buffer;
+        JSWriter writer(buffer);
+        writeValue(writer, property.getValue());
+        std::string field(buffer.GetString(), buffer.GetSize());
+        bool replaced = false;
+        for (auto pos = field.find(name); pos != std::string::npos; pos = field.find(name, pos + localized.size())) {
+            field.replace(pos, name.size(), localized);
+            replaced = true;
+        }
+        if (!replaced) {
+            continue;
+        }
+        JSDocument document;
+        document.Parse<0>(field.c_str());
+        const JSValue* value = &document;
+        if (auto error = symbol->setProperty("text-field", style::conversion::Convertible(value))) {
+            Log::Warning(Event::General, "Unable to localize labels of layer " + layer->getID() + ": " + error->message);
+        }
+    }
+}
+
+rust::String MapRenderer_styleJson(const MapRenderer& self) {
+    const auto& style = self.map->getStyle();
+    // Sources cannot be serialized by MapLibre, so take them from the original style JSON,
+    // or from the definitions added at runtime. Layers are serialized from their current state.
+    JSDocument original;
+    original.Parse<0>(style.getJSON().c_str());
+    const bool hasOriginal = !original.HasParseError() && original.IsObject();
+
+    rapidjson::StringBuffer buffer;
+    JSWriter writer(buffer);
+    writer.StartObject();
+    if (hasOriginal) {
+        for (const auto& member : original.GetObject()) {
+            const std::string key = member.name.GetString();
+            if (key != "sources" && key != "layers") {
+                writer.Key(key.c_str());
+                member.value.Accept(writer);
+            }
+        }
+    } else {
+        writer.Key("version");
+        writer.Int(8);
+    }
+
+    writer.Key("sources");
+    writer.StartObject();
+    const auto* originalSources = hasOriginal && original.HasMember("sources") && original["sources"].IsObject()
+                                      ? &original["sources"]
+                                      : nullptr;
+    for (const auto* source : style.getSources()) {
+        const auto& id = source->getID();
+        if (originalSources && originalSources->HasMember(id.c_str())) {
+            writer.Key(id.c_str());
+            (*originalSources)[id.c_str()].Accept(writer);
+        } else if (auto added = self.addedSources.find(id); added != self.addedSources.end()) {
+            JSDocument definition;
+            definition.Parse<0>(added->second.c_str());
+            writer.Key(id.c_str());
+            definition.Accept(writer);
+        }
+    }
+    writer.EndObject();
+
+    writer.Key("layers");
+    writer.StartArray();
+    for (const auto* layer : style.getLayers()) {
+        // The graticule is generated again when the document is loaded, so leave it out
+        if (self.graticule && layer->getID() == "graticule") {
+            continue;
+        }
+        writeValue(writer, layer->serialize());
+    }
+    writer.EndArray();
+    writer.EndObject();
+    return rust::String(buffer.GetString(), buffer.GetSize());
+}
+
+bool MapRenderer_setStyleJson(MapRenderer& self, const rust::Str json, rust::String& error) {
+    const auto style = (std::string)json;
+    // Report malformed JSON right away, instead of as a style load failure on the next render
+    JSDocument document;
+    document.Parse<0>(style.c_str());
+    if (document.HasParseError()) {
+        error = rust::String(std::string("Invalid JSON at offset ") +
+                             util::toString(document.GetErrorOffset()) + ": " +
+                             rapidjson::GetParseError_En(document.GetParseError()));
+        return false;
+    }
+    if (!document.IsObject()) {
+        error = rust::String("Style must be a JSON object");
+        return false;
+    }
+    self.styleError.reset();
+    self.map->getStyle().loadJSON(style);
+    return true;
+}
+
+rust::Vec<rust::String> validateStyle(const rust::Str json, rust::Vec<rust::String>& missingSources) {
+    rust::Vec<rust::String> errors;
+    const auto fail = [&](const std::string& message) { errors.push_back(rust::String(message)); };
+    JSDocument document;
+    document.Parse<0>(((std::string)json).c_str());
+    if (document.HasParseError()) {
+        fail(std::string("Invalid JSON at offset ") + util::toString(document.GetErrorOffset()) + ": " +
+             rapidjson::GetParseError_En(document.GetParseError()));
+        return errors;
+    }
+    if (!document.IsObject()) {
+        fail("Style must be a JSON object");
+        return errors;
+    }
+    if (!document.HasMember("version") || !document["version"].IsInt() || document["version"].GetInt() != 8) {
+        fail("Style version must be 8");
+    }
+
+    // Convert every source and layer on its own, like the style parser, but collect all failures
+    // instead of logging and skipping them
+    std::set<std::string> declaredSources;
+    std::set<std::string> reportedSources;
+    if (document.HasMember("sources")) {
+        const JSValue& sources = document["sources"];
+        if (!sources.IsObject()) {
+            fail("Style sources must be an object");
+        } else {
+            for (const auto& member : sources.GetObject()) {
+                const std::string id = member.name.GetString();
+                declaredSources.insert(id);
+                style::conversion::Error error;
+                const JSValue* value = &member.value;
+                if (!style::conversion::convert<std::unique_ptr<style::Source>>(
+                        style::conversion::Convertible(value), error, id)) {
+                    fail("Source \"" + id + "\": " + error.message);
+                }
+            }
+        }
+    }
+    if (!document.HasMember("layers") || !document["layers"].IsArray()) {
+        fail("Style layers must be an array");
+        return errors;
+    }
+    std::set<std::string> layerIds;
+    for (const auto& value : document["layers"].GetArray()) {
+        style::conversion::Error error;
+        const JSValue* pointer = &value;
+        auto layer = style::conversion::convert<std::unique_ptr<style::Layer>>(
+            style::conversion::Convertible(pointer), error);
+        if (!layer) {
+            const std::string id = value.IsObject() && value.HasMember("id") && value["id"].IsString()
+                                       ? value["id"].GetString()
+                                       : "";
+            fail("Layer \"" + id + "\": " + error.message);
+            continue;
+        }
+        const auto& id = (*layer)->getID();
+        if (!layerIds.insert(id).second) {
+            fail("Layer \"" + id + "\" is defined more than once");
+        }
+        // Sources that failed to convert were already reported
+        const auto& source = (*layer)->getSourceID();
+        if (!source.empty() && !declaredSources.contains(source) && reportedSources.insert(source).second) {
+            missingSources.push_back(rust::String(source));
+        }
+    }
+    return errors;
+}
+
+LayerStatus MapRenderer_setLineDash(MapRenderer& self, const rust::Str layerId, const rust::Slice<const double> dashes) {
+    auto* layer = self.map->getStyle().getLayer((std::string)layerId);
+    if (!layer) {
+        return LayerStatus::NotFound;
+    }
+    auto* line = layer->as<style::LineLayer>();
+    if (!line) {
+        return LayerStatus::WrongType;
+    }
+    if (dashes.empty()) {
+        // An undefined value restores a solid line
+        line->setLineDasharray({});
+    } else {
+        line->setLineDasharray(std::vector<float>(dashes.begin(), dashes.end()));
+    }
+    return LayerStatus::Ok;
+}
+
+void MapRenderer_addImage(MapRenderer& self,
+                          const rust::Str id,
+                          const rust::Slice<const uint8_t> rgba,
+                          uint32_t width,
+                          uint32_t height,
+                          float pixelRatio,
+                          bool sdf) {
+    // The size was checked by the caller, and the style expects premultiplied pixels
+    UnassociatedImage image({width, height}, rgba.data(), rgba.size());
+    self.map->getStyle().addImage(
+        std::make_unique<style::Image>((std::string)id, util::premultiply(std::move(image)), pixelRatio, sdf));
+}
+
+void MapRenderer_setGraticule(MapRenderer& self, const rust::Str geojson, Rgba color) {
+    style::conversion::Error error;
+    auto data = style::conversion::parseGeoJSON((std::string)geojson, error);
+    if (!data) {
+        throw std::runtime_error("Invalid graticule GeoJSON: " + error.message);
+    }
+    const auto channel = [](uint8_t value) { return value / 255.0f; };
+    self.graticule.emplace(std::move(*data), Color(channel(color.r), channel(color.g), channel(color.b), channel(color.a)));
+}
+
+/// Format a feature ID the same way regardless of whether it is stored as a number or a string
+static std::string featureIdToString(const FeatureIdentifier& id) {
+    return id.match([](const std::string& value) { return value; },
+                    [](NullValue) { return std::string(); },
+                    [](const auto& value) { return util::toString(value); });
+}
+
+bool MapRenderer_featureScreenBounds(const MapRenderer& self,
+                                     const rust::Str source,
+                                     const rust::Str sourceLayer,
+                                     const rust::Str featureId,
+                                     ScreenRect& bounds) {
+    auto* renderer = self.frontend->getRenderer();
+    if (!renderer) {
+        return false;
+    }
+    SourceQueryOptions options;
+    if (!sourceLayer.empty()) {
+        options.sourceLayers = std::vector<std::string>{(std::string)sourceLayer};
+    }
+    const auto id = (std::string)featureId;
+
+    bool found = false;
+    // A feature may be split across several tiles, so combine the extent of all its parts
+    for (const auto& feature : renderer->querySourceFeatures((std::string)source, options)) {
+        if (featureIdToString(feature.id) != id) {
+            continue;
+        }
+        mapbox::geometry::for_each_point(feature.geometry, [&](const Point<double>& point) {
+            const auto pixel = self.map->pixelForLatLng(LatLng{point.y, point.x});
+            if (!found) {
+                bounds = ScreenRect{pixel.x, pixel.y, pixel.x, pixel.y};
+                found = true;
+            } else {
+                bounds.min_x = std::min(bounds.min_x, pixel.x);
+                bounds.min_y = std::min(bounds.min_y, pixel.y);
+                bounds.max_x = std::max(bounds.max_x, pixel.x);
+                bounds.max_y = std::max(bounds.max_y, pixel.y);
+            }
+        });
+    }
+
+    const auto size = self.map->getMapOptions().size();
+    return found && bounds.max_x >= 0 && bounds.max_y >= 0 && bounds.min_x <= size.width &&
+           bounds.min_y <= size.height;
+}
+
+rust::Vec<FeatureInfo> MapRenderer_queryRenderedFeatures(const MapRenderer& self, double x, double y) {
+    rust::Vec<FeatureInfo> result;
+    auto* renderer = self.frontend->getRenderer();
+    if (!renderer) {
+        return result;
+    }
+    // Features do not record the layer that rendered them, so query each layer on its own, topmost first
+    const auto layers = self.map->getStyle().getLayers();
+    for (auto layer = layers.rbegin(); layer != layers.rend(); ++layer) {
+        RenderedQueryOptions options;
+        options.layerIDs = std::vector<std::string>{(*layer)->getID()};
+        for (const auto& feature : renderer->queryRenderedFeatures(ScreenCoordinate{x, y}, options)) {
+            rapidjson::StringBuffer buffer;
+            JSWriter writer(buffer);
+            writer.StartObject();
+            for (const auto& [key, value] : feature.properties) {
+                writer.Key(key.data(), static_cast<rapidjson::SizeType>(key.size()));
+                writeValue(writer, value);
+            }
+            writer.EndObject();
+            result.push_back(FeatureInfo{
+                rust::String((*layer)->getID()),
+                rust::String(feature.source),
+                rust::String(feature.sourceLayer),
+                rust::String(featureIdToString(feature.id)),
+                rust::String(buffer.GetString(), buffer.GetSize()),
+            });
+        }
+    }
+    return result;
+}
+
+void MapRenderer_setCameraWithOptions(
+    MapRenderer& self, CameraState camera, Padding padding, bool anchored, double anchorX, double anchorY) {
+    const auto& view = self.viewPadding;
+    EdgeInsets insets{padding.top + view.top(),
+                      padding.left + view.left(),
+                      padding.bottom + view.bottom(),
+                      padding.right + view.right()};
+    if (anchored) {
+        // MapLibre centers the camera in the area left by the padding,
+        // so pad the far side of each axis to move that center onto the anchor
+        const auto size = self.frontend->getSize();
+        const auto width = static_cast<double>(size.width);
+        const auto height = static_cast<double>(size.height);
+        insets = EdgeInsets{std::max(0.0, 2.0 * anchorY - height),
+                            std::max(0.0, 2.0 * anchorX - width),
+                            std::max(0.0, height - 2.0 * anchorY),
+                            std::max(0.0, width - 2.0 * anchorX)};
+    }
+    self.map->jumpTo(CameraOptions()
+                         .withCenter(LatLng{camera.lat, camera.lon})
+                         .withPadding(insets)
+                         .withZoom(camera.zoom)
+                         .withBearing(camera.bearing)
+                         .withPitch(camera.pitch));
+    // Describe the same view relative to the viewport padding, which later camera changes keep
+    self.map->jumpTo(self.map->getCameraOptions(self.viewPadding));
+}
+
+void MapRenderer_setCameraToBounds(
+    MapRenderer& self, double west, double south, double east, double north, Padding padding) {
+    if (east < west) {
+        // Crossing the antimeridian, keep the bounds contiguous with an unwrapped eastern edge
+        east += 360.0;
+    }
+    // Keep the bounds clear of the viewport padding as well
+    const auto& view = self.viewPadding;
+    const EdgeInsets insets{padding.top + view.top(),
+                            padding.left + view.left(),
+                            padding.bottom + view.bottom(),
+                            padding.right + view.right()};
+    CameraOptions cameraOptions;
+    if (west == east && south == north) {
+        // A single point has no extent to fit, so only move the center
+        cameraOptions.withCenter(LatLng{south, west});
+    } else {
+        const auto bounds = LatLngBounds::hull(LatLng{south, west}, LatLng{north, east});
+        cameraOptions = self.map->cameraForLatLngBounds(bounds, insets, 0.0, 0.0);
+    }
+    if (cameraOptions.center) {
+        cameraOptions.center->wrap();
+    }
+    cameraOptions.withBearing(0.0).withPitch(0.0).withPadding(insets);
+    self.map->jumpTo(cameraOptions);
+    // Describe the same view relative to the viewport padding, which later camera changes keep
+    self.map->jumpTo(self.map->getCameraOptions(self.viewPadding));
+}
+
+void MapRenderer_setPadding(MapRenderer& self, Padding padding) {
+    self.viewPadding = EdgeInsets{padding.top, padding.left, padding.bottom, padding.right};
+    // Only the padding changes, so the center stays on the same coordinate, shifted on screen
+    self.map->jumpTo(CameraOptions().withPadding(self.viewPadding));
+}
+
+CameraState MapRenderer_getCamera(const MapRenderer& self) {
+    // The camera is only moved with jumpTo, so there are never pending transitions
+    const auto camera = self.map->getCameraOptions();
+    const auto center = camera.center.value_or(LatLng{}).wrapped();
+    return CameraState{
+        center.longitude(),
+        center.latitude(),
+        camera.zoom.value_or(0.0),
+        camera.bearing.value_or(0.0),
+        camera.pitch.value_or(0.0),
+    };
+}
+
+void MapRenderer_setResourceObserver(MapRenderer& self, rust::Box<ResourceObserver> observer) {
+    // std::function must be copyable, so share the observer between copies
+    auto shared = std::make_shared<rust::Box<ResourceObserver>>(std::move(observer));
+    if (!self.resourceObserver) {
+        observedRenderers.push_back(&self);
+    }
+    self.resourceObserver = [shared](const Resource& resource, const Response& response) {
+        ResourceStatus status = ResourceStatus::Ok;
+        std::string message;
+        if (response.error) {
+            status = response.error->reason == Response::Error::Reason::NotFound ? ResourceStatus::NotFound
+                                                                                 : ResourceStatus::Error;
+            message = response.error->message;
+        }
+        notify_resource_observer(
+            **shared, ResourceEvent{rust::String(resource.url), toResourceKind(resource.kind), status, rust::String(message)});
+    };
+}
+
+void MapRenderer_setSchemeResolver(MapRenderer& self, const rust::Str scheme, rust::Box<SchemeResolver> resolver) {
+    if (self.schemeResolvers.empty()) {
+        resolvingRenderers.push_back(&self);
+    }
+    self.schemeResolvers[urlScheme((std::string)scheme + "://")] =
+        std::make_shared<const rust::Box<SchemeResolver>>(std::move(resolver));
+}
+
+bool MapRenderer_hasSchemeResolver(const MapRenderer& self, const rust::Str scheme) {
+    return self.schemeResolvers.contains(urlScheme((std::string)scheme + "://"));
+}
+
+namespace {
+
+/// Outcome of an offline region download, reported once by RegionDownloadObserver
+struct RegionDownloadResult {
+    OfflineRegionStatus status;
+    RenderStatus error = RenderStatus::Ok;
+    std::string message;
+};
+
+/// Reports the first terminal event of a download, i.e. its completion or its first failure.
+/// MapLibre would retry failed resources indefinitely, which would block the caller forever.
+class RegionDownloadObserver : public OfflineRegionObserver {
+public:
+    explicit RegionDownloadObserver(std::shared_ptr<std::promise<RegionDownloadResult>> result_)
+        : result(std::move(result_)) {}
+
+    void statusChanged(OfflineRegionStatus status) override {
+        latest = status;
+        if (status.complete()) {
+            finish(RenderStatus::Ok, {});
+        }
+    }
+
+    void responseError(Response::Error error) override { finish(RenderStatus::Network, error.message); }
+
+    void mapboxTileCountLimitExceeded(uint64_t limit) override {
+        finish(RenderStatus::Cache, "Region exceeds the limit of " + std::to_string(limit) + " tiles");
+    }
+
+private:
+    void finish(RenderStatus error, std::string message) {
+        if (!finished) {
+            finished = true;
+            result->set_value(RegionDownloadResult{latest, error, std::move(message)});
+        }
+    }
+
+    std::shared_ptr<std::promise<RegionDownloadResult>> result;
+    OfflineRegionStatus latest;
+    bool finished = false;
+};
+
+} // namespace
+
+RenderStatus MapRenderer_downloadRegion(MapRenderer& self,
+                                        double west,
+                                        double south,
+                                        double east,
+                                        double north,
+                                        double minZoom,
+                                        double maxZoom,
+                                        RegionStats& stats,
+                                        rust::String& error) {
+    const auto fail = [&](RenderStatus status, const std::string& message) {
+        error = rust::String(message);
+        return status;
+    };
+    // Offline regions store the URL of their style, as it is fetched again when downloading
+    const auto styleUrl = self.map->getStyle().getURL();
+    if (styleUrl.empty()) {
+        return fail(RenderStatus::StyleLoad, "Offline regions require a style loaded from a URL");
+    }
+    if (!self.databaseSource) {
+        return fail(RenderStatus::Cache, "The renderer has no cache database");
+    }
+
+    // The region pixel ratio selects which sprites and raster tiles are downloaded, so it must be the
+    // resource pixel ratio held by the map options, which later renders request, not the image pixel ratio
+    const OfflineTilePyramidRegionDefinition definition(styleUrl,
+                                                        LatLngBounds::hull(LatLng{south, west}, LatLng{north, east}),
+                                                        minZoom,
+                                                        maxZoom,
+                                                        self.map->getMapOptions().pixelRatio(),
+                                                        true);
+
+    // All callbacks run on the database thread, so block on promises instead of the run loop
+    std::promise<expected<OfflineRegion, std::exception_ptr>> created;
+    self.databaseSource->createOfflineRegion(
+        definition, {}, [&created](expected<OfflineRegion, std::exception_ptr> region) {
+            created.set_value(std::move(region));
+        });
+    auto region = created.get_future().get();
+    if (!region) {
+        return fail(RenderStatus::Cache, describeDatabaseError(region.error()));
+    }
+
+    auto result = std::make_shared<std::promise<RegionDownloadResult>>();
+    auto finished = result->get_future();
+    self.databaseSource->setOfflineRegionObserver(*region, std::make_unique<RegionDownloadObserver>(result));
+    self.databaseSource->setOfflineRegionDownloadState(*region, OfflineRegionDownloadState::Active);
+    const auto outcome = finished.get();
+    self.databaseSource->setOfflineRegionDownloadState(*region, OfflineRegionDownloadState::Inactive);
+
+    stats = RegionStats{
+        outcome.status.completedTileCount,
+        outcome.status.completedResourceCount,
+        outcome.status.completedResourceSize,
+    };
+    if (outcome.error != RenderStatus::Ok) {
+        return fail(outcome.error, outcome.message);
+    }
+    return RenderStatus::Ok;
+}
+
+//
+// Logging
+//
+
+namespace {
+
+/// Forwards all MapLibre log records to the `log` crate, see `init_logging`
+class RustLogObserver : public Log::Observer {
+public:
+    bool onRecord(EventSeverity severity, Event event, int64_t code, const std::string& msg) override {
+        LogSeverity level = LogSeverity::Error;
+        switch (severity) {
+            case EventSeverity::Debug:
+                level = LogSeverity::Debug;
+                break;
+            case EventSeverity::Info:
+                level = LogSeverity::Info;
+                break;
+            case EventSeverity::Warning:
+                level = LogSeverity::Warning;
+                break;
+            default:
+                break;
+        }
+        log_record(level, Enum<Event>::toString(event), code, msg);
+        // The record is consumed, so MapLibre does not also write it to the platform log
+        return true;
+    }
+};
+
+} // namespace
+
+void installLogObserver() {
+    Log::setObserver(std::make_unique<RustLogObserver>());
+}
+
+} // namespace bridge
 } // namespace mln
@@ -1,5 +1,14 @@
 use cxx::{CxxString, UniquePtr};
 
+use crate::renderer::assets::{embedded_asset, EmbeddedAssets};
+use crate::renderer::cancel::{cancellation_requested, CancellationToken};
+use crate::renderer::logging::log_record;
+use crate::renderer::provider_keys::apply_provider_keys;
+use crate::renderer::resources::{notify_resource_observer, ResourceObserver};
+use crate::renderer::scheme::{resolve_scheme, SchemeResolver};
+
+// The code generated by cxx for documented shared structs uses fully qualified paths
+#[allow(unused_qualifications)]
 #[cxx::bridge(namespace = "mln::bridge")]
 pub mod ffi {
     //
@@ -46,12 +55,233 @@ pub mod ffi {
         DepthBuffer = 0b1000_0000, // 1 << 7
     }
 
+    #[repr(u32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum ConstrainMode {
+        /// The camera may move freely in all directions
+        None,
+        /// The camera cannot move past the top and bottom edges of the world
+        HeightOnly,
+        /// The camera cannot move past any of the world's edges
+        WidthAndHeight,
+    }
+
+    /// Color with 8-bit red, green, blue, and alpha channels, not premultiplied.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    struct Rgba {
+        r: u8,
+        g: u8,
+        b: u8,
+        a: u8,
+    }
+
+    /// Kind of symbol part a [`CollisionBox`] belongs to.
+    #[repr(u8)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum CollisionBoxKind {
+        /// The text of a label
+        Text,
+        /// The icon of a symbol
+        Icon,
+    }
+
+    /// Collision box of a symbol considered during the last label placement.
+    ///
+    /// Coordinates are in logical pixels of the viewport, i.e. not multiplied by the pixel ratio.
+    #[derive(Debug, Clone, PartialEq)]
+    struct CollisionBox {
+        /// Text content of the symbol, used by MapLibre to identify it across tiles
+        key: String,
+        /// ID of the symbol layer the symbol belongs to
+        layer: String,
+        /// Whether this is the box of the text or of the icon
+        kind: CollisionBoxKind,
+        /// `true` if the symbol was placed, `false` if it was hidden due to a collision
+        placed: bool,
+        /// `true` if the box crosses a tile boundary
+        intersects_tile_border: bool,
+        min_x: f32,
+        min_y: f32,
+        max_x: f32,
+        max_y: f32,
+    }
+
+    /// Feature found by [`ImageRenderer::query_rendered_features`](crate::ImageRenderer::query_rendered_features).
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct FeatureInfo {
+        /// ID of the style layer that rendered the feature
+        layer: String,
+        /// ID of the source the feature belongs to
+        source: String,
+        /// Layer of a vector source the feature belongs to, empty for other sources
+        source_layer: String,
+        /// Feature ID, with numbers in their decimal representation, or empty if it has none
+        id: String,
+        /// Properties of the feature as a JSON object
+        properties: String,
+    }
+
+    /// Axis-aligned rectangle in logical pixels of the viewport, with the origin in the top-left corner.
+    #[derive(Debug, Clone, Copy, PartialEq, Default)]
+    struct ScreenRect {
+        min_x: f64,
+        min_y: f64,
+        max_x: f64,
+        max_y: f64,
+    }
+
+    /// Space in logical pixels to keep free along each edge of the viewport,
+    /// e.g. around the bounds passed to [`ImageRenderer::set_camera_to_bounds`](crate::ImageRenderer::set_camera_to_bounds),
+    /// or covered by UI elements, see [`ImageRenderer::set_padding`](crate::ImageRenderer::set_padding).
+    #[derive(Debug, Clone, Copy, PartialEq, Default)]
+    struct Padding {
+        top: f64,
+        left: f64,
+        bottom: f64,
+        right: f64,
+    }
+
+    /// Camera position as resolved by MapLibre, see [`ImageRenderer::get_camera`](crate::ImageRenderer::get_camera).
+    #[derive(Debug, Clone, Copy, PartialEq, Default)]
+    struct CameraState {
+        /// Longitude of the center in degrees, wrapped to `[-180, 180]`
+        lon: f64,
+        /// Latitude of the center in degrees
+        lat: f64,
+        zoom: f64,
+        /// Bearing in degrees clockwise from north
+        bearing: f64,
+        /// Pitch in degrees away from the vertical
+        pitch: f64,
+    }
+
+    /// Resources stored by [`ImageRenderer::download_region`](crate::ImageRenderer::download_region).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    struct RegionStats {
+        /// Number of tiles downloaded
+        tiles: u64,
+        /// Number of resources downloaded, including tiles, the style, sprites, and glyphs
+        resources: u64,
+        /// Total size of the downloaded resources in bytes
+        bytes: u64,
+    }
+
+    /// Outcome of a render, classified by the C++ side.
+    #[repr(u8)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum RenderStatus {
+        Ok,
+        /// The style could not be fetched or parsed
+        StyleLoad,
+        /// A resource needed by the style, e.g. a tile, could not be fetched
+        Network,
+        /// The graphics context could not be created or used
+        GpuContext,
+        /// The render produced no image
+        Empty,
+        /// The cache database could not be read or written
+        Cache,
+        /// A resource request took longer than the configured timeout
+        Timeout,
+        /// The render was cancelled by its token
+        Cancelled,
+    }
+
+    /// Outcome of modifying a style layer from C++.
+    #[repr(u8)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum LayerStatus {
+        Ok,
+        /// The style has no layer with the given ID
+        NotFound,
+        /// The layer does not support the requested change
+        WrongType,
+        /// The JSON definition was rejected, with the reason in the error message
+        Invalid,
+    }
+
+    /// Type of a resource loaded by the map, see [`ResourceEvent`].
+    #[repr(u8)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum ResourceKind {
+        Unknown,
+        /// The style document
+        Style,
+        /// A TileJSON document referenced by a source
+        Source,
+        /// A vector or raster tile
+        Tile,
+        /// A range of glyphs of a font stack
+        Glyphs,
+        /// The sprite sheet image
+        SpriteImage,
+        /// The index of the sprite sheet
+        SpriteJson,
+        /// An image referenced by an image source
+        Image,
+    }
+
+    /// Outcome of loading a resource, see [`ResourceEvent`].
+    #[repr(u8)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum ResourceStatus {
+        /// The resource was loaded, possibly with no content, e.g. for an empty tile
+        Ok,
+        /// The server has no such resource
+        NotFound,
+        /// The resource could not be loaded, with the reason in the message
+        Error,
+    }
+
+    /// Outcome of a URL scheme resolver call, with the data or the URL in its out-parameters.
+    #[repr(u8)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum ResolveStatus {
+        NotFound,
+        Data,
+        Redirect,
+    }
+
+    /// A response received for a resource requested by the map,
+    /// see [`ImageRenderer::set_resource_observer`](crate::ImageRenderer::set_resource_observer).
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct ResourceEvent {
+        /// URL of the resource as requested by the style, before provider keys are appended
+        url: String,
+        kind: ResourceKind,
+        status: ResourceStatus,
+        /// Error message, empty if the resource was loaded
+        message: String,
+    }
+
+    /// Severity of a MapLibre log record, mapped to a [`log::Level`].
+    #[repr(u8)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum LogSeverity {
+        Debug,
+        Info,
+        Warning,
+        Error,
+    }
+
+    /// Query parameter appended to every request sent to a matching host.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct ProviderKey {
+        /// Exact host name, or a `*.example.com` wildcard matching any subdomain
+        host_pattern: String,
+        /// Name of the query parameter, e.g. `api_key`
+        param: String,
+        /// Value of the query parameter
+        key: String,
+    }
+
     #[namespace = "mbgl"]
     unsafe extern "C++" {
         include!("mbgl/map/mode.hpp");
 
         type MapMode;
         type MapDebugOptions;
+        type ConstrainMode;
     }
 
     unsafe extern "C++" {
@@ -66,6 +296,7 @@ pub mod ffi {
             width: u32,
             height: u32,
             pixelRatio: f32,
+            resourcePixelRatio: f32,
             cachePath: &str,
             assetRoot: &str,
             apiKey: &str,
@@ -79,17 +310,241 @@ pub mod ffi {
             tileTemplate: &str,
             defaultStyleUrl: &str,
             requiresApiKey: bool,
+            providerKeys: &[ProviderKey],
+            labelDensity: f64,
+            constrainMode: ConstrainMode,
+            localFontFamily: &str,
+            eglPlatform: &str,
+            embeddedAssets: Box<EmbeddedAssets>,
+            hasEmbeddedAssets: bool,
+            maxConcurrentRequests: u32,
+            error: &mut String,
         ) -> UniquePtr<MapRenderer>;
-        fn MapRenderer_render(obj: Pin<&mut MapRenderer>) -> UniquePtr<CxxString>;
-        fn MapRenderer_setDebugFlags(obj: Pin<&mut MapRenderer>, flags: MapDebugOptions);
+        fn MapRenderer_setLetterboxColor(obj: Pin<&mut MapRenderer>, color: Rgba);
+        fn MapRenderer_setClearColor(obj: Pin<&mut MapRenderer>, color: Rgba);
+        fn MapRenderer_setGraticule(
+            obj: Pin<&mut MapRenderer>,
+            geojson: &str,
+            color: Rgba,
+        ) -> Result<()>;
+        fn MapRenderer_setFadeDuration(obj: Pin<&mut MapRenderer>, micros: u64);
+        fn MapRenderer_setLabelLocale(obj: Pin<&mut MapRenderer>, locale: &str);
+        fn MapRenderer_setTransparentBackground(obj: Pin<&mut MapRenderer>, transparent: bool);
+        fn MapRenderer_setRequestTimeout(obj: Pin<&mut MapRenderer>, millis: u64);
+        fn MapRenderer_setFrameTimeout(obj: Pin<&mut MapRenderer>, millis: u64);
+        fn MapRenderer_render(
+            obj: Pin<&mut MapRenderer>,
+            status: &mut RenderStatus,
+            error: &mut String,
+        ) -> UniquePtr<CxxString>;
+        fn MapRenderer_renderCancellable(
+            obj: Pin<&mut MapRenderer>,
+            token: &CancellationToken,
+            status: &mut RenderStatus,
+            error: &mut String,
+        ) -> UniquePtr<CxxString>;
+        fn MapRenderer_renderContinuous(
+            obj: Pin<&mut MapRenderer>,
+            status: &mut RenderStatus,
+            error: &mut String,
+        ) -> UniquePtr<CxxString>;
+        fn MapRenderer_renderRawInto(
+            obj: Pin<&mut MapRenderer>,
+            pixels: &mut [u8],
+            error: &mut String,
+        ) -> RenderStatus;
+        fn MapRenderer_waitUntilIdle(
+            obj: Pin<&mut MapRenderer>,
+            error: &mut String,
+        ) -> RenderStatus;
+        fn MapRenderer_isFullyLoaded(obj: &MapRenderer) -> bool;
+        fn MapRenderer_renderRaw(
+            obj: Pin<&mut MapRenderer>,
+            status: &mut RenderStatus,
+            error: &mut String,
+            width: &mut u32,
+            height: &mut u32,
+        ) -> UniquePtr<CxxString>;
+        fn MapRenderer_collisionBoxes(obj: &MapRenderer) -> Vec<CollisionBox>;
+        fn MapRenderer_featureScreenBounds(
+            obj: &MapRenderer,
+            source: &str,
+            sourceLayer: &str,
+            featureId: &str,
+            bounds: &mut ScreenRect,
+        ) -> bool;
+        fn MapRenderer_setFeatureState(
+            obj: Pin<&mut MapRenderer>,
+            sourceId: &str,
+            sourceLayer: &str,
+            featureId: &str,
+            json: &str,
+            error: &mut String,
+        ) -> LayerStatus;
+        fn MapRenderer_queryRenderedFeatures(obj: &MapRenderer, x: f64, y: f64)
+            -> Vec<FeatureInfo>;
+        fn MapRenderer_layerIds(obj: &MapRenderer) -> Vec<String>;
+        fn MapRenderer_layerVisibility(
+            obj: &MapRenderer,
+            layerId: &str,
+            visible: &mut bool,
+        ) -> LayerStatus;
+        fn MapRenderer_setLayerVisibility(
+            obj: Pin<&mut MapRenderer>,
+            layerId: &str,
+            visible: bool,
+        ) -> LayerStatus;
+        fn MapRenderer_addSource(
+            obj: Pin<&mut MapRenderer>,
+            sourceId: &str,
+            json: &str,
+            error: &mut String,
+        ) -> LayerStatus;
+        fn MapRenderer_addGeoJsonSource(
+            obj: Pin<&mut MapRenderer>,
+            sourceId: &str,
+            geojson: &str,
+            error: &mut String,
+        ) -> LayerStatus;
+        fn MapRenderer_updateGeoJsonSource(
+            obj: Pin<&mut MapRenderer>,
+            sourceId: &str,
+            geojson: &str,
+            error: &mut String,
+        ) -> LayerStatus;
+        fn MapRenderer_addLayer(
+            obj: Pin<&mut MapRenderer>,
+            json: &str,
+            beforeLayerId: &str,
+            error: &mut String,
+        ) -> LayerStatus;
+        fn MapRenderer_removeLayer(obj: Pin<&mut MapRenderer>, layerId: &str) -> LayerStatus;
+        fn MapRenderer_setLayerProperty(
+            obj: Pin<&mut MapRenderer>,
+            layerId: &str,
+            name: &str,
+            json: &str,
+            error: &mut String,
+        ) -> LayerStatus;
+        fn MapRenderer_styleJson(obj: &MapRenderer) -> String;
+        fn MapRenderer_flush(obj: Pin<&mut MapRenderer>, error: &mut String) -> bool;
+        fn MapRenderer_setStyleJson(
+            obj: Pin<&mut MapRenderer>,
+            json: &str,
+            error: &mut String,
+        ) -> bool;
+        fn MapRenderer_setLineDash(
+            obj: Pin<&mut MapRenderer>,
+            layerId: &str,
+            dashes: &[f64],
+        ) -> LayerStatus;
+        fn MapRenderer_addImage(
+            obj: Pin<&mut MapRenderer>,
+            id: &str,
+            rgba: &[u8],
+            width: u32,
+            height: u32,
+            pixelRatio: f32,
+            sdf: bool,
+        );
+        fn MapRenderer_setTerrainExaggeration(obj: Pin<&mut MapRenderer>, factor: f64);
+        fn MapRenderer_setDebugFlags(obj: Pin<&mut MapRenderer>, flags: u32);
+        fn MapRenderer_setSize(obj: Pin<&mut MapRenderer>, width: u32, height: u32);
+        fn MapRenderer_getSize(obj: &MapRenderer, width: &mut u32, height: &mut u32);
+        fn MapRenderer_getImageSize(obj: &MapRenderer, width: &mut u32, height: &mut u32);
+        fn MapRenderer_getPixelRatio(obj: &MapRenderer) -> f32;
+        fn MapRenderer_setRenderRatio(
+            obj: Pin<&mut MapRenderer>,
+            width: u32,
+            height: u32,
+            ratio: f32,
+        );
+        fn MapRenderer_getZoomRange(obj: &MapRenderer, minZoom: &mut f64, maxZoom: &mut f64);
+        fn MapRenderer_project(obj: &MapRenderer, lon: f64, lat: f64, x: &mut f64, y: &mut f64);
+        fn MapRenderer_unproject(obj: &MapRenderer, x: f64, y: f64, lon: &mut f64, lat: &mut f64);
+        fn MapRenderer_setZoomRange(obj: Pin<&mut MapRenderer>, minZoom: f64, maxZoom: f64);
+        fn MapRenderer_setLatLngBounds(
+            obj: Pin<&mut MapRenderer>,
+            west: f64,
+            south: f64,
+            east: f64,
+            north: f64,
+        );
         fn MapRenderer_setCamera(
             obj: Pin<&mut MapRenderer>,
-            lat: f64,
             lon: f64,
+            lat: f64,
             zoom: f64,
             bearing: f64,
             pitch: f64,
         );
+        fn MapRenderer_setCameraWithOptions(
+            obj: Pin<&mut MapRenderer>,
+            camera: CameraState,
+            padding: Padding,
+            anchored: bool,
+            anchor_x: f64,
+            anchor_y: f64,
+        );
+        fn MapRenderer_setCameraToBounds(
+            obj: Pin<&mut MapRenderer>,
+            west: f64,
+            south: f64,
+            east: f64,
+            north: f64,
+            padding: Padding,
+        );
+        fn MapRenderer_getCamera(obj: &MapRenderer) -> CameraState;
+        fn MapRenderer_setPadding(obj: Pin<&mut MapRenderer>, padding: Padding);
+        fn MapRenderer_setResourceObserver(
+            obj: Pin<&mut MapRenderer>,
+            observer: Box<ResourceObserver>,
+        );
+        fn MapRenderer_setSchemeResolver(
+            obj: Pin<&mut MapRenderer>,
+            scheme: &str,
+            resolver: Box<SchemeResolver>,
+        );
+        fn MapRenderer_hasSchemeResolver(obj: &MapRenderer, scheme: &str) -> bool;
+        #[allow(clippy::too_many_arguments)]
+        fn MapRenderer_downloadRegion(
+            obj: Pin<&mut MapRenderer>,
+            west: f64,
+            south: f64,
+            east: f64,
+            north: f64,
+            minZoom: f64,
+            maxZoom: f64,
+            stats: &mut RegionStats,
+            error: &mut String,
+        ) -> RenderStatus;
         fn MapRenderer_setStyleUrl(obj: Pin<&mut MapRenderer>, url: &str);
+
+        fn installLogObserver();
+        fn validateStyle(json: &str, missingSources: &mut Vec<String>) -> Vec<String>;
+    }
+
+    extern "Rust" {
+        type EmbeddedAssets;
+        type ResourceObserver;
+        type SchemeResolver;
+        type CancellationToken;
+
+        fn apply_provider_keys(keys: &[ProviderKey], url: &str) -> String;
+        fn notify_resource_observer(observer: &ResourceObserver, event: ResourceEvent);
+        fn cancellation_requested(token: &CancellationToken) -> bool;
+        fn resolve_scheme(
+            resolver: &SchemeResolver,
+            url: &str,
+            redirect: &mut String,
+            data: &mut Vec<u8>,
+        ) -> ResolveStatus;
+        fn log_record(severity: LogSeverity, event: &str, code: i64, message: &str);
+        /// The returned data borrows from `assets`, so C++ must not use it after `assets` is dropped
+        unsafe fn embedded_asset<'a>(
+            assets: &'a EmbeddedAssets,
+            path: &str,
+            found: &mut bool,
+        ) -> &'a [u8];
     }
 }
@@ -0,0 +1,440 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::renderer::bridge::ffi;
+use crate::renderer::image_renderer::MAX_PITCH;
+use crate::renderer::{ImageRenderer, LatLngBounds, Padding};
+
+/// Zoom levels accepted by [`Zoom::new`].
+const ZOOM_RANGE: RangeInclusive<f64> = 0.0..=24.0;
+const LATITUDE_RANGE: RangeInclusive<f64> = -90.0..=90.0;
+const PITCH_RANGE: RangeInclusive<f64> = 0.0..=MAX_PITCH;
+pub(crate) const LONGITUDE_RANGE: RangeInclusive<f64> = -180.0..=180.0;
+pub(crate) const NON_NEGATIVE: RangeInclusive<f64> = 0.0..=f64::INFINITY;
+/// Finite values above zero, which [`CameraValueError`] describes as positive.
+pub(crate) const POSITIVE: RangeInclusive<f64> = f64::MIN_POSITIVE..=f64::MAX;
+
+/// Error returned when constructing a camera value, or setting another value, out of its valid range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraValueError {
+    /// Name of the rejected value, e.g. `latitude`
+    pub name: &'static str,
+    /// The rejected value
+    pub value: f64,
+    /// The accepted range, or `None` if only non-finite values are rejected
+    pub range: Option<RangeInclusive<f64>>,
+}
+
+impl fmt::Display for CameraValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.range {
+            Some(range) if *range == POSITIVE => write!(
+                f,
+                "Invalid {} {}, expected a finite positive value",
+                self.name, self.value
+            ),
+            Some(range) if range.end().is_infinite() => write!(
+                f,
+                "Invalid {} {}, expected a value of at least {}",
+                self.name,
+                self.value,
+                range.start()
+            ),
+            Some(range) => write!(
+                f,
+                "Invalid {} {}, expected a value in [{}, {}]",
+                self.name,
+                self.value,
+                range.start(),
+                range.end()
+            ),
+            None => write!(
+                f,
+                "Invalid {} {}, expected a finite value",
+                self.name, self.value
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CameraValueError {}
+
+pub(crate) fn check(
+    name: &'static str,
+    value: f64,
+    range: Option<RangeInclusive<f64>>,
+) -> Result<f64, CameraValueError> {
+    let valid = match &range {
+        Some(range) => range.contains(&value),
+        None => value.is_finite(),
+    };
+    if valid {
+        Ok(value)
+    } else {
+        Err(CameraValueError { name, value, range })
+    }
+}
+
+/// Reject a camera center MapLibre cannot represent, which would abort the process.
+pub(crate) fn check_center(lon: f64, lat: f64) -> Result<(), CameraValueError> {
+    check("longitude", lon, None)?;
+    check("latitude", lat, Some(LATITUDE_RANGE))?;
+    Ok(())
+}
+
+/// Reject padding with a negative side.
+pub(crate) fn check_padding(padding: Padding) -> Result<Padding, CameraValueError> {
+    check("top padding", padding.top, Some(NON_NEGATIVE))?;
+    check("left padding", padding.left, Some(NON_NEGATIVE))?;
+    check("bottom padding", padding.bottom, Some(NON_NEGATIVE))?;
+    check("right padding", padding.right, Some(NON_NEGATIVE))?;
+    Ok(padding)
+}
+
+/// Reject bounds outside of the world, or with `south` above `north`.
+/// `west` may be greater than `east` only if `antimeridian` is set.
+pub(crate) fn check_bounds(
+    bounds: LatLngBounds,
+    antimeridian: bool,
+) -> Result<LatLngBounds, CameraValueError> {
+    check("west", bounds.west, Some(LONGITUDE_RANGE))?;
+    let east_range = if antimeridian {
+        LONGITUDE_RANGE
+    } else {
+        bounds.west..=*LONGITUDE_RANGE.end()
+    };
+    check("east", bounds.east, Some(east_range))?;
+    check("south", bounds.south, Some(LATITUDE_RANGE))?;
+    check(
+        "north",
+        bounds.north,
+        Some(bounds.south..=*LATITUDE_RANGE.end()),
+    )?;
+    Ok(bounds)
+}
+
+/// Geographic position in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatLng {
+    lat: f64,
+    lon: f64,
+}
+
+impl LatLng {
+    /// Position at `lon`, `lat`, matching the GeoJSON coordinate order used across this crate.
+    ///
+    /// The longitude is wrapped into `[-180, 180)`.
+    ///
+    /// # Errors
+    /// Returns an error if the latitude is not in `[-90, 90]`, or if the longitude is not finite.
+    pub fn new(lon: f64, lat: f64) -> Result<Self, CameraValueError> {
+        let lat = check("latitude", lat, Some(LATITUDE_RANGE))?;
+        let lon = check("longitude", lon, None)?;
+        Ok(Self {
+            lat,
+            lon: (lon + 180.0).rem_euclid(360.0) - 180.0,
+        })
+    }
+
+    #[must_use]
+    pub fn lat(self) -> f64 {
+        self.lat
+    }
+
+    #[must_use]
+    pub fn lon(self) -> f64 {
+        self.lon
+    }
+}
+
+/// Zoom level, where the whole world fits into a single tile at zero.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Zoom(f64);
+
+impl Zoom {
+    /// # Errors
+    /// Returns an error if the zoom is not in `[0, 24]`.
+    pub fn new(zoom: f64) -> Result<Self, CameraValueError> {
+        check("zoom", zoom, Some(ZOOM_RANGE)).map(Self)
+    }
+
+    #[must_use]
+    pub fn get(self) -> f64 {
+        self.0
+    }
+}
+
+/// Rotation in degrees clockwise from north.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Bearing(f64);
+
+impl Bearing {
+    /// The bearing is normalized into `[0, 360)`.
+    ///
+    /// # Errors
+    /// Returns an error if the bearing is not finite.
+    pub fn new(degrees: f64) -> Result<Self, CameraValueError> {
+        check("bearing", degrees, None).map(|degrees| Self(degrees.rem_euclid(360.0)))
+    }
+
+    #[must_use]
+    pub fn get(self) -> f64 {
+        self.0
+    }
+}
+
+/// Tilt in degrees away from the vertical.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Pitch(f64);
+
+impl Pitch {
+    /// # Errors
+    /// Returns an error if the pitch is not in `[0, 60]`, the range supported by MapLibre.
+    pub fn new(degrees: f64) -> Result<Self, CameraValueError> {
+        check("pitch", degrees, Some(PITCH_RANGE)).map(Self)
+    }
+
+    #[must_use]
+    pub fn get(self) -> f64 {
+        self.0
+    }
+}
+
+impl<S> ImageRenderer<S> {
+    /// Same as [`set_camera`](Self::set_camera), with values validated by their constructors.
+    pub fn set_camera_to(
+        &mut self,
+        center: LatLng,
+        zoom: Zoom,
+        bearing: Bearing,
+        pitch: Pitch,
+    ) -> &mut Self {
+        self.set_camera_unchecked(center.lon, center.lat, zoom.0, bearing.0, pitch.0)
+    }
+
+    /// Position of `position` in the rendered image for the current camera, in logical pixels
+    /// from the top left corner. Multiply by the pixel ratio to get the physical pixels of the image.
+    ///
+    /// Positions outside of the viewport return coordinates outside of the image.
+    #[must_use]
+    pub fn project(&self, position: LatLng) -> (f64, f64) {
+        let (mut x, mut y) = (0.0, 0.0);
+        ffi::MapRenderer_project(&self.0, position.lon, position.lat, &mut x, &mut y);
+        (x, y)
+    }
+
+    /// Geographic position shown at logical pixel `x`, `y` of the rendered image,
+    /// the inverse of [`project`](Self::project).
+    #[must_use]
+    pub fn unproject(&self, x: f64, y: f64) -> LatLng {
+        let (lon, lat) = self.unproject_unwrapped(x, y);
+        LatLng::new(lon, lat).expect("MapLibre returns finite positions")
+    }
+
+    /// Same as [`project`](Self::project), but in physical pixels of the rendered image,
+    /// i.e. scaled by the [pixel ratio](Self::pixel_ratio), e.g. to draw on top of a [`RawImage`](crate::RawImage).
+    ///
+    /// Like `project`, this follows the bearing and pitch of the current camera.
+    #[must_use]
+    pub fn project_to_image(&self, position: LatLng) -> (f64, f64) {
+        let ratio = f64::from(self.pixel_ratio());
+        let (x, y) = self.project(position);
+        (x * ratio, y * ratio)
+    }
+
+    /// Geographic position shown at physical pixel `x`, `y` of the rendered image,
+    /// e.g. where the image was clicked, the inverse of [`project_to_image`](Self::project_to_image).
+    #[must_use]
+    pub fn unproject_from_image(&self, x: f64, y: f64) -> LatLng {
+        let ratio = f64::from(self.pixel_ratio());
+        self.unproject(x / ratio, y / ratio)
+    }
+
+    /// Geographic extent of the image rendered with the current camera, e.g. to write a world file.
+    ///
+    /// Without bearing and pitch, the corners of the image are exactly at the corners of the bounds,
+    /// otherwise the bounds enclose all four corners. The image uses the Web Mercator projection,
+    /// so latitudes are not spaced evenly across it. Longitudes are not wrapped,
+    /// so a view across the antimeridian has an `east` above `180`, and a view wider than
+    /// the world spans more than 360 degrees.
+    #[must_use]
+    pub fn rendered_bounds(&self) -> LatLngBounds {
+        let (width, height) = self.size();
+        let (width, height) = (f64::from(width), f64::from(height));
+        let corners = [(0.0, 0.0), (width, 0.0), (0.0, height), (width, height)]
+            .map(|(x, y)| self.unproject_unwrapped(x, y));
+        corners.iter().fold(
+            LatLngBounds {
+                west: f64::INFINITY,
+                south: f64::INFINITY,
+                east: f64::NEG_INFINITY,
+                north: f64::NEG_INFINITY,
+            },
+            |bounds, &(lon, lat)| LatLngBounds {
+                west: bounds.west.min(lon),
+                south: bounds.south.min(lat),
+                east: bounds.east.max(lon),
+                north: bounds.north.max(lat),
+            },
+        )
+    }
+
+    fn unproject_unwrapped(&self, x: f64, y: f64) -> (f64, f64) {
+        let (mut lon, mut lat) = (0.0, 0.0);
+        ffi::MapRenderer_unproject(&self.0, x, y, &mut lon, &mut lat);
+        (lon, lat)
+    }
+}
+
+#[cfg(test)]
+// Normalized values are exact, so they are compared exactly
+#[allow(clippy::float_cmp)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::renderer::ImageRendererOptions;
+
+    #[test]
+    fn lat_lng() {
+        let pos = LatLng::new(10.0, 50.0).unwrap();
+        assert_eq!((pos.lon(), pos.lat()), (10.0, 50.0));
+        assert_eq!(LatLng::new(190.0, 0.0).unwrap().lon(), -170.0);
+        assert_eq!(LatLng::new(-540.0, 0.0).unwrap().lon(), -180.0);
+        assert_eq!(LatLng::new(0.0, -90.0).unwrap().lat(), -90.0);
+
+        let err = LatLng::new(50.0, 91.0).unwrap_err();
+        assert_eq!(err.name, "latitude");
+        assert_eq!(
+            err.to_string(),
+            "Invalid latitude 91, expected a value in [-90, 90]"
+        );
+        assert!(LatLng::new(f64::NAN, 0.0).is_err());
+        assert!(LatLng::new(f64::INFINITY, 0.0).is_err());
+    }
+
+    #[test]
+    fn padding_and_bounds() {
+        let padding = Padding {
+            bottom: -2.0,
+            ..Padding::default()
+        };
+        assert_eq!(
+            check_padding(padding).unwrap_err().to_string(),
+            "Invalid bottom padding -2, expected a value of at least 0"
+        );
+        let bounds = LatLngBounds {
+            west: 170.0,
+            south: -5.0,
+            east: -170.0,
+            north: 5.0,
+        };
+        assert_eq!(check_bounds(bounds, true), Ok(bounds));
+        assert_eq!(check_bounds(bounds, false).unwrap_err().name, "east");
+    }
+
+    #[test]
+    fn zoom_bearing_pitch() {
+        assert_eq!(Zoom::new(24.0).unwrap().get(), 24.0);
+        assert!(Zoom::new(-0.5).is_err());
+        assert!(Zoom::new(24.5).is_err());
+        assert!(Zoom::new(f64::NAN).is_err());
+
+        assert_eq!(Bearing::new(-90.0).unwrap().get(), 270.0);
+        assert_eq!(Bearing::new(360.0).unwrap().get(), 0.0);
+        assert_eq!(
+            Bearing::new(f64::NAN).unwrap_err().to_string(),
+            "Invalid bearing NaN, expected a finite value"
+        );
+
+        assert_eq!(Pitch::new(60.0).unwrap().get(), 60.0);
+        assert!(Pitch::new(-1.0).is_err());
+        assert!(Pitch::new(61.0).is_err());
+    }
+
+    #[test]
+    fn rendered_bounds() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(512, 256).with_pixel_ratio(2.0);
+        let mut map = opts.build_static_renderer();
+        map.set_camera(0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+
+        // The world is 1024 logical pixels wide at zoom 1, so the image spans half of it
+        let north = (PI / 4.0).sinh().atan().to_degrees();
+        let bounds = map.rendered_bounds();
+        assert!((bounds.west + 90.0).abs() < 1e-6, "{bounds:?}");
+        assert!((bounds.east - 90.0).abs() < 1e-6, "{bounds:?}");
+        assert!((bounds.north - north).abs() < 1e-6, "{bounds:?}");
+        assert!((bounds.south + north).abs() < 1e-6, "{bounds:?}");
+
+        let corners = [
+            (bounds.west, bounds.north, 0.0, 0.0),
+            (bounds.east, bounds.north, 512.0, 0.0),
+            (bounds.west, bounds.south, 0.0, 256.0),
+            (bounds.east, bounds.south, 512.0, 256.0),
+        ];
+        for (lon, lat, x, y) in corners {
+            let (px, py) = map.project(LatLng::new(lon, lat).unwrap());
+            assert!((px - x).abs() < 1e-6 && (py - y).abs() < 1e-6, "{px},{py}");
+            let pos = map.unproject(x, y);
+            assert!((pos.lon() - lon).abs() < 1e-6 && (pos.lat() - lat).abs() < 1e-6);
+        }
+
+        // Across the antimeridian, longitudes continue past it instead of wrapping
+        map.set_camera(180.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+        let bounds = map.rendered_bounds();
+        assert!(
+            (bounds.east - bounds.west - 180.0).abs() < 1e-6,
+            "{bounds:?}"
+        );
+        assert!(
+            (bounds.west.rem_euclid(360.0) - 90.0).abs() < 1e-6,
+            "{bounds:?}"
+        );
+    }
+
+    #[test]
+    fn project_round_trip() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(400, 300).with_pixel_ratio(2.0);
+        let mut map = opts.build_static_renderer();
+        map.set_camera(13.4, 52.5, 10.0, 30.0, 45.0).unwrap();
+        assert!((map.pixel_ratio() - 2.0).abs() < f32::EPSILON);
+
+        // The center of the view stays at the center of the image when rotated and tilted
+        let (x, y) = map.project_to_image(LatLng::new(13.4, 52.5).unwrap());
+        assert!(
+            (x - 400.0).abs() < 1e-6 && (y - 300.0).abs() < 1e-6,
+            "{x},{y}"
+        );
+        for (lon, lat) in [(13.38, 52.51), (13.45, 52.49), (13.41, 52.53)] {
+            let position = LatLng::new(lon, lat).unwrap();
+            let (x, y) = map.project_to_image(position);
+            let (lx, ly) = map.project(position);
+            assert!((x - lx * 2.0).abs() < 1e-9 && (y - ly * 2.0).abs() < 1e-9);
+            let back = map.unproject_from_image(x, y);
+            assert!((back.lon() - lon).abs() < 1e-6, "{back:?}");
+            assert!((back.lat() - lat).abs() < 1e-6, "{back:?}");
+        }
+    }
+
+    #[test]
+    fn set_camera_to() {
+        let mut map = ImageRendererOptions::new().build_static_renderer();
+        map.set_camera_to(
+            LatLng::new(370.0, 45.0).unwrap(),
+            Zoom::new(4.0).unwrap(),
+            Bearing::new(-30.0).unwrap(),
+            Pitch::new(20.0).unwrap(),
+        );
+        let camera = map.get_camera();
+        assert!((camera.lon - 10.0).abs() < 1e-9, "{camera:?}");
+        assert!((camera.lat - 45.0).abs() < 1e-9, "{camera:?}");
+        assert!((camera.zoom - 4.0).abs() < 1e-9, "{camera:?}");
+        assert!(
+            (camera.bearing.rem_euclid(360.0) - 330.0).abs() < 1e-9,
+            "{camera:?}"
+        );
+        assert!((camera.pitch - 20.0).abs() < 1e-9, "{camera:?}");
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::renderer::bridge::ffi;
+use crate::renderer::image_renderer::{check_status, ImageData};
+use crate::renderer::{Image, ImageRenderer, RenderError, Static};
+
+/// Flag to abandon a render from another thread, e.g. when the client requesting it disconnects.
+///
+/// Clones share the same flag, so one clone can be passed to the render,
+/// and another one kept to [`cancel`](Self::cancel) it.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request all renders using this token to stop. A cancelled token stays cancelled.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Called by the C++ render loop while it waits for resources.
+pub fn cancellation_requested(token: &CancellationToken) -> bool {
+    token.is_cancelled()
+}
+
+impl ImageRenderer<Static> {
+    /// Same as [`render_static`](Self::render_static), but stops early once `token` is cancelled.
+    ///
+    /// The token is checked at least every 10 milliseconds while resources are loading.
+    /// A cancelled render returns right away, and discards its frame. MapLibre only renders one
+    /// still image at a time, so the next render of this renderer first waits for the resources
+    /// of the cancelled one, unless the style or the camera changed so that they are not needed.
+    ///
+    /// # Errors
+    /// Returns [`RenderError::Cancelled`] if the token was cancelled before the render completed,
+    /// or any other error of [`render_static`](Self::render_static).
+    pub fn render_static_cancellable(
+        &mut self,
+        token: &CancellationToken,
+    ) -> Result<Image, RenderError> {
+        if token.is_cancelled() {
+            return Err(RenderError::Cancelled);
+        }
+        let mut status = ffi::RenderStatus::Ok;
+        let mut error = String::new();
+        let data =
+            ffi::MapRenderer_renderCancellable(self.0.pin_mut(), token, &mut status, &mut error);
+        check_status(status, error)?;
+        Ok(Image(ImageData::Cxx(data)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::renderer::image_renderer::tests::decode;
+    use crate::renderer::ImageRendererOptions;
+
+    #[test]
+    fn token() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!token.is_cancelled());
+        clone.cancel();
+        assert!(token.is_cancelled());
+        assert!(cancellation_requested(&token));
+    }
+
+    #[test]
+    fn cancel_slow_render() {
+        // A tile server that accepts connections but never responds
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let connections: Vec<_> = listener.incoming().collect();
+            drop(connections);
+        });
+        let style = format!(
+            r#"{{
+                "version": 8,
+                "sources": {{
+                    "slow": {{"type": "raster", "tiles": ["http://127.0.0.1:{port}/{{z}}/{{x}}/{{y}}.png"]}}
+                }},
+                "layers": [{{"id": "slow", "type": "raster", "source": "slow"}}]
+            }}"#
+        );
+
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64).with_in_memory_cache();
+        let mut map = opts.build_static_renderer();
+        map.set_style_json(&style).unwrap();
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            canceller.cancel();
+        });
+        let start = Instant::now();
+        assert_eq!(
+            map.render_static_cancellable(&token).err(),
+            Some(RenderError::Cancelled)
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(
+            map.render_static_cancellable(&token).err(),
+            Some(RenderError::Cancelled)
+        );
+
+        // Without the slow source, the abandoned render completes, and the renderer is usable again
+        map.set_style_json(
+            r##"{"version": 8, "sources": {}, "layers": [
+                {"id": "bg", "type": "background", "paint": {"background-color": "#0000ff"}}
+            ]}"##,
+        )
+        .unwrap();
+        let image = map
+            .render_static_cancellable(&CancellationToken::new())
+            .unwrap();
+        assert_eq!(decode(&image)[0], [0, 0, 255, 255]);
+        assert_eq!(decode(&map.render_static().unwrap())[0], [0, 0, 255, 255]);
+    }
+}
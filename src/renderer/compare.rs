@@ -0,0 +1,136 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::renderer::retile::{decode_rgba, RetileError};
+use crate::renderer::Image;
+
+/// Errors returned by [`Image::compare`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompareError {
+    /// One of the images could not be decoded as an 8-bit RGBA PNG.
+    Decode(String),
+    /// The images have different sizes.
+    SizeMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+}
+
+impl fmt::Display for CompareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode(e) => write!(f, "Unable to decode image: {e}"),
+            Self::SizeMismatch { expected, actual } => write!(
+                f,
+                "Image of {}x{} pixels cannot be compared to one of {}x{} pixels",
+                actual.0, actual.1, expected.0, expected.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CompareError {}
+
+fn decode(image: &Image) -> Result<(Vec<u8>, u32, u32), CompareError> {
+    decode_rgba(image.as_slice()).map_err(|e| match e {
+        RetileError::Decode(e) => CompareError::Decode(e),
+        e => CompareError::Decode(e.to_string()),
+    })
+}
+
+impl Image {
+    /// Difference to `other`, e.g. a golden image of a visual regression test.
+    ///
+    /// The metric is the mean absolute error over all RGBA channels, normalized to `0.0..=1.0`:
+    /// `0.0` for identical pixels, and `1.0` if every channel differs by the full range,
+    /// e.g. opaque white compared to transparent black. Anti-aliasing differences between
+    /// GPUs usually stay well below `0.01`. Only PNG images can be compared.
+    ///
+    /// # Errors
+    /// Returns an error if either image is not a PNG, or if their sizes differ.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn compare(&self, other: &Image) -> Result<f64, CompareError> {
+        let (pixels, width, height) = decode(self)?;
+        let (other_pixels, other_width, other_height) = decode(other)?;
+        if (width, height) != (other_width, other_height) {
+            return Err(CompareError::SizeMismatch {
+                expected: (other_width, other_height),
+                actual: (width, height),
+            });
+        }
+        if pixels.is_empty() {
+            return Ok(0.0);
+        }
+        let total: u64 = pixels
+            .iter()
+            .zip(&other_pixels)
+            .map(|(a, b)| u64::from(a.abs_diff(*b)))
+            .sum();
+        Ok(total as f64 / (pixels.len() as f64 * 255.0))
+    }
+
+    /// Write the encoded image to a file, e.g. `map.png`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::image_renderer::ImageData;
+    use crate::renderer::retile::encode_rgba;
+    use crate::renderer::ImageRendererOptions;
+
+    fn solid(color: [u8; 4], width: u32, height: u32) -> Image {
+        let pixels = color.repeat((width * height) as usize);
+        Image(ImageData::Vec(encode_rgba(&pixels, width, height).unwrap()))
+    }
+
+    #[test]
+    fn metric() {
+        let white = solid([255; 4], 4, 4);
+        let clear = solid([0; 4], 4, 4);
+        assert!(white.compare(&white).unwrap().abs() < f64::EPSILON);
+        assert!((white.compare(&clear).unwrap() - 1.0).abs() < f64::EPSILON);
+        let gray = solid([255, 255, 255, 0], 4, 4);
+        assert!((white.compare(&gray).unwrap() - 0.25).abs() < f64::EPSILON);
+
+        assert_eq!(
+            white.compare(&solid([255; 4], 2, 4)),
+            Err(CompareError::SizeMismatch {
+                expected: (2, 4),
+                actual: (4, 4),
+            })
+        );
+        let garbage = Image(ImageData::Vec(vec![1, 2, 3]));
+        assert!(matches!(
+            white.compare(&garbage),
+            Err(CompareError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn golden_image() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64);
+        let mut map = opts.build_static_renderer();
+        map.set_style_json(
+            r##"{
+                "version": 8,
+                "sources": {},
+                "layers": [{"id": "bg", "type": "background", "paint": {"background-color": "#fff"}}]
+            }"##,
+        )
+        .unwrap();
+        let image = map.render_static().unwrap();
+        let path = std::env::temp_dir().join("mln_golden.png");
+        image.save(&path).unwrap();
+        let golden = Image(ImageData::Vec(std::fs::read(&path).unwrap()));
+        assert!(image.compare(&golden).unwrap().abs() < f64::EPSILON);
+        assert!(image.compare(&solid([255; 4], 64, 64)).unwrap() < 0.01);
+    }
+}
@@ -0,0 +1,117 @@
+use std::ops::{BitOr, BitOrAssign};
+
+use crate::renderer::MapDebugOptions;
+
+/// Set of [`MapDebugOptions`] overlays, combined with `|`.
+///
+/// ```
+/// # use maplibre_native::{MapDebugFlags, MapDebugOptions};
+/// let flags = MapDebugOptions::TileBorders | MapDebugOptions::Collision;
+/// assert!(flags.contains(MapDebugOptions::Collision));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MapDebugFlags(u32);
+
+impl MapDebugFlags {
+    /// No debug overlays, same as [`MapDebugOptions::NoDebug`].
+    pub const NONE: Self = Self(0);
+
+    /// Whether all overlays of `option` are enabled.
+    #[must_use]
+    pub fn contains(self, option: impl Into<Self>) -> bool {
+        let option = option.into();
+        self.0 & option.0 == option.0
+    }
+
+    #[must_use]
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Bit values as used by MapLibre.
+    #[must_use]
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<MapDebugOptions> for MapDebugFlags {
+    fn from(value: MapDebugOptions) -> Self {
+        Self(value.repr)
+    }
+}
+
+impl<T: Into<MapDebugFlags>> BitOr<T> for MapDebugFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: T) -> Self {
+        Self(self.0 | rhs.into().0)
+    }
+}
+
+impl<T: Into<MapDebugFlags>> BitOrAssign<T> for MapDebugFlags {
+    fn bitor_assign(&mut self, rhs: T) {
+        self.0 |= rhs.into().0;
+    }
+}
+
+impl<T: Into<MapDebugFlags>> BitOr<T> for MapDebugOptions {
+    type Output = MapDebugFlags;
+
+    fn bitor(self, rhs: T) -> MapDebugFlags {
+        MapDebugFlags::from(self) | rhs
+    }
+}
+
+impl FromIterator<MapDebugOptions> for MapDebugFlags {
+    fn from_iter<I: IntoIterator<Item = MapDebugOptions>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(Self::NONE, |flags, option| flags | option)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::image_renderer::tests::decode;
+    use crate::renderer::ImageRendererOptions;
+
+    #[test]
+    fn combine() {
+        let flags = MapDebugOptions::TileBorders | MapDebugOptions::Collision;
+        assert_eq!(flags.bits(), 0b0001_0010);
+        assert!(flags.contains(MapDebugOptions::TileBorders));
+        assert!(flags.contains(MapDebugOptions::NoDebug));
+        assert!(!flags.contains(MapDebugOptions::Overdraw));
+        assert!(!flags.contains(MapDebugOptions::Overdraw | MapDebugOptions::Collision));
+
+        let mut flags = MapDebugFlags::NONE;
+        assert!(flags.is_empty());
+        flags |= MapDebugOptions::Timestamps;
+        assert_eq!(flags, MapDebugOptions::Timestamps.into());
+        let collected: MapDebugFlags = [MapDebugOptions::Timestamps, MapDebugOptions::Collision]
+            .into_iter()
+            .collect();
+        assert_eq!(collected, flags | MapDebugOptions::Collision);
+    }
+
+    #[test]
+    fn combined_overlays() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256).with_in_memory_cache();
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_camera(0.0, 30.0, 2.0, 0.0, 0.0).unwrap();
+        let mut render = |flags: MapDebugFlags| {
+            map.set_debug_flags(flags);
+            decode(&map.render_static().unwrap())
+        };
+        let borders = render(MapDebugOptions::TileBorders.into());
+        let collision = render(MapDebugOptions::Collision.into());
+        let both = render(MapDebugOptions::TileBorders | MapDebugOptions::Collision);
+        assert_ne!(both, borders);
+        assert_ne!(both, collision);
+        assert_ne!(render(MapDebugFlags::NONE), borders);
+    }
+}
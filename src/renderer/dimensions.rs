@@ -0,0 +1,138 @@
+//! Image size detection from the headers of the supported output formats.
+
+/// Width and height of a PNG, JPEG, or WebP image, read from its header without decoding it.
+pub(crate) fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    png_dimensions(data)
+        .or_else(|| jpeg_dimensions(data))
+        .or_else(|| webp_dimensions(data))
+}
+
+fn png_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    // The IHDR chunk must come first, right after the signature
+    if !data.starts_with(b"\x89PNG\r\n\x1a\n") || data.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    Some((be_u32(data.get(16..20)?), be_u32(data.get(20..24)?)))
+}
+
+fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    // Walk the marker segments until the start of frame, which holds the size
+    let mut pos = 2;
+    loop {
+        if *data.get(pos)? != 0xFF {
+            return None;
+        }
+        let marker = *data.get(pos + 1)?;
+        match marker {
+            // Fill bytes may precede a marker
+            0xFF => pos += 1,
+            // Start of frame markers, except for DHT, JPG, and DAC which share the range
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                let height = be_u16(data.get(pos + 5..pos + 7)?);
+                let width = be_u16(data.get(pos + 7..pos + 9)?);
+                return Some((u32::from(width), u32::from(height)));
+            }
+            _ => pos += 2 + usize::from(be_u16(data.get(pos + 2..pos + 4)?)),
+        }
+    }
+}
+
+fn webp_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if data.get(0..4)? != b"RIFF" || data.get(8..12)? != b"WEBP" {
+        return None;
+    }
+    match data.get(12..16)? {
+        // Lossy bitstream, the size follows the frame tag and the start code
+        b"VP8 " => {
+            if data.get(23..26)? != [0x9D, 0x01, 0x2A] {
+                return None;
+            }
+            let width = le_u16(data.get(26..28)?) & 0x3FFF;
+            let height = le_u16(data.get(28..30)?) & 0x3FFF;
+            Some((u32::from(width), u32::from(height)))
+        }
+        // Lossless bitstream, the size is packed into 14-bit fields after the signature byte
+        b"VP8L" => {
+            if *data.get(20)? != 0x2F {
+                return None;
+            }
+            let bits = le_u32(data.get(21..25)?);
+            Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+        }
+        // Extended format, with the canvas size stored as 24-bit values
+        b"VP8X" => Some((le_u24(data.get(24..27)?) + 1, le_u24(data.get(27..30)?) + 1)),
+        _ => None,
+    }
+}
+
+fn be_u16(bytes: &[u8]) -> u16 {
+    u16::from_be_bytes([bytes[0], bytes[1]])
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn le_u16(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn le_u24(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0])
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn png() {
+        let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        data.extend_from_slice(&640_u32.to_be_bytes());
+        data.extend_from_slice(&480_u32.to_be_bytes());
+        assert_eq!(image_dimensions(&data), Some((640, 480)));
+        assert_eq!(image_dimensions(&data[..20]), None);
+    }
+
+    #[test]
+    fn jpeg() {
+        let data = [
+            0xFF, 0xD8, // SOI
+            0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, // APP0 with 2 bytes of payload
+            0xFF, 0xC4, 0x00, 0x02, // DHT, must be skipped
+            0xFF, 0xC0, 0x00, 0x11, 0x08, 0x01, 0xE0, 0x02, 0x80, // SOF0, 640x480
+        ];
+        assert_eq!(image_dimensions(&data), Some((640, 480)));
+        assert_eq!(image_dimensions(&data[..14]), None);
+    }
+
+    #[test]
+    fn webp() {
+        let mut lossy = b"RIFF\0\0\0\0WEBPVP8 \0\0\0\0\0\0\0\x9d\x01\x2a".to_vec();
+        lossy.extend_from_slice(&640_u16.to_le_bytes());
+        lossy.extend_from_slice(&480_u16.to_le_bytes());
+        assert_eq!(image_dimensions(&lossy), Some((640, 480)));
+
+        let mut lossless = b"RIFF\0\0\0\0WEBPVP8L\0\0\0\0\x2f".to_vec();
+        // 14 bits each for the width and the height minus one
+        lossless.extend_from_slice(&(0x27F_u32 | (0x1DF << 14)).to_le_bytes());
+        assert_eq!(image_dimensions(&lossless), Some((640, 480)));
+
+        let mut extended = b"RIFF\0\0\0\0WEBPVP8X\0\0\0\0\0\0\0\0".to_vec();
+        extended.extend_from_slice(&[0x7F, 0x02, 0x00, 0xDF, 0x01, 0x00]);
+        assert_eq!(image_dimensions(&extended), Some((640, 480)));
+    }
+
+    #[test]
+    fn unknown() {
+        assert_eq!(image_dimensions(b""), None);
+        assert_eq!(image_dimensions(b"GIF89a\x80\x02\xe0\x01"), None);
+    }
+}
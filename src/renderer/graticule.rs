@@ -0,0 +1,106 @@
+use std::fmt::Write as _;
+
+/// Latitude at which Web Mercator ends. Lines beyond it would extend to infinity.
+const MAX_LAT: f64 = 85.051_128_779_806_59;
+
+/// Generate a GeoJSON `FeatureCollection` of meridians and parallels every `spacing` degrees.
+///
+/// Meridians start at the antimeridian, which is only included once as `-180`,
+/// and are cut at the Web Mercator latitude limit.
+/// The poles cannot be shown in Web Mercator, so parallels are only generated between them.
+/// Each line is densified to one vertex per `spacing` degrees.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+pub(crate) fn graticule_geojson(spacing: f64) -> String {
+    let steps = |from: f64, to: f64| {
+        let count = ((to - from) / spacing).ceil() as usize;
+        (0..count).map(move |idx| from + idx as f64 * spacing)
+    };
+    let mut lines: Vec<Vec<(f64, f64)>> = Vec::new();
+    for lon in steps(-180.0, 180.0) {
+        let mut line: Vec<_> = steps(-MAX_LAT, MAX_LAT).map(|lat| (lon, lat)).collect();
+        line.push((lon, MAX_LAT));
+        lines.push(line);
+    }
+    for lat in steps(0.0, 90.0).chain(steps(0.0, 90.0).skip(1).map(|lat| -lat)) {
+        let mut line: Vec<_> = steps(-180.0, 180.0).map(|lon| (lon, lat)).collect();
+        line.push((180.0, lat));
+        lines.push(line);
+    }
+
+    let mut json = String::from(r#"{"type":"FeatureCollection","features":["#);
+    for (idx, line) in lines.iter().enumerate() {
+        if idx > 0 {
+            json.push(',');
+        }
+        json.push_str(
+            r#"{"type":"Feature","properties":{},"geometry":{"type":"LineString","coordinates":["#,
+        );
+        for (idx, (lon, lat)) in line.iter().enumerate() {
+            if idx > 0 {
+                json.push(',');
+            }
+            write!(json, "[{lon},{lat}]").unwrap();
+        }
+        json.push_str("]}}");
+    }
+    json.push_str("]}");
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::{ImageRendererOptions, Rgba};
+
+    #[test]
+    fn line_count() {
+        let json = graticule_geojson(30.0);
+        // 12 meridians, and parallels at 0, ±30, ±60
+        assert_eq!(json.matches("LineString").count(), 12 + 5);
+        assert!(json.contains("[-180,-85.05112877980659]"));
+        assert!(!json.contains("[180,-85"));
+        assert!(!json.contains(",90]"));
+    }
+
+    #[test]
+    fn world_graticule() {
+        let color = Rgba {
+            r: 255,
+            g: 0,
+            b: 255,
+            a: 255,
+        };
+        let mut opts = ImageRendererOptions::new();
+        // Render at a higher pixel ratio, so that lines cover whole pixels
+        opts.with_size(512, 512)
+            .with_pixel_ratio(2.0)
+            .with_graticule(30.0, color);
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_camera(0.0, 0.0, 0.0, 0.0, 0.0).unwrap();
+        let pixels = crate::renderer::image_renderer::tests::decode(&map.render_static().unwrap());
+
+        let is_line = |[r, g, b, _]: [u8; 4]| r > 200 && g < 80 && b > 200;
+        let count_runs = |line: Vec<[u8; 4]>| {
+            let mut runs = 0;
+            let mut prev = false;
+            for px in line {
+                let current = is_line(px);
+                runs += usize::from(current && !prev);
+                prev = current;
+            }
+            runs
+        };
+        // A row at about 15°N, skipping the antimeridian at the image edges
+        let row = pixels[460 * 1024 + 8..461 * 1024 - 8].to_vec();
+        assert_eq!(count_runs(row), 11);
+        // A column at 15°E
+        let column = (0..1024).map(|y| pixels[y * 1024 + 554]).collect();
+        assert_eq!(count_runs(column), 5);
+    }
+}
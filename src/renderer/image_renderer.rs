@@ -1,92 +1,2669 @@
 use std::f64::consts::PI;
+use std::fmt::{self, Write as _};
 use std::marker::PhantomData;
+use std::ops::{Bound, Range, RangeBounds};
 use std::path::Path;
+use std::pin::Pin;
+use std::time::Duration;
 
 use cxx::{CxxString, UniquePtr};
 
 use crate::renderer::bridge::ffi;
-use crate::renderer::{ImageRendererOptions, MapDebugOptions, MapMode};
+use crate::renderer::camera::{
+    check, check_bounds, check_center, check_padding, CameraValueError, POSITIVE,
+};
+use crate::renderer::dimensions::image_dimensions;
+use crate::renderer::retile::{encode_jpeg, encode_rgba, encode_webp};
+use crate::renderer::{
+    CameraState, CollisionBox, FeatureInfo, ImageRendererOptions, LatLngBounds, MapDebugFlags,
+    MapMode, Padding, ScreenRect, StyleError, TileSize,
+};
+use crate::tile::TileId;
+use crate::{supported_output_formats, Error, ImageFormat, OutputFormat};
 
 /// A rendered map image.
 ///
-/// The image is stored as a PNG byte array in a buffer allocated by the C++ code.
-pub struct Image(UniquePtr<CxxString>);
+/// The image is stored as an encoded byte array, PNG unless another [`ImageFormat`] was requested,
+/// either in a buffer allocated by the C++ code, or in a Rust buffer if the image was produced
+/// on the Rust side, e.g. by [`Image::to_tiles`].
+pub struct Image(pub(crate) ImageData);
+
+pub(crate) enum ImageData {
+    Cxx(UniquePtr<CxxString>),
+    Vec(Vec<u8>),
+}
 
 impl Image {
     #[must_use]
     pub fn as_slice(&self) -> &[u8] {
-        self.0.as_bytes()
+        match &self.0 {
+            ImageData::Cxx(data) => data.as_bytes(),
+            ImageData::Vec(data) => data,
+        }
+    }
+
+    /// Size of the encoded image in bytes, e.g. for a `Content-Length` header.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.as_slice().is_empty()
+    }
+
+    /// Width and height in physical pixels, read from the image header without decoding it.
+    ///
+    /// Returns `None` if the data is not a PNG, JPEG, or WebP image.
+    #[must_use]
+    pub fn dimensions(&self) -> Option<(u32, u32)> {
+        image_dimensions(self.as_slice())
+    }
+
+    /// Take the encoded bytes, e.g. to send them to another thread or keep them in a `'static` cache.
+    ///
+    /// Images encoded by MapLibre are held in a C++ buffer, which is copied into a new `Vec`,
+    /// costing one allocation and a copy of the whole image. Images produced on the Rust side,
+    /// e.g. by [`Image::to_tiles`], are returned without copying.
+    #[must_use]
+    pub fn into_vec(self) -> Vec<u8> {
+        match self.0 {
+            ImageData::Cxx(data) => data.as_bytes().to_vec(),
+            ImageData::Vec(data) => data,
+        }
+    }
+}
+
+impl AsRef<[u8]> for Image {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl From<Image> for Vec<u8> {
+    /// See [`Image::into_vec`].
+    fn from(image: Image) -> Self {
+        image.into_vec()
+    }
+}
+
+/// A rendered map image as raw RGBA pixels, 8 bits per channel, without PNG encoding.
+///
+/// MapLibre renders into a premultiplied buffer, but the pixels are converted
+/// to straight (not premultiplied) alpha, the same convention as the PNG output.
+pub struct RawImage {
+    data: ImageData,
+    width: u32,
+    height: u32,
+}
+
+impl RawImage {
+    /// Pixels in row-major order starting at the top-left corner, 4 bytes per pixel.
+    #[must_use]
+    pub fn as_rgba(&self) -> &[u8] {
+        match &self.data {
+            ImageData::Cxx(data) => data.as_bytes(),
+            ImageData::Vec(data) => data,
+        }
+    }
+
+    /// Width in physical pixels, i.e. multiplied by the pixel ratio.
+    #[must_use]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height in physical pixels, i.e. multiplied by the pixel ratio.
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Encode the pixels into an image of the given format.
+    ///
+    /// # Errors
+    /// Returns an error if the encoder failed, e.g. because a JPEG image
+    /// would be wider or taller than 65535 pixels.
+    pub fn encode(&self, format: ImageFormat) -> Result<Image, RenderError> {
+        let (pixels, width, height) = (self.as_rgba(), self.width, self.height);
+        let data = match format {
+            ImageFormat::Png => encode_rgba(pixels, width, height).map_err(|e| e.to_string()),
+            ImageFormat::Jpeg { quality } => encode_jpeg(pixels, width, height, quality),
+            ImageFormat::Webp { .. } => encode_webp(pixels, width, height),
+        };
+        data.map(|data| Image(ImageData::Vec(data)))
+            .map_err(RenderError::Encode)
+    }
+
+    /// Shrink the image by an integer `factor`, averaging each block of pixels.
+    ///
+    /// Colors are weighted by their alpha, so that transparent pixels do not darken the edges.
+    #[allow(clippy::cast_possible_truncation)]
+    fn downscale(&self, factor: u32) -> Self {
+        let (width, height) = (self.width / factor, self.height / factor);
+        let pixels = self.as_rgba();
+        let (row_len, factor) = (self.width as usize * 4, factor as usize);
+        let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                let (mut color, mut alpha) = ([0_u32; 3], 0_u32);
+                for row in y * factor..(y + 1) * factor {
+                    let start = row * row_len + x * factor * 4;
+                    for pixel in pixels[start..start + factor * 4].chunks_exact(4) {
+                        let a = u32::from(pixel[3]);
+                        for (sum, &c) in color.iter_mut().zip(pixel) {
+                            *sum += u32::from(c) * a;
+                        }
+                        alpha += a;
+                    }
+                }
+                data.extend(color.map(|sum| sum.checked_div(alpha).unwrap_or(0) as u8));
+                data.push((alpha / (factor * factor) as u32) as u8);
+            }
+        }
+        Self {
+            data: ImageData::Vec(data),
+            width,
+            height,
+        }
+    }
+}
+
+/// Errors returned when rendering a map.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderError {
+    /// The style could not be fetched or parsed, e.g. because of a bad style URL.
+    StyleLoad(String),
+    /// A resource needed by the style, e.g. a tile or a sprite, could not be fetched.
+    Network(String),
+    /// The graphics context could not be created or used.
+    GpuContext(String),
+    /// The render completed without producing an image.
+    Empty,
+    /// All workers of a [`RenderService`](crate::RenderService) are busy and its queue is full.
+    /// The request may be retried later.
+    QueueFull,
+    /// The worker handling the request stopped before producing a result, e.g. because it panicked.
+    WorkerStopped,
+    /// The rendered pixels could not be encoded.
+    Encode(String),
+    /// The cache database could not be read or written.
+    Cache(String),
+    /// A resource request took longer than the limit set by
+    /// [`ImageRendererOptions::with_request_timeout`](crate::ImageRendererOptions::with_request_timeout),
+    /// or a continuous map was not loaded within the limit set by
+    /// [`ImageRendererOptions::with_frame_timeout`](crate::ImageRendererOptions::with_frame_timeout).
+    Timeout(String),
+    /// The render was stopped by its [`CancellationToken`](crate::CancellationToken).
+    Cancelled,
+    /// The buffer passed to [`ImageRenderer::render_rgba_into`] does not have the size of the image, in bytes.
+    BufferSize { expected: usize, actual: usize },
+    /// A camera value of the request is invalid, e.g. a latitude outside of `[-90, 90]`.
+    InvalidValue(CameraValueError),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StyleLoad(e) => write!(f, "Unable to load style: {e}"),
+            Self::Network(e) => write!(f, "Unable to load resource: {e}"),
+            Self::GpuContext(e) => write!(f, "Graphics context error: {e}"),
+            Self::Empty => f.write_str("Render produced no image"),
+            Self::QueueFull => f.write_str("Render queue is full"),
+            Self::WorkerStopped => {
+                f.write_str("Render worker stopped before finishing the request")
+            }
+            Self::Encode(e) => write!(f, "Unable to encode image: {e}"),
+            Self::Cache(e) => write!(f, "Cache database error: {e}"),
+            Self::Timeout(e) => write!(f, "Resource request timed out: {e}"),
+            Self::Cancelled => f.write_str("Render was cancelled"),
+            Self::BufferSize { expected, actual } => write!(
+                f,
+                "Pixel buffer has {actual} bytes, but the image needs {expected} bytes"
+            ),
+            Self::InvalidValue(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+/// Error returned by [`ImageRenderer::set_style_url`] for an unsupported URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyleUrlError {
+    /// The rejected URL
+    pub url: String,
+    /// The scheme of the URL, or `None` if it has no `scheme://` prefix
+    pub scheme: Option<String>,
+}
+
+impl fmt::Display for StyleUrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.scheme {
+            Some(scheme) => write!(
+                f,
+                "Unsupported scheme {scheme:?} in style URL {:?}",
+                self.url
+            ),
+            None => write!(f, "Style URL {:?} has no scheme, e.g. https://", self.url),
+        }
+    }
+}
+
+impl std::error::Error for StyleUrlError {}
+
+/// URL schemes MapLibre can load a style from, in addition to the configured URI scheme alias.
+const STYLE_URL_SCHEMES: &[&str] = &["http", "https", "file", "mapbox", "maplibre", "asset"];
+
+/// Check that MapLibre can load a style from `url`, given the configured URI scheme alias.
+pub(crate) fn check_style_url(url: &str, scheme_alias: &str) -> Result<(), StyleUrlError> {
+    let scheme = url
+        .split_once("://")
+        .map(|(scheme, _)| scheme)
+        .filter(|scheme| {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        });
+    let supported = scheme.is_some_and(|scheme| {
+        STYLE_URL_SCHEMES
+            .iter()
+            .chain([&scheme_alias])
+            .any(|v| !v.is_empty() && v.eq_ignore_ascii_case(scheme))
+    });
+    if supported {
+        Ok(())
+    } else {
+        Err(StyleUrlError {
+            url: url.to_string(),
+            scheme: scheme.map(ToString::to_string),
+        })
+    }
+}
+
+/// Build a `file://` URL for an absolute path, percent-encoding it as MapLibre decodes it.
+///
+/// Windows paths use backslashes and start with a drive letter, e.g. `C:\styles\a.json`
+/// becomes `file:///C:/styles/a.json`.
+fn file_url(path: &str, windows: bool) -> String {
+    let mut url = String::from("file://");
+    if windows && !path.starts_with(['\\', '/']) {
+        url.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'\\' if windows => url.push('/'),
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                url.push(char::from(byte));
+            }
+            _ => {
+                let _ = write!(url, "%{byte:02X}");
+            }
+        }
     }
+    url
 }
 
+/// Maximum pitch in degrees supported by MapLibre.
+pub(crate) const MAX_PITCH: f64 = 60.0;
+
+/// Maximum zoom level supported by MapLibre.
+const MAX_ZOOM: f64 = 25.5;
+
 /// Internal state type to render a static map image.
 pub struct Static;
 /// Internal state type to render a map tile.
 pub struct Tile;
+/// Internal state type to render frames of a continuously updating map.
+pub struct Continuous;
 
 /// Configuration options for a tile server.
+///
+/// A renderer is neither `Send` nor `Sync`: MapLibre binds each map to the run loop
+/// of the thread that created it, and each render blocks that thread until all resources
+/// are loaded. Any number of renderers may be used on the same thread, they share its run loop.
+/// To render from async code without blocking the executor, use a
+/// [`RenderService`](crate::RenderService), which keeps its renderers on worker threads.
 pub struct ImageRenderer<S>(
     pub(crate) UniquePtr<ffi::MapRenderer>,
     pub(crate) PhantomData<S>,
+    /// URI scheme alias accepted by [`ImageRenderer::set_style_url`]
+    pub(crate) String,
 );
 
 impl<S> ImageRenderer<S> {
     /// Set the style URL for the map.
+    ///
+    /// Supported schemes are `http`, `https`, `file`, `mapbox`, `maplibre`, `asset`,
+    /// the configured URI scheme alias, and the schemes with a
+    /// [resolver](Self::set_scheme_resolver). Use [`set_style_path`](Self::set_style_path)
+    /// for local files without a scheme.
+    ///
+    /// # Errors
+    /// Returns an error if the URL has no scheme, or if the scheme is not supported.
     // FIXME: without this call, renderer just hangs
-    pub fn set_style_url(&mut self, url: &str) -> &mut Self {
-        // FIXME: return a result instead of panicking
-        assert!(url.contains("://"));
+    pub fn set_style_url(&mut self, url: &str) -> Result<&mut Self, StyleUrlError> {
+        check_style_url(url, &self.2).or_else(|e| match &e.scheme {
+            Some(scheme) if self.has_scheme_resolver(scheme) => Ok(()),
+            _ => Err(e),
+        })?;
         ffi::MapRenderer_setStyleUrl(self.0.pin_mut(), url);
-        self
+        Ok(self)
     }
 
-    pub fn set_style_path(&mut self, path: impl AsRef<Path>) -> &mut Self {
-        // TODO: check if the file exists?
-        // FIXME: return a result instead of panicking
-        let path = path.as_ref().to_str().expect("Path is not valid UTF-8");
-        ffi::MapRenderer_setStyleUrl(self.0.pin_mut(), &format!("file://{path}"));
-        self
+    /// Load a style from a JSON document, e.g. one generated at runtime,
+    /// without writing it to a file first.
+    ///
+    /// # Errors
+    /// Returns an error if the string is empty, is not valid JSON, or is not a JSON object.
+    pub fn set_style_json(&mut self, json: &str) -> Result<&mut Self, StyleError> {
+        if json.trim().is_empty() {
+            return Err(StyleError::Invalid("Style JSON is empty".to_string()));
+        }
+        let mut error = String::new();
+        if ffi::MapRenderer_setStyleJson(self.0.pin_mut(), json, &mut error) {
+            Ok(self)
+        } else {
+            Err(StyleError::Invalid(error))
+        }
     }
 
+    /// Load a style from a local file.
+    ///
+    /// Relative paths are resolved against the current directory. The file is only parsed
+    /// by the next render, but it is opened right away, as MapLibre waits indefinitely
+    /// for a style file that cannot be read.
+    ///
+    /// # Errors
+    /// Returns [`Error::NonUtf8Path`] if the path is not valid UTF-8,
+    /// or [`Error::Io`] if the file does not exist or cannot be read.
+    pub fn set_style_path(&mut self, path: impl AsRef<Path>) -> Result<&mut Self, Error> {
+        let path = std::path::absolute(path.as_ref())?;
+        let Some(path_str) = path.to_str() else {
+            return Err(Error::NonUtf8Path(path));
+        };
+        if !std::fs::File::open(&path)?.metadata()?.is_file() {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} is not a file", path.display()),
+            )));
+        }
+        ffi::MapRenderer_setStyleUrl(self.0.pin_mut(), &file_url(path_str, cfg!(windows)));
+        Ok(self)
+    }
+
+    /// Center the map on `lon`, `lat` in degrees, matching the GeoJSON coordinate order.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidValue`] if the latitude is not in `[-90, 90]`, or if the longitude is not finite.
     pub fn set_camera(
         &mut self,
+        lon: f64,
         lat: f64,
+        zoom: f64,
+        bearing: f64,
+        pitch: f64,
+    ) -> Result<&mut Self, Error> {
+        check_center(lon, lat)?;
+        Ok(self.set_camera_unchecked(lon, lat, zoom, bearing, pitch))
+    }
+
+    /// Same as [`set_camera`](Self::set_camera), for a center already known to be valid.
+    pub(crate) fn set_camera_unchecked(
+        &mut self,
         lon: f64,
+        lat: f64,
         zoom: f64,
         bearing: f64,
         pitch: f64,
     ) -> &mut Self {
-        ffi::MapRenderer_setCamera(self.0.pin_mut(), lat, lon, zoom, bearing, pitch);
+        ffi::MapRenderer_setCamera(self.0.pin_mut(), lon, lat, zoom, bearing, pitch);
         self
     }
 
-    pub fn set_debug_flags(&mut self, flags: MapDebugOptions) -> &mut Self {
-        ffi::MapRenderer_setDebugFlags(self.0.pin_mut(), flags);
+    /// Same as [`set_camera`](Self::set_camera), but keeping `padding` free along the viewport edges
+    /// in addition to the one of [`set_padding`](Self::set_padding), for this camera change only.
+    ///
+    /// If `anchor` is set, the center is placed at that position in logical pixels from the top left
+    /// corner instead of the middle of the viewport, and `padding` is ignored.
+    /// Afterwards, [`get_camera`](Self::get_camera) reports the position at the middle of the viewport,
+    /// like for [`set_camera_to_bounds`](Self::set_camera_to_bounds).
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidValue`] if the center of the camera is invalid, see [`set_camera`](Self::set_camera),
+    /// if the padding is negative, or if the anchor is outside the viewport.
+    pub fn set_camera_with(
+        &mut self,
+        camera: CameraState,
+        padding: Padding,
+        anchor: Option<(f64, f64)>,
+    ) -> Result<&mut Self, Error> {
+        check_center(camera.lon, camera.lat)?;
+        let padding = check_padding(padding)?;
+        let (anchor_x, anchor_y) = anchor.unwrap_or_default();
+        if anchor.is_some() {
+            let (width, height) = self.size();
+            check("anchor x", anchor_x, Some(0.0..=f64::from(width)))?;
+            check("anchor y", anchor_y, Some(0.0..=f64::from(height)))?;
+        }
+        ffi::MapRenderer_setCameraWithOptions(
+            self.0.pin_mut(),
+            camera,
+            padding,
+            anchor.is_some(),
+            anchor_x,
+            anchor_y,
+        );
+        Ok(self)
+    }
+
+    /// Center and zoom the map so that the given bounds, in degrees, fill the viewport.
+    ///
+    /// Mirrors MapLibre's `cameraForLatLngBounds` for the configured size, keeping `padding` free
+    /// along the viewport edges. Bearing and pitch are reset to zero. Bounds with `west > east`
+    /// cross the antimeridian. If the bounds are a single point, the map is centered on it,
+    /// and the zoom is left unchanged.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidValue`] if a longitude is not in `[-180, 180]`, a latitude is not
+    /// in `[-90, 90]`, `south` is above `north`, or the padding is negative.
+    pub fn set_camera_to_bounds(
+        &mut self,
+        west: f64,
+        south: f64,
+        east: f64,
+        north: f64,
+        padding: Padding,
+    ) -> Result<&mut Self, Error> {
+        let bounds = LatLngBounds {
+            west,
+            south,
+            east,
+            north,
+        };
+        check_bounds(bounds, true)?;
+        let padding = check_padding(padding)?;
+        ffi::MapRenderer_setCameraToBounds(self.0.pin_mut(), west, south, east, north, padding);
+        Ok(self)
+    }
+
+    /// Keep `padding` free along the viewport edges, e.g. where a sidebar covers the image.
+    ///
+    /// The camera center is placed in the middle of the remaining area, so the center set by
+    /// [`set_camera`](Self::set_camera) is offset on screen, and [`set_camera_to_bounds`](Self::set_camera_to_bounds)
+    /// fits the bounds into that area. The padding is kept by all later camera changes,
+    /// until it is replaced by another call, e.g. with [`Padding::default`] to remove it.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidValue`] if the padding is negative.
+    pub fn set_padding(&mut self, padding: Padding) -> Result<&mut Self, Error> {
+        let padding = check_padding(padding)?;
+        ffi::MapRenderer_setPadding(self.0.pin_mut(), padding);
+        Ok(self)
+    }
+
+    /// Move the center of the map to `lon`, `lat` in degrees, keeping the rest of the camera.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidValue`] if the latitude is not in `[-90, 90]`, or if the longitude is not finite.
+    pub fn set_center(&mut self, lon: f64, lat: f64) -> Result<&mut Self, Error> {
+        let camera = self.get_camera();
+        self.set_camera(lon, lat, camera.zoom, camera.bearing, camera.pitch)
+    }
+
+    /// Zoom the map, keeping the rest of the camera, e.g. to animate a zoom sequence.
+    /// The zoom is clamped to the range allowed by the map, see [`zoom_range`](Self::zoom_range).
+    pub fn set_zoom(&mut self, zoom: f64) -> &mut Self {
+        let camera = self.get_camera();
+        let (min, max) = self.zoom_range();
+        let zoom = zoom.clamp(min, max);
+        self.set_camera_unchecked(camera.lon, camera.lat, zoom, camera.bearing, camera.pitch)
+    }
+
+    /// Minimum and maximum zoom levels allowed by the map, `0` and `25.5` unless restricted.
+    #[must_use]
+    pub fn zoom_range(&self) -> (f64, f64) {
+        let (mut min, mut max) = (0.0, 0.0);
+        ffi::MapRenderer_getZoomRange(&self.0, &mut min, &mut max);
+        (min, max)
+    }
+
+    /// Restrict the zoom of the map to `min..=max`, e.g. to stop at the maximum zoom of a tileset.
+    ///
+    /// The current camera is clamped right away, and so are all later camera changes.
+    /// MapLibre supports zoom levels from `0` to `25.5`.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidValue`] if `min` or `max` is outside that range, or if `min` is above `max`.
+    pub fn set_zoom_range(&mut self, min: f64, max: f64) -> Result<&mut Self, Error> {
+        check("minimum zoom", min, Some(0.0..=MAX_ZOOM))?;
+        check("maximum zoom", max, Some(min..=MAX_ZOOM))?;
+        ffi::MapRenderer_setZoomRange(self.0.pin_mut(), min, max);
+        Ok(self)
+    }
+
+    /// Keep the center of the map within `bounds`, or anywhere in the world if `None`.
+    ///
+    /// The current camera is moved into the bounds right away, and so are all later camera
+    /// changes, e.g. to lock renders to one area. Only the center is constrained,
+    /// so the viewport may still show areas outside of the bounds.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidValue`] if the bounds are outside of the world,
+    /// or if `west` is above `east` or `south` is above `north`.
+    pub fn set_lat_lng_bounds(&mut self, bounds: Option<LatLngBounds>) -> Result<&mut Self, Error> {
+        let bounds = check_bounds(
+            bounds.unwrap_or(LatLngBounds {
+                west: -180.0,
+                south: -90.0,
+                east: 180.0,
+                north: 90.0,
+            }),
+            false,
+        )?;
+        ffi::MapRenderer_setLatLngBounds(
+            self.0.pin_mut(),
+            bounds.west,
+            bounds.south,
+            bounds.east,
+            bounds.north,
+        );
+        Ok(self)
+    }
+
+    /// Rotate the map to `degrees` clockwise from north, keeping the rest of the camera.
+    /// The bearing is normalized into `[0, 360)`.
+    pub fn set_bearing(&mut self, degrees: f64) -> &mut Self {
+        let camera = self.get_camera();
+        let bearing = degrees.rem_euclid(360.0);
+        self.set_camera_unchecked(camera.lon, camera.lat, camera.zoom, bearing, camera.pitch)
+    }
+
+    /// Tilt the map to `degrees` away from the vertical, keeping the rest of the camera.
+    /// The pitch is clamped to `[0, 60]`, the range supported by MapLibre.
+    pub fn set_pitch(&mut self, degrees: f64) -> &mut Self {
+        let camera = self.get_camera();
+        let pitch = degrees.clamp(0.0, MAX_PITCH);
+        self.set_camera_unchecked(camera.lon, camera.lat, camera.zoom, camera.bearing, pitch)
+    }
+
+    /// Whether the style and all resources needed for the current camera are loaded,
+    /// and no transitions are running.
+    ///
+    /// [`Static`] and [`Tile`] renders always wait for this before returning an image,
+    /// so this is mostly useful with [`Continuous`] renderers, see [`ImageRenderer::wait_until_idle`].
+    #[must_use]
+    pub fn is_fully_loaded(&self) -> bool {
+        ffi::MapRenderer_isFullyLoaded(&self.0)
+    }
+
+    /// The current camera, as resolved by MapLibre.
+    ///
+    /// This may differ from the values passed to [`set_camera`](Self::set_camera),
+    /// e.g. when the center is constrained by the [`ConstrainMode`](crate::ConstrainMode),
+    /// and it reports the result of [`set_camera_to_bounds`](Self::set_camera_to_bounds).
+    /// Camera changes are applied immediately, so the state is final as soon as they return.
+    #[must_use]
+    pub fn get_camera(&self) -> CameraState {
+        ffi::MapRenderer_getCamera(&self.0)
+    }
+
+    /// Draw debug overlays, replacing any previously set.
+    /// Accepts a single [`MapDebugOptions`](crate::MapDebugOptions), or several combined with `|`.
+    pub fn set_debug_flags(&mut self, flags: impl Into<MapDebugFlags>) -> &mut Self {
+        ffi::MapRenderer_setDebugFlags(self.0.pin_mut(), flags.into().bits());
         self
     }
+
+    /// Resize the map, overriding [`ImageRendererOptions::with_size`](crate::ImageRendererOptions::with_size).
+    ///
+    /// The size is in logical pixels, so images are still scaled by the configured pixel ratio.
+    /// The camera center and zoom are kept, so a larger size shows more of the map around it.
+    /// The new size applies from the next render.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidValue`] if the width or the height is zero.
+    pub fn set_size(&mut self, width: u32, height: u32) -> Result<&mut Self, Error> {
+        check("width", f64::from(width), Some(POSITIVE))?;
+        check("height", f64::from(height), Some(POSITIVE))?;
+        ffi::MapRenderer_setSize(self.0.pin_mut(), width, height);
+        Ok(self)
+    }
+
+    /// Current size of the map in logical pixels, see [`set_size`](Self::set_size).
+    #[must_use]
+    pub fn size(&self) -> (u32, u32) {
+        let (mut width, mut height) = (0, 0);
+        ffi::MapRenderer_getSize(&self.0, &mut width, &mut height);
+        (width, height)
+    }
+
+    /// Image pixels per logical pixel, see [`ImageRendererOptions::with_pixel_ratio`](crate::ImageRendererOptions::with_pixel_ratio).
+    #[must_use]
+    pub fn pixel_ratio(&self) -> f32 {
+        ffi::MapRenderer_getPixelRatio(&self.0)
+    }
+
+    /// Size of the rendered images in physical pixels, i.e. the [size](Self::size) scaled by the pixel ratio.
+    #[must_use]
+    pub fn image_size(&self) -> (u32, u32) {
+        let (mut width, mut height) = (0, 0);
+        ffi::MapRenderer_getImageSize(&self.0, &mut width, &mut height);
+        (width, height)
+    }
+
+    /// Screen-space bounding box of a feature's geometry for the current camera.
+    ///
+    /// The feature is looked up by its ID among the loaded tiles of `source`,
+    /// so this should be called after a render. `source_layer` is required for vector sources.
+    /// Numeric IDs are matched by their decimal representation.
+    /// The rectangle is not clipped to the viewport, and may extend past its edges.
+    /// Returns `None` if the feature was not found, or if it is entirely off-screen.
+    #[must_use]
+    pub fn feature_screen_bounds(
+        &mut self,
+        source: &str,
+        source_layer: Option<&str>,
+        feature_id: &str,
+    ) -> Option<ScreenRect> {
+        let mut bounds = ScreenRect::default();
+        ffi::MapRenderer_featureScreenBounds(
+            &self.0,
+            source,
+            source_layer.unwrap_or_default(),
+            feature_id,
+            &mut bounds,
+        )
+        .then_some(bounds)
+    }
+
+    /// Features rendered at `x`, `y` by the last render, topmost layer first.
+    ///
+    /// Coordinates are in logical pixels of the viewport, like [`ScreenRect`], so pixel
+    /// coordinates of the rendered image must be divided by the pixel ratio first.
+    /// Returns an empty list if nothing was rendered there, or if nothing was rendered yet.
+    #[must_use]
+    pub fn query_rendered_features(&self, x: f64, y: f64) -> Vec<FeatureInfo> {
+        ffi::MapRenderer_queryRenderedFeatures(&self.0, x, y)
+    }
+
+    /// Override the relief exaggeration of the style, e.g. for a more dramatic terrain.
+    ///
+    /// MapLibre Native does not render 3D terrain, so this sets the `hillshade-exaggeration`
+    /// of all hillshade layers, and has no effect on styles without them.
+    /// The override persists across style changes.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidValue`] if `factor` is outside `0.0..=1.0`, the range of the style
+    /// specification. The hillshade shader of MapLibre Native renders larger values like `1.0`,
+    /// so they are rejected rather than silently having no further effect.
+    pub fn set_terrain_exaggeration(&mut self, factor: f64) -> Result<&mut Self, Error> {
+        let factor = check("terrain exaggeration", factor, Some(0.0..=1.0))?;
+        ffi::MapRenderer_setTerrainExaggeration(self.0.pin_mut(), factor);
+        Ok(self)
+    }
+
+    /// Collision boxes of all symbols considered during the label placement of the last render.
+    ///
+    /// Unlike the [`MapDebugOptions::Collision`](crate::MapDebugOptions::Collision) overlay, this reports the geometry as data,
+    /// including symbols that were hidden. Symbols with both text and an icon produce two boxes.
+    /// MapLibre does not keep feature IDs in its placement results,
+    /// so symbols are identified by their text content and layer instead.
+    /// Returns an empty list if nothing has been rendered yet.
+    #[must_use]
+    pub fn collision_info(&mut self) -> Vec<CollisionBox> {
+        ffi::MapRenderer_collisionBoxes(&self.0)
+    }
+
+    /// Wait until all resources fetched so far are stored in the cache database, and compact it.
+    ///
+    /// MapLibre writes to the cache database at the configured cache path in the background,
+    /// so it may lag behind the renders. Once this returns, the file is complete and can be
+    /// inspected or copied. Dropping the renderer also waits for pending writes,
+    /// but does not compact the database.
+    ///
+    /// # Errors
+    /// Returns an error if the cache database could not be written.
+    pub fn flush(&mut self) -> Result<(), RenderError> {
+        let mut error = String::new();
+        if ffi::MapRenderer_flush(self.0.pin_mut(), &mut error) {
+            Ok(())
+        } else {
+            Err(RenderError::Cache(error))
+        }
+    }
+}
+
+impl<S> ImageRenderer<S> {
+    fn render(&mut self) -> Result<Image, RenderError> {
+        self.render_with(ffi::MapRenderer_render)
+    }
+
+    fn render_with(
+        &mut self,
+        render: fn(
+            Pin<&mut ffi::MapRenderer>,
+            &mut ffi::RenderStatus,
+            &mut String,
+        ) -> UniquePtr<CxxString>,
+    ) -> Result<Image, RenderError> {
+        let mut status = ffi::RenderStatus::Ok;
+        let mut error = String::new();
+        let data = render(self.0.pin_mut(), &mut status, &mut error);
+        check_status(status, error)?;
+        Ok(Image(ImageData::Cxx(data)))
+    }
+
+    fn render_raw(&mut self) -> Result<RawImage, RenderError> {
+        let mut status = ffi::RenderStatus::Ok;
+        let mut error = String::new();
+        let (mut width, mut height) = (0, 0);
+        let data = ffi::MapRenderer_renderRaw(
+            self.0.pin_mut(),
+            &mut status,
+            &mut error,
+            &mut width,
+            &mut height,
+        );
+        check_status(status, error)?;
+        Ok(RawImage {
+            data: ImageData::Cxx(data),
+            width,
+            height,
+        })
+    }
+}
+
+pub(crate) fn check_status(status: ffi::RenderStatus, error: String) -> Result<(), RenderError> {
+    match status {
+        ffi::RenderStatus::Ok => Ok(()),
+        ffi::RenderStatus::StyleLoad => Err(RenderError::StyleLoad(error)),
+        ffi::RenderStatus::Network => Err(RenderError::Network(error)),
+        ffi::RenderStatus::GpuContext => Err(RenderError::GpuContext(error)),
+        ffi::RenderStatus::Cache => Err(RenderError::Cache(error)),
+        ffi::RenderStatus::Timeout => Err(RenderError::Timeout(error)),
+        ffi::RenderStatus::Cancelled => Err(RenderError::Cancelled),
+        _ => Err(RenderError::Empty),
+    }
 }
 
 impl ImageRenderer<Static> {
-    pub fn render_static(&mut self) -> Image {
-        Image(ffi::MapRenderer_render(self.0.pin_mut()))
+    /// Render the map for the current camera.
+    ///
+    /// # Errors
+    /// Returns an error if the style or one of its resources could not be loaded,
+    /// or if the graphics backend failed.
+    pub fn render_static(&mut self) -> Result<Image, RenderError> {
+        self.render()
+    }
+
+    /// Same as [`render_static`](Self::render_static), but at `ratio` image pixels per logical pixel
+    /// instead of the configured [`with_pixel_ratio`](crate::ImageRendererOptions::with_pixel_ratio),
+    /// e.g. to serve both 1x and 2x requests with the same renderer.
+    ///
+    /// The size set by [`set_size`](Self::set_size) and returned by [`size`](Self::size)
+    /// stays in logical pixels, and the camera shows the same area at any ratio.
+    /// The image has the logical size multiplied by `ratio`, rounded to whole pixels.
+    /// Sprites and raster tiles are still loaded for the configured
+    /// [resource pixel ratio](crate::ImageRendererOptions::with_resource_pixel_ratio),
+    /// so icons may be blurry when rendering well above it.
+    /// The configured ratio applies again from the next render.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidValue`] if `ratio` is not a finite positive value,
+    /// and otherwise the errors of [`render_static`](Self::render_static).
+    pub fn render_static_scaled(&mut self, ratio: f32) -> Result<Image, Error> {
+        check("pixel ratio", f64::from(ratio), Some(POSITIVE))?;
+        let (width, height) = self.size();
+        ffi::MapRenderer_setRenderRatio(self.0.pin_mut(), width, height, ratio);
+        let image = self.render();
+        ffi::MapRenderer_setSize(self.0.pin_mut(), width, height);
+        Ok(image?)
+    }
+
+    /// Same as [`render_static`](Self::render_static), but returns the raw pixels
+    /// without encoding them as PNG.
+    ///
+    /// # Errors
+    /// See [`render_static`](Self::render_static).
+    pub fn render_static_raw(&mut self) -> Result<RawImage, RenderError> {
+        self.render_raw()
     }
+
+    /// Same as [`render_static`](Self::render_static), but replaces the content of `buf` with the PNG,
+    /// so that a buffer reused across renders only grows when an image is larger than the previous ones.
+    ///
+    /// # Errors
+    /// See [`render_static`](Self::render_static). `buf` is left empty on error.
+    pub fn render_static_into(&mut self, buf: &mut Vec<u8>) -> Result<(), RenderError> {
+        buf.clear();
+        let image = self.render()?;
+        buf.extend_from_slice(image.as_slice());
+        Ok(())
+    }
+
+    /// Same as [`render_static_raw`](Self::render_static_raw), but writes the pixels into `buf`,
+    /// e.g. a frame buffer reused across renders in a hot loop.
+    ///
+    /// `buf` must hold exactly `width * height * 4` bytes for the [`image_size`](Self::image_size).
+    /// The pixels are converted to straight alpha while copied out of MapLibre's frame,
+    /// so no other buffer is allocated on the Rust side.
+    ///
+    /// # Errors
+    /// Returns [`RenderError::BufferSize`] without rendering if `buf` has the wrong length,
+    /// or any error of [`render_static`](Self::render_static).
+    pub fn render_rgba_into(&mut self, buf: &mut [u8]) -> Result<(), RenderError> {
+        let (width, height) = self.image_size();
+        let expected = width as usize * height as usize * 4;
+        if buf.len() != expected {
+            return Err(RenderError::BufferSize {
+                expected,
+                actual: buf.len(),
+            });
+        }
+        let mut error = String::new();
+        let status = ffi::MapRenderer_renderRawInto(self.0.pin_mut(), buf, &mut error);
+        check_status(status, error)
+    }
+
+    /// Same as [`render_static`](Self::render_static), but encodes the image in `format`.
+    ///
+    /// # Errors
+    /// See [`render_static`](Self::render_static) and [`RawImage::encode`].
+    pub fn render_static_with_format(&mut self, format: ImageFormat) -> Result<Image, RenderError> {
+        match format {
+            ImageFormat::Png => self.render(),
+            format => self.render_raw()?.encode(format),
+        }
+    }
+
+    /// Render the map once for each of `cameras`, in order.
+    ///
+    /// The style and the resources already loaded are reused across renders,
+    /// which is much faster than building a renderer and setting the style for each view.
+    /// A failed render does not stop the batch, its error is returned in place of the image,
+    /// e.g. [`RenderError::InvalidValue`] for a camera with an invalid center.
+    /// The camera is left at the last valid entry.
+    pub fn render_batch(&mut self, cameras: &[CameraState]) -> Vec<Result<Image, RenderError>> {
+        cameras
+            .iter()
+            .map(|camera| {
+                check_center(camera.lon, camera.lat).map_err(RenderError::InvalidValue)?;
+                self.set_camera_unchecked(
+                    camera.lon,
+                    camera.lat,
+                    camera.zoom,
+                    camera.bearing,
+                    camera.pitch,
+                );
+                self.render()
+            })
+            .collect()
+    }
+}
+
+/// Part of a [`BufferedTile`] image covering the tile itself, in physical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileCrop {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A tile rendered with a margin of its neighbors, see [`ImageRenderer::render_tile_buffered`].
+pub struct BufferedTile {
+    /// The tile with its margin on all sides
+    pub image: Image,
+    /// Where the tile is within the image, to crop the margin away
+    pub crop: TileCrop,
 }
 
 impl ImageRenderer<Tile> {
-    pub fn render_tile(&mut self, zoom: u8, x: u32, y: u32) -> Image {
-        let (lat, lon) = coords_to_lat_lon(f64::from(zoom), x, y);
-        ffi::MapRenderer_setCamera(self.0.pin_mut(), lat, lon, f64::from(zoom), 0.0, 0.0);
-        Image(ffi::MapRenderer_render(self.0.pin_mut()))
+    /// Render a single tile.
+    ///
+    /// The image is a standard slippy map tile of the configured
+    /// [`TileSize`](crate::TileSize), so that neighboring tiles line up when stitched together.
+    /// Prefer [`render_tile_id`](Self::render_tile_id), which cannot mix up the coordinates.
+    ///
+    /// # Errors
+    /// Returns an error if the style or one of its resources could not be loaded,
+    /// or if the graphics backend failed.
+    pub fn render_tile(&mut self, zoom: u8, x: u32, y: u32) -> Result<Image, RenderError> {
+        self.render_tile_with_format(zoom, x, y, ImageFormat::Png)
+    }
+
+    /// Same as [`render_tile`](Self::render_tile), for a tile that is known to exist.
+    ///
+    /// # Errors
+    /// See [`render_tile`](Self::render_tile).
+    pub fn render_tile_id(&mut self, tile: TileId) -> Result<Image, RenderError> {
+        self.render_tile(tile.z(), tile.x(), tile.y())
+    }
+
+    /// Same as [`render_tile`](Self::render_tile), but returns the raw pixels
+    /// without encoding them as PNG.
+    ///
+    /// # Errors
+    /// See [`render_tile`](Self::render_tile).
+    pub fn render_tile_raw(&mut self, zoom: u8, x: u32, y: u32) -> Result<RawImage, RenderError> {
+        match self.set_tile_camera(zoom, x, y) {
+            1 => self.render_raw(),
+            factor => self.render_scaled_raw(factor),
+        }
+    }
+
+    /// Same as [`render_tile`](Self::render_tile), but encodes the image in `format`.
+    ///
+    /// # Errors
+    /// See [`render_tile`](Self::render_tile) and [`RawImage::encode`].
+    pub fn render_tile_with_format(
+        &mut self,
+        zoom: u8,
+        x: u32,
+        y: u32,
+        format: ImageFormat,
+    ) -> Result<Image, RenderError> {
+        match (self.set_tile_camera(zoom, x, y), format) {
+            (1, ImageFormat::Png) => self.render(),
+            (1, format) => self.render_raw()?.encode(format),
+            (factor, format) => self.render_scaled_raw(factor)?.encode(format),
+        }
+    }
+
+    /// Same as [`render_tile`](Self::render_tile), but with the map rotated to `bearing` degrees
+    /// clockwise from north and tilted by `pitch` degrees, e.g. for print layouts addressed by tile.
+    ///
+    /// The camera is centered on the tile at the same scale as the standard tile, and then oriented,
+    /// so the image is no longer a slippy map tile: it does not line up with its neighbors,
+    /// the corners of the tile may be cut off, and the surrounding area fills the rest.
+    /// The pitch is clamped to `[0, 60]` as by [`set_pitch`](Self::set_pitch).
+    /// Later renders of other tiles are north-up again.
+    ///
+    /// # Errors
+    /// See [`render_tile`](Self::render_tile).
+    pub fn render_tile_oriented(
+        &mut self,
+        zoom: u8,
+        x: u32,
+        y: u32,
+        bearing: f64,
+        pitch: f64,
+    ) -> Result<Image, RenderError> {
+        let factor = self.set_tile_camera(zoom, x, y);
+        self.set_bearing(bearing).set_pitch(pitch);
+        match factor {
+            1 => self.render(),
+            factor => self
+                .render_scaled_raw(factor)
+                .and_then(|raw| raw.encode(ImageFormat::Png)),
+        }
+    }
+
+    /// Render a tile with a margin of `buffer` pixels of its neighbors on every side.
+    ///
+    /// Labels and symbols near the tile edges are placed as if the map continued past them,
+    /// so tiles cropped to [`BufferedTile::crop`] do not show clipped labels when stitched.
+    /// The buffer uses the same logical pixels as the [`TileSize`](crate::TileSize),
+    /// e.g. 64 pixels are a quarter of a 256px tile, but only an eighth of a 512px tile.
+    /// The image and the crop are both scaled by the pixel ratio.
+    ///
+    /// # Errors
+    /// See [`render_tile`](Self::render_tile).
+    pub fn render_tile_buffered(
+        &mut self,
+        zoom: u8,
+        x: u32,
+        y: u32,
+        buffer: u32,
+    ) -> Result<BufferedTile, RenderError> {
+        let (width, height) = self.size();
+        let factor = self.set_tile_camera(zoom, x, y);
+        let buffered = (width + 2 * buffer, height + 2 * buffer);
+        ffi::MapRenderer_setSize(self.0.pin_mut(), buffered.0, buffered.1);
+        let image = match factor {
+            1 => self.render(),
+            factor => self
+                .render_scaled_raw(factor)
+                .and_then(|raw| raw.encode(ImageFormat::Png)),
+        };
+        ffi::MapRenderer_setSize(self.0.pin_mut(), width, height);
+        let image = image?;
+
+        let (image_width, _) = image.dimensions().unwrap_or(buffered);
+        let ratio = f64::from(image_width) / f64::from(buffered.0);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let scale = |value: u32| (f64::from(value) * ratio).round() as u32;
+        let crop = TileCrop {
+            x: scale(buffer),
+            y: scale(buffer),
+            width: scale(width),
+            height: scale(height),
+        };
+        Ok(BufferedTile { image, crop })
+    }
+
+    /// Render all tiles of `zoom` within `x_range` and `y_range`, row by row.
+    ///
+    /// Tiles are rendered lazily as the iterator advances. The renderer is reused,
+    /// and only the camera moves between tiles, so resources shared by neighboring tiles
+    /// are loaded once. The ranges are clamped to the tiles that exist at `zoom`,
+    /// e.g. `0..4` at zoom 1 only yields columns 0 and 1.
+    pub fn render_tile_range(
+        &mut self,
+        zoom: u8,
+        x_range: impl RangeBounds<u32>,
+        y_range: impl RangeBounds<u32>,
+    ) -> impl Iterator<Item = (u32, u32, Result<Image, RenderError>)> + '_ {
+        let tiles = 1_u32.checked_shl(u32::from(zoom)).unwrap_or(u32::MAX);
+        let x_range = clamp_tile_range(&x_range, tiles);
+        let y_range = clamp_tile_range(&y_range, tiles);
+        y_range
+            .flat_map(move |y| x_range.clone().map(move |x| (x, y)))
+            .map(move |(x, y)| (x, y, self.render_tile(zoom, x, y)))
+    }
+
+    /// Center the camera on a tile, and zoom so that the tile fills the viewport.
+    ///
+    /// MapLibre zoom levels refer to 512px tiles, so smaller tiles use a lower map zoom.
+    /// As MapLibre cannot zoom below 0, a tile showing the whole world in less than 512px
+    /// must be rendered at a larger size and scaled down, by the returned factor.
+    fn set_tile_camera(&mut self, zoom: u8, x: u32, y: u32) -> u32 {
+        let (size, _) = self.size();
+        let (lon, lat) = tile_center(f64::from(zoom), x, y);
+        let map_zoom =
+            f64::from(zoom) + (f64::from(size) / f64::from(TileSize::Px512.pixels())).log2();
+        ffi::MapRenderer_setCamera(self.0.pin_mut(), lon, lat, map_zoom.max(0.0), 0.0, 0.0);
+        if map_zoom < 0.0 {
+            TileSize::Px512.pixels() / size
+        } else {
+            1
+        }
     }
+
+    /// Render at `factor` times the current size, and scale the image back down.
+    fn render_scaled_raw(&mut self, factor: u32) -> Result<RawImage, RenderError> {
+        let (width, height) = self.size();
+        ffi::MapRenderer_setSize(self.0.pin_mut(), width * factor, height * factor);
+        let image = self.render_raw();
+        ffi::MapRenderer_setSize(self.0.pin_mut(), width, height);
+        Ok(image?.downscale(factor))
+    }
+}
+
+impl ImageRenderer<Continuous> {
+    /// Render the current state of the map as a frame.
+    ///
+    /// Unlike [`Static`] renderers, which load all resources and discard animation state for
+    /// every still image, a continuous map keeps running between frames: tiles stay loaded,
+    /// and transitions and fades progress with time. This makes repeated camera moves and
+    /// animations cheaper. Each call processes pending updates until all resources
+    /// for the current camera are loaded, and then returns the latest frame.
+    ///
+    /// # Errors
+    /// Returns an error if the style or one of its resources could not be loaded,
+    /// or if the graphics backend failed.
+    pub fn render_frame(&mut self) -> Result<Image, RenderError> {
+        self.render_with(ffi::MapRenderer_renderContinuous)
+    }
+
+    /// Process updates until the map [is fully loaded](Self::is_fully_loaded) for the current camera,
+    /// without reading back a frame.
+    ///
+    /// [`render_frame`](Self::render_frame) already waits, so this is only needed to avoid
+    /// starting an animation before the first frame is complete, e.g. after setting the style.
+    ///
+    /// # Errors
+    /// See [`render_frame`](Self::render_frame).
+    pub fn wait_until_idle(&mut self) -> Result<&mut Self, RenderError> {
+        let mut error = String::new();
+        let status = ffi::MapRenderer_waitUntilIdle(self.0.pin_mut(), &mut error);
+        check_status(status, error)?;
+        Ok(self)
+    }
+
+    /// Same as [`render_frame`](Self::render_frame), but returns the raw pixels
+    /// without encoding them as PNG.
+    ///
+    /// # Errors
+    /// See [`render_frame`](Self::render_frame).
+    pub fn render_frame_raw(&mut self) -> Result<RawImage, RenderError> {
+        self.render_raw()
+    }
+}
+
+/// Convert `range` into a half-open range of tile coordinates below `tiles`.
+fn clamp_tile_range(range: &impl RangeBounds<u32>, tiles: u32) -> Range<u32> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.saturating_add(1),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => tiles,
+    };
+    start.min(tiles)..end.min(tiles)
 }
 
+/// Longitude and latitude of the center of a tile.
 #[allow(clippy::cast_precision_loss)]
-fn coords_to_lat_lon(zoom: f64, x: u32, y: u32) -> (f64, f64) {
+pub(crate) fn tile_center(zoom: f64, x: u32, y: u32) -> (f64, f64) {
     // https://github.com/oldmammuth/slippy_map_tilenames/blob/058678480f4b50b622cda7a48b98647292272346/src/lib.rs#L114
     let zz = 2_f64.powf(zoom);
-    let lng = (f64::from(x) + 0.5) / zz * 360_f64 - 180_f64;
+    let lon = (f64::from(x) + 0.5) / zz * 360_f64 - 180_f64;
     let lat = ((PI * (1_f64 - 2_f64 * (f64::from(y) + 0.5) / zz)).sinh())
         .atan()
         .to_degrees();
-    (lat, lng)
+    (lon, lat)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::renderer::retile::to_tile_coords;
+    use crate::renderer::{ConstrainMode, OffscreenContext, Rgba};
+
+    /// Decode the PNG into RGBA pixels
+    pub(crate) fn decode(image: &Image) -> Vec<[u8; 4]> {
+        let decoder = png::Decoder::new(image.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).unwrap();
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+        buf[..info.buffer_size()]
+            .chunks_exact(4)
+            .map(|px| [px[0], px[1], px[2], px[3]])
+            .collect()
+    }
+
+    /// On the demo style, dark pixels are mostly label text
+    fn count_dark_pixels(image: &Image) -> usize {
+        decode(image)
+            .iter()
+            .filter(|[r, g, b, a]| *a > 0 && *r < 100 && *g < 100 && *b < 100)
+            .count()
+    }
+
+    #[test]
+    fn label_density() {
+        let render = |density| {
+            let mut opts = ImageRendererOptions::new();
+            opts.with_size(1024, 256).with_label_density(density);
+            let mut map = opts.build_static_renderer();
+            map.set_style_url("https://demotiles.maplibre.org/style.json")
+                .unwrap();
+            map.set_camera(0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+            map.render_static().unwrap()
+        };
+        let sparse = count_dark_pixels(&render(1.0));
+        let dense = count_dark_pixels(&render(4.0));
+        assert!(
+            dense > sparse,
+            "{dense} label pixels is not more than {sparse}"
+        );
+    }
+
+    /// Style with a single black label on a white background
+    const TEXT_STYLE: &str = r##"{
+        "version": 8,
+        "glyphs": "https://demotiles.maplibre.org/font/{fontstack}/{range}.pbf",
+        "sources": {
+            "label": {
+                "type": "geojson",
+                "data": {"type": "Point", "coordinates": [0.0, 0.0]}
+            }
+        },
+        "layers": [
+            {"id": "bg", "type": "background", "paint": {"background-color": "#fff"}},
+            {
+                "id": "label",
+                "type": "symbol",
+                "source": "label",
+                "layout": {"text-field": "Gamma", "text-font": ["Open Sans Semibold"]},
+                "paint": {"text-color": "#000"}
+            }
+        ]
+    }"##;
+
+    #[test]
+    fn locale() {
+        const STYLE: &str = r##"{
+            "version": 8,
+            "glyphs": "https://demotiles.maplibre.org/font/{fontstack}/{range}.pbf",
+            "sources": {
+                "city": {
+                    "type": "geojson",
+                    "data": {
+                        "type": "Feature",
+                        "properties": {"name": "London", "name:fr": "Londres"},
+                        "geometry": {"type": "Point", "coordinates": [0.0, 0.0]}
+                    }
+                }
+            },
+            "layers": [
+                {"id": "bg", "type": "background", "paint": {"background-color": "#fff"}},
+                {
+                    "id": "city",
+                    "type": "symbol",
+                    "source": "city",
+                    "layout": {"text-field": "{name}", "text-font": ["Open Sans Semibold"]},
+                    "paint": {"text-color": "#000"}
+                }
+            ]
+        }"##;
+        let render = |locale: Option<&str>| {
+            let mut opts = ImageRendererOptions::new();
+            opts.with_size(128, 64).with_fade_duration(Duration::ZERO);
+            if let Some(locale) = locale {
+                opts.with_locale(locale);
+            }
+            let mut map = opts.build_static_renderer();
+            map.set_style_json(STYLE).unwrap();
+            map.set_camera(0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+            decode(&map.render_static().unwrap())
+        };
+        let default = render(None);
+        assert_ne!(render(Some("fr")), default);
+        // There is no German name, so the label falls back to the default one
+        assert_eq!(render(Some("de")), default);
+    }
+
+    #[test]
+    fn zero_fade_duration() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(128, 64).with_fade_duration(Duration::ZERO);
+        let mut map = opts.build_static_renderer();
+        map.set_style_json(TEXT_STYLE).unwrap();
+        map.set_camera(0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+        // Only a fully opaque black label can produce pure black pixels on a white background
+        let pixels = decode(&map.render_static().unwrap());
+        assert!(pixels.contains(&[0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn collision_info() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256);
+        let mut map = opts.build_static_renderer();
+        assert!(map.collision_info().is_empty());
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_camera(10.0, 50.0, 3.0, 0.0, 0.0).unwrap();
+        map.render_static().unwrap();
+
+        let boxes = map.collision_info();
+        assert!(boxes.iter().any(|b| b.placed));
+        assert!(boxes.iter().any(|b| !b.placed));
+        for b in boxes {
+            assert!(b.min_x <= b.max_x && b.min_y <= b.max_y, "{b:?}");
+        }
+    }
+
+    #[test]
+    fn terrain_exaggeration() {
+        let style = r##"{
+            "version": 8,
+            "sources": {
+                "dem": {
+                    "type": "raster-dem",
+                    "url": "https://demotiles.maplibre.org/terrain-tiles/tiles.json",
+                    "tileSize": 256
+                }
+            },
+            "layers": [
+                {"id": "bg", "type": "background", "paint": {"background-color": "#fff"}},
+                {"id": "hills", "type": "hillshade", "source": "dem"}
+            ]
+        }"##;
+        let render = |factor| {
+            let mut opts = ImageRendererOptions::new();
+            opts.with_size(256, 256);
+            let mut map = opts.build_static_renderer();
+            map.set_style_json(style).unwrap();
+            map.set_camera(11.39, 47.27, 11.0, 0.0, 0.0).unwrap();
+            map.set_terrain_exaggeration(factor).unwrap();
+            decode(&map.render_static().unwrap())
+        };
+        assert_ne!(render(0.1), render(1.0));
+
+        let mut map = ImageRendererOptions::new().build_static_renderer();
+        for factor in [3.0, -0.5, f64::NAN] {
+            assert!(matches!(
+                map.set_terrain_exaggeration(factor).err(),
+                Some(Error::InvalidValue(e)) if e.name == "terrain exaggeration"
+            ));
+        }
+    }
+
+    #[test]
+    fn query_rendered_features() {
+        let style = r#"{
+            "version": 8,
+            "sources": {
+                "points": {
+                    "type": "geojson",
+                    "data": {
+                        "type": "Feature",
+                        "id": 7,
+                        "properties": {"name": "center", "rank": 1},
+                        "geometry": {"type": "Point", "coordinates": [0.0, 0.0]}
+                    }
+                }
+            },
+            "layers": [
+                {"id": "halo", "type": "circle", "source": "points", "paint": {"circle-radius": 20}},
+                {"id": "dot", "type": "circle", "source": "points", "paint": {"circle-radius": 5}}
+            ]
+        }"#;
+        let mut opts = ImageRendererOptions::new();
+        // Query coordinates are logical pixels, so the pixel ratio must not matter
+        opts.with_size(256, 256).with_pixel_ratio(2.0);
+        let mut map = opts.build_static_renderer();
+        map.set_style_json(style).unwrap();
+        map.set_camera(0.0, 0.0, 2.0, 0.0, 0.0).unwrap();
+        assert!(map.query_rendered_features(128.0, 128.0).is_empty());
+        map.render_static().unwrap();
+
+        let features = map.query_rendered_features(128.0, 128.0);
+        let layers: Vec<_> = features.iter().map(|f| f.layer.as_str()).collect();
+        assert_eq!(layers, ["dot", "halo"]);
+        let feature = &features[0];
+        assert_eq!(feature.source, "points");
+        assert_eq!(feature.source_layer, "");
+        assert_eq!(feature.id, "7");
+        // Property order is not preserved
+        assert!(feature.properties.starts_with('{'), "{feature:?}");
+        assert!(
+            feature.properties.contains(r#""name":"center""#),
+            "{feature:?}"
+        );
+        assert!(feature.properties.contains(r#""rank":1"#), "{feature:?}");
+
+        let features = map.query_rendered_features(140.0, 128.0);
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0].layer, "halo");
+        assert!(map.query_rendered_features(10.0, 10.0).is_empty());
+    }
+
+    #[test]
+    fn feature_screen_bounds() {
+        let style = r#"{
+            "version": 8,
+            "sources": {
+                "points": {
+                    "type": "geojson",
+                    "data": {
+                        "type": "Feature",
+                        "id": 42,
+                        "properties": {},
+                        "geometry": {"type": "Point", "coordinates": [10.0, 20.0]}
+                    }
+                }
+            },
+            "layers": [{"id": "points", "type": "circle", "source": "points"}]
+        }"#;
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256);
+        let mut map = opts.build_static_renderer();
+        map.set_style_json(style).unwrap();
+        map.set_camera(10.0, 20.0, 4.0, 0.0, 0.0).unwrap();
+        map.render_static().unwrap();
+
+        let rect = map.feature_screen_bounds("points", None, "42").unwrap();
+        assert!(rect.min_x <= 128.5 && rect.max_x >= 127.5, "{rect:?}");
+        assert!(rect.min_y <= 128.5 && rect.max_y >= 127.5, "{rect:?}");
+        assert!(map.feature_screen_bounds("points", None, "7").is_none());
+
+        map.set_camera(-10.0, -20.0, 4.0, 0.0, 0.0).unwrap();
+        assert!(map.feature_screen_bounds("points", None, "42").is_none());
+    }
+
+    #[test]
+    fn tile_center_round_trip() {
+        for (z, x, y) in [(0, 0, 0), (1, 1, 0), (3, 2, 5), (12, 2200, 1343)] {
+            let (lon, lat) = tile_center(f64::from(z), x, y);
+            let (tx, ty) = to_tile_coords(lon, lat, z);
+            assert!((tx - f64::from(x) - 0.5).abs() < 1e-9, "{z}/{x}/{y}: {tx}");
+            assert!((ty - f64::from(y) - 0.5).abs() < 1e-9, "{z}/{x}/{y}: {ty}");
+        }
+        // Tile 2/3/1 lies east of the meridian and north of the equator
+        let (lon, lat) = tile_center(2.0, 3, 1);
+        assert!((lon - 135.0).abs() < 1e-9, "{lon}");
+        assert!(lat > 40.0 && lat < 41.0, "{lat}");
+    }
+
+    #[test]
+    fn tile_center_pixel() {
+        // A marker at the center of tile 2/3/1, whose coordinates are not symmetric,
+        // so that swapping longitude and latitude moves it out of the tile
+        let (lon, lat) = tile_center(2.0, 3, 1);
+        let style = format!(
+            r##"{{
+                "version": 8,
+                "sources": {{
+                    "marker": {{
+                        "type": "geojson",
+                        "data": {{"type": "Point", "coordinates": [{lon}, {lat}]}}
+                    }}
+                }},
+                "layers": [
+                    {{"id": "bg", "type": "background", "paint": {{"background-color": "#fff"}}}},
+                    {{
+                        "id": "marker",
+                        "type": "circle",
+                        "source": "marker",
+                        "paint": {{"circle-radius": 16, "circle-color": "#f0f"}}
+                    }}
+                ]
+            }}"##
+        );
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256);
+        let mut map = opts.build_tile_renderer();
+        map.set_style_json(&style).unwrap();
+        let pixels = decode(&map.render_tile(2, 3, 1).unwrap());
+        assert_eq!(pixels[128 * 256 + 128], [255, 0, 255, 255]);
+        let pixels = decode(&map.render_tile(2, 1, 3).unwrap());
+        assert_eq!(pixels[128 * 256 + 128], [255, 255, 255, 255]);
+        let tile = TileId::parse("2/3/1").unwrap();
+        let pixels = decode(&map.render_tile_id(tile).unwrap());
+        assert_eq!(pixels[128 * 256 + 128], [255, 0, 255, 255]);
+    }
+
+    /// Style with a magenta fill over the north-eastern quadrant of the world, on white.
+    const QUADRANT_STYLE: &str = r##"{
+        "version": 8,
+        "sources": {
+            "quadrant": {
+                "type": "geojson",
+                "data": {
+                    "type": "Polygon",
+                    "coordinates": [[[0, 0], [180, 0], [180, 89], [0, 89], [0, 0]]]
+                }
+            }
+        },
+        "layers": [
+            {"id": "bg", "type": "background", "paint": {"background-color": "#fff"}},
+            {
+                "id": "quadrant",
+                "type": "fill",
+                "source": "quadrant",
+                "paint": {"fill-color": "#f0f", "fill-antialias": false}
+            }
+        ]
+    }"##;
+
+    #[test]
+    fn tile_sizes() {
+        const MAGENTA: [u8; 4] = [255, 0, 255, 255];
+        const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+        let mut map = ImageRendererOptions::new().build_tile_renderer();
+        map.set_style_json(QUADRANT_STYLE).unwrap();
+        // Tile 1/1/0 is exactly the north-eastern quadrant
+        let image = map.render_tile(1, 1, 0).unwrap();
+        assert_eq!(image.dimensions(), Some((256, 256)));
+        let pixels = decode(&image);
+        for (x, y) in [(2, 2), (253, 2), (128, 128), (2, 253), (253, 253)] {
+            assert_eq!(pixels[y * 256 + x], MAGENTA, "1/1/0 at {x},{y}");
+        }
+        let pixels = decode(&map.render_tile(1, 0, 0).unwrap());
+        assert_eq!(pixels[128 * 256 + 128], WHITE);
+
+        // The whole world is scaled down into a single tile, with the quadrant in its upper right
+        let image = map.render_tile(0, 0, 0).unwrap();
+        assert_eq!(image.dimensions(), Some((256, 256)));
+        let pixels = decode(&image);
+        assert_eq!(pixels[64 * 256 + 192], MAGENTA);
+        assert_eq!(pixels[64 * 256 + 64], WHITE);
+        assert_eq!(pixels[192 * 256 + 192], WHITE);
+        assert_eq!(map.size(), (256, 256));
+
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(100, 50).with_tile_size(TileSize::Px512);
+        let mut map = opts.build_tile_renderer();
+        map.set_style_json(QUADRANT_STYLE).unwrap();
+        let image = map.render_tile(1, 1, 0).unwrap();
+        assert_eq!(image.dimensions(), Some((512, 512)));
+        let pixels = decode(&image);
+        for (x, y) in [(2, 2), (509, 2), (2, 509), (509, 509)] {
+            assert_eq!(pixels[y * 512 + x], MAGENTA, "1/1/0 at {x},{y}");
+        }
+    }
+
+    #[test]
+    fn oriented_tile() {
+        const MAGENTA: [u8; 4] = [255, 0, 255, 255];
+        const WHITE: [u8; 4] = [255, 255, 255, 255];
+        let mut map = ImageRendererOptions::new().build_tile_renderer();
+        map.set_style_json(QUADRANT_STYLE).unwrap();
+        // Tile 2/2/1 touches the prime meridian on its west edge
+        let pixels = decode(&map.render_tile(2, 2, 1).unwrap());
+        assert_eq!(pixels[253 * 256 + 2], MAGENTA);
+
+        // Rotated by 45 degrees, the bottom-left corner points west, past the meridian
+        let image = map.render_tile_oriented(2, 2, 1, 45.0, 0.0).unwrap();
+        assert_eq!(image.dimensions(), Some((256, 256)));
+        let pixels = decode(&image);
+        assert_eq!(pixels[128 * 256 + 128], MAGENTA);
+        assert_eq!(pixels[253 * 256 + 2], WHITE);
+        assert!((map.get_camera().bearing - 45.0).abs() < 1e-9);
+
+        let pixels = decode(&map.render_tile(2, 2, 1).unwrap());
+        assert_eq!(pixels[253 * 256 + 2], MAGENTA);
+        assert!(map.get_camera().bearing.abs() < 1e-9);
+    }
+
+    #[test]
+    fn buffered_tile() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_pixel_ratio(2.0);
+        let mut map = opts.build_tile_renderer();
+        map.set_style_json(QUADRANT_STYLE).unwrap();
+        let plain = decode(&map.render_tile(1, 1, 0).unwrap());
+
+        let tile = map.render_tile_buffered(1, 1, 0, 16).unwrap();
+        assert_eq!(tile.image.dimensions(), Some((576, 576)));
+        assert_eq!(
+            tile.crop,
+            TileCrop {
+                x: 32,
+                y: 32,
+                width: 512,
+                height: 512
+            }
+        );
+        assert_eq!(map.size(), (256, 256));
+        let pixels = decode(&tile.image);
+        // West of the tile is the white north-western quadrant
+        assert_eq!(pixels[300 * 576 + 8], [255, 255, 255, 255]);
+        for row in 0..512 {
+            let start = (row + 32) * 576 + 32;
+            assert_eq!(
+                pixels[start..start + 512],
+                plain[row * 512..(row + 1) * 512]
+            );
+        }
+
+        // The whole world is scaled down, and so is its buffer
+        let world = map.render_tile_buffered(0, 0, 0, 8).unwrap();
+        assert_eq!(world.image.dimensions(), Some((544, 544)));
+        assert_eq!(world.crop.x, 16);
+    }
+
+    #[test]
+    fn downscale() {
+        // Opaque red and transparent pixels average to half-transparent red, not dark red
+        let pixels = [
+            [255, 0, 0, 255],
+            [0, 0, 0, 0],
+            [255, 0, 0, 255],
+            [0, 0, 0, 0],
+        ]
+        .concat();
+        let image = RawImage {
+            data: ImageData::Vec(pixels),
+            width: 2,
+            height: 2,
+        }
+        .downscale(2);
+        assert_eq!((image.width(), image.height()), (1, 1));
+        assert_eq!(image.as_rgba(), [255, 0, 0, 127]);
+    }
+
+    #[test]
+    fn camera_to_bounds() {
+        let style = r#"{
+            "version": 8,
+            "sources": {
+                "points": {
+                    "type": "geojson",
+                    "data": {
+                        "type": "FeatureCollection",
+                        "features": [
+                            {
+                                "type": "Feature",
+                                "id": 1,
+                                "properties": {},
+                                "geometry": {"type": "Point", "coordinates": [0.0, 0.0]}
+                            },
+                            {
+                                "type": "Feature",
+                                "id": 2,
+                                "properties": {},
+                                "geometry": {"type": "Point", "coordinates": [20.0, 10.0]}
+                            },
+                            {
+                                "type": "Feature",
+                                "id": 3,
+                                "properties": {},
+                                "geometry": {"type": "Point", "coordinates": [170.0, 0.0]}
+                            },
+                            {
+                                "type": "Feature",
+                                "id": 4,
+                                "properties": {},
+                                "geometry": {"type": "Point", "coordinates": [-170.0, 0.0]}
+                            }
+                        ]
+                    }
+                }
+            },
+            "layers": [{"id": "points", "type": "circle", "source": "points"}]
+        }"#;
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256);
+        let mut map = opts.build_static_renderer();
+        map.set_style_json(style).unwrap();
+        let padding = Padding {
+            top: 10.0,
+            left: 10.0,
+            bottom: 10.0,
+            right: 10.0,
+        };
+        let center_x = |map: &mut ImageRenderer<Static>, id| {
+            let rect = map.feature_screen_bounds("points", None, id).unwrap();
+            (rect.min_x + rect.max_x) / 2.0
+        };
+
+        // The wider extent is fitted between the left and right padding
+        map.set_camera_to_bounds(0.0, 0.0, 20.0, 10.0, padding)
+            .unwrap();
+        map.render_static().unwrap();
+        assert!((center_x(&mut map, "1") - 10.0).abs() < 1.0);
+        assert!((center_x(&mut map, "2") - 246.0).abs() < 1.0);
+
+        // Bounds crossing the antimeridian span 20 degrees, not 340
+        map.set_camera_to_bounds(170.0, -5.0, -170.0, 5.0, padding)
+            .unwrap();
+        map.render_static().unwrap();
+        assert!((center_x(&mut map, "3") - 10.0).abs() < 1.0);
+        assert!((center_x(&mut map, "4") - 246.0).abs() < 1.0);
+
+        // A single point is centered
+        map.set_camera_to_bounds(20.0, 10.0, 20.0, 10.0, Padding::default())
+            .unwrap();
+        map.render_static().unwrap();
+        let rect = map.feature_screen_bounds("points", None, "2").unwrap();
+        assert!(rect.min_x <= 128.5 && rect.max_x >= 127.5, "{rect:?}");
+        assert!(rect.min_y <= 128.5 && rect.max_y >= 127.5, "{rect:?}");
+    }
+
+    #[test]
+    fn wait_until_idle() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(128, 128).with_in_memory_cache();
+        let mut map = opts.build_continuous_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_camera(0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+        assert!(!map.is_fully_loaded());
+        map.wait_until_idle().unwrap();
+        assert!(map.is_fully_loaded());
+        assert!(!map.render_frame().unwrap().is_empty());
+
+        let mut map = ImageRendererOptions::new().build_continuous_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/missing-style.json")
+            .unwrap();
+        assert!(matches!(
+            map.wait_until_idle(),
+            Err(RenderError::StyleLoad(_))
+        ));
+    }
+
+    #[test]
+    fn zoom_sweep() {
+        // Keep the center where it was set, even when the world is smaller than the viewport
+        let mut opts = ImageRendererOptions::new();
+        opts.with_constrain_mode(ConstrainMode::None);
+        let mut map = opts.build_static_renderer();
+        map.set_camera(10.0, 50.0, 1.0, 30.0, 20.0).unwrap();
+        for step in 0..=10 {
+            let zoom = f64::from(step) * 0.5;
+            map.set_zoom(zoom);
+            let camera = map.get_camera();
+            assert!((camera.zoom - zoom).abs() < 1e-9, "{camera:?}");
+            assert!((camera.lon - 10.0).abs() < 1e-9, "{camera:?}");
+            assert!((camera.lat - 50.0).abs() < 1e-9, "{camera:?}");
+            assert!((camera.bearing.rem_euclid(360.0) - 30.0).abs() < 1e-9);
+            assert!((camera.pitch - 20.0).abs() < 1e-9, "{camera:?}");
+        }
+
+        let (min, max) = map.zoom_range();
+        assert!(min <= 0.0 && max >= 22.0, "{min}..{max}");
+        assert!((map.set_zoom(100.0).get_camera().zoom - max).abs() < 1e-9);
+        assert!((map.set_zoom(-1.0).get_camera().zoom - min).abs() < 1e-9);
+
+        map.set_center(-20.0, -30.0).unwrap();
+        let camera = map.get_camera();
+        assert!((camera.lon + 20.0).abs() < 1e-9, "{camera:?}");
+        assert!((camera.lat + 30.0).abs() < 1e-9, "{camera:?}");
+        assert!((camera.zoom - min).abs() < 1e-9, "{camera:?}");
+    }
+
+    #[test]
+    fn camera_constraints() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256);
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_zoom_range(2.0, 5.0).unwrap();
+        assert_eq!(map.zoom_range(), (2.0, 5.0));
+        assert!((map.set_zoom(8.0).get_camera().zoom - 5.0).abs() < 1e-9);
+        assert!((map.set_zoom(1.0).get_camera().zoom - 2.0).abs() < 1e-9);
+
+        let europe = LatLngBounds {
+            west: -10.0,
+            south: 35.0,
+            east: 30.0,
+            north: 60.0,
+        };
+        map.set_lat_lng_bounds(Some(europe)).unwrap();
+        map.set_camera(-100.0, 0.0, 10.0, 0.0, 0.0).unwrap();
+        let camera = map.get_camera();
+        assert!((camera.lon + 10.0).abs() < 1e-6, "{camera:?}");
+        assert!((camera.lat - 35.0).abs() < 1e-6, "{camera:?}");
+        assert!((camera.zoom - 5.0).abs() < 1e-9, "{camera:?}");
+        map.render_static().unwrap();
+        // Rendering keeps the clamped camera
+        assert_eq!(map.get_camera(), camera);
+
+        map.set_lat_lng_bounds(None).unwrap();
+        map.set_camera(-100.0, 0.0, 4.0, 0.0, 0.0).unwrap();
+        assert!((map.get_camera().lon + 100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn get_camera() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-6;
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256);
+        let mut map = opts.build_static_renderer();
+
+        let camera = map
+            .set_camera(10.0, 50.0, 3.5, 45.0, 30.0)
+            .unwrap()
+            .get_camera();
+        assert!(
+            close(camera.lon, 10.0) && close(camera.lat, 50.0),
+            "{camera:?}"
+        );
+        assert!(close(camera.zoom, 3.5), "{camera:?}");
+        assert!(
+            close(camera.bearing, 45.0) && close(camera.pitch, 30.0),
+            "{camera:?}"
+        );
+
+        // The fitted camera is centered on the bounds, with bearing and pitch reset
+        let camera = map
+            .set_camera_to_bounds(170.0, -5.0, -170.0, 5.0, Padding::default())
+            .unwrap()
+            .get_camera();
+        assert!(
+            close(camera.lon.abs(), 180.0) && close(camera.lat, 0.0),
+            "{camera:?}"
+        );
+        assert!(camera.zoom > 3.0 && camera.zoom < 4.0, "{camera:?}");
+        assert!(
+            close(camera.bearing, 0.0) && close(camera.pitch, 0.0),
+            "{camera:?}"
+        );
+
+        // The default constrain mode keeps the area beyond the poles out of view
+        let camera = map
+            .set_camera(0.0, 80.0, 0.0, 0.0, 0.0)
+            .unwrap()
+            .get_camera();
+        assert!(camera.lat > 0.0 && camera.lat < 80.0, "{camera:?}");
+    }
+
+    #[test]
+    fn bearing_and_pitch() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-6;
+        let mut map = ImageRendererOptions::new().build_static_renderer();
+        map.set_camera(10.0, 50.0, 3.0, 0.0, 0.0).unwrap();
+
+        let camera = map.set_bearing(-90.0).get_camera();
+        assert!(close(camera.bearing.rem_euclid(360.0), 270.0), "{camera:?}");
+        let camera = map.set_pitch(30.0).get_camera();
+        assert!(close(camera.pitch, 30.0), "{camera:?}");
+        assert!(close(camera.bearing.rem_euclid(360.0), 270.0), "{camera:?}");
+        assert!(
+            close(camera.lon, 10.0) && close(camera.lat, 50.0),
+            "{camera:?}"
+        );
+        assert!(close(camera.zoom, 3.0), "{camera:?}");
+
+        assert!(close(map.set_pitch(85.0).get_camera().pitch, 60.0));
+        assert!(close(map.set_pitch(-5.0).get_camera().pitch, 0.0));
+        let bearing = map.set_bearing(725.0).get_camera().bearing;
+        assert!(close(bearing.rem_euclid(360.0), 5.0), "{bearing}");
+    }
+
+    #[test]
+    fn raw_pixels() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 32).with_pixel_ratio(2.0);
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        let png = decode(&map.render_static().unwrap());
+        let raw = map.render_static_raw().unwrap();
+        assert_eq!((raw.width(), raw.height()), (128, 64));
+        assert_eq!(raw.as_rgba(), png.concat());
+    }
+
+    #[test]
+    fn render_into() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 32).with_pixel_ratio(2.0);
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        assert_eq!(map.image_size(), (128, 64));
+
+        let mut png = vec![1, 2, 3];
+        map.render_static_into(&mut png).unwrap();
+        let expected = decode(&map.render_static().unwrap()).concat();
+        assert_eq!(png.len(), map.render_static().unwrap().len());
+
+        let mut pixels = vec![0; 128 * 64 * 4];
+        map.render_rgba_into(&mut pixels).unwrap();
+        assert_eq!(pixels, expected);
+        // The buffer is reused as is by the next render
+        map.render_rgba_into(&mut pixels).unwrap();
+        assert_eq!(pixels, expected);
+
+        assert_eq!(
+            map.render_rgba_into(&mut [0; 16]),
+            Err(RenderError::BufferSize {
+                expected: 128 * 64 * 4,
+                actual: 16
+            })
+        );
+    }
+
+    #[test]
+    fn render_static_scaled() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 32).with_pixel_ratio(2.0);
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        let dimensions = |image: Image| image.dimensions().unwrap();
+        assert_eq!(dimensions(map.render_static_scaled(1.0).unwrap()), (64, 32));
+        assert_eq!(dimensions(map.render_static_scaled(1.5).unwrap()), (96, 48));
+        assert_eq!(map.size(), (64, 32));
+        assert_eq!(dimensions(map.render_static().unwrap()), (128, 64));
+
+        map.set_size(40, 20).unwrap();
+        assert_eq!(
+            dimensions(map.render_static_scaled(3.0).unwrap()),
+            (120, 60)
+        );
+        assert_eq!(dimensions(map.render_static().unwrap()), (80, 40));
+    }
+
+    #[test]
+    fn invalid_camera_values() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 32);
+        let mut map = opts.build_static_renderer();
+        let name = |result: Result<&mut ImageRenderer<Static>, Error>| match result.err() {
+            Some(Error::InvalidValue(e)) => e.name,
+            _ => "",
+        };
+        let negative = Padding {
+            left: -1.0,
+            ..Padding::default()
+        };
+        assert_eq!(
+            name(map.set_camera_to_bounds(0.0, 10.0, 20.0, 5.0, Padding::default())),
+            "north"
+        );
+        assert_eq!(
+            name(map.set_camera_to_bounds(0.0, 0.0, 200.0, 5.0, Padding::default())),
+            "east"
+        );
+        assert_eq!(
+            name(map.set_camera_to_bounds(0.0, 0.0, 20.0, 5.0, negative)),
+            "left padding"
+        );
+        assert_eq!(name(map.set_padding(negative)), "left padding");
+        assert_eq!(name(map.set_size(0, 32)), "width");
+        assert_eq!(name(map.set_zoom_range(5.0, 2.0)), "maximum zoom");
+        assert_eq!(name(map.set_zoom_range(-1.0, 2.0)), "minimum zoom");
+        let crossing = LatLngBounds {
+            west: 170.0,
+            south: -5.0,
+            east: -170.0,
+            north: 5.0,
+        };
+        assert_eq!(name(map.set_lat_lng_bounds(Some(crossing))), "east");
+        assert_eq!(name(map.set_camera(0.0, 91.0, 1.0, 0.0, 0.0)), "latitude");
+        assert_eq!(name(map.set_center(f64::NAN, 0.0)), "longitude");
+        let camera = map.get_camera();
+        assert_eq!(
+            name(map.set_camera_with(camera, Padding::default(), Some((65.0, 0.0)))),
+            "anchor x"
+        );
+        let invalid = CameraState {
+            lat: -91.0,
+            ..camera
+        };
+        assert_eq!(
+            name(map.set_camera_with(invalid, Padding::default(), None)),
+            "latitude"
+        );
+        assert!(matches!(
+            map.render_static_scaled(0.0),
+            Err(Error::InvalidValue(e)) if e.to_string() == "Invalid pixel ratio 0, expected a finite positive value"
+        ));
+        assert!(matches!(
+            map.render_static_scaled(f32::INFINITY),
+            Err(Error::InvalidValue(e)) if e.name == "pixel ratio"
+        ));
+
+        // Rejected values leave the map unchanged
+        assert_eq!(map.size(), (64, 32));
+        assert_eq!(map.zoom_range(), (0.0, MAX_ZOOM));
+        assert_eq!(map.get_camera(), camera);
+    }
+
+    #[test]
+    fn resource_pixel_ratio() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 32).with_resource_pixel_ratio(2.0);
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        // The image size only depends on the image pixel ratio
+        let image = map.render_static_raw().unwrap();
+        assert_eq!((image.width(), image.height()), (64, 32));
+    }
+
+    #[test]
+    fn local_ideograph_font_family() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(128, 64)
+            .with_local_ideograph_font_family("Noto Sans CJK SC");
+        let mut map = opts.build_static_renderer();
+        map.set_style_json(TEXT_STYLE).unwrap();
+        assert!(count_dark_pixels(&map.render_static().unwrap()) > 0);
+    }
+
+    #[test]
+    fn file_urls() {
+        assert_eq!(
+            file_url("/tmp/styles/a.json", false),
+            "file:///tmp/styles/a.json"
+        );
+        assert_eq!(
+            file_url("/tmp/my style%1#ä.json", false),
+            "file:///tmp/my%20style%251%23%C3%A4.json"
+        );
+        assert_eq!(
+            file_url("C:\\styles\\a b.json", true),
+            "file:///C:/styles/a%20b.json"
+        );
+        assert_eq!(
+            file_url("/tmp/back\\slash.json", false),
+            "file:///tmp/back%5Cslash.json"
+        );
+    }
+
+    #[test]
+    fn set_style_path_checks_file() {
+        let mut map = ImageRendererOptions::new().build_static_renderer();
+        let missing = std::env::temp_dir().join("mln_missing_style.json");
+        assert!(matches!(
+            map.set_style_path(&missing),
+            Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound
+        ));
+        assert!(matches!(
+            map.set_style_path(std::env::temp_dir()),
+            Err(Error::Io(_))
+        ));
+
+        // Characters with a meaning in URLs are encoded, so that MapLibre reads the same file
+        let dir = std::env::temp_dir().join("mln style #1%");
+        std::fs::create_dir_all(&dir).unwrap();
+        let style = dir.join("style.json");
+        std::fs::write(
+            &style,
+            r##"{"version": 8, "sources": {}, "layers": [
+                {"id": "bg", "type": "background", "paint": {"background-color": "#00ff00"}}
+            ]}"##,
+        )
+        .unwrap();
+        map.set_style_path(&style).unwrap();
+        let pixels = decode(&map.render_static().unwrap());
+        assert_eq!(pixels[0], [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn renderers_on_one_thread() {
+        let style = r##"{"version": 8, "sources": {}, "layers": [
+            {"id": "bg", "type": "background", "paint": {"background-color": "#ff0000"}}
+        ]}"##;
+        let mut first = ImageRendererOptions::new().build_static_renderer();
+        first.set_style_json(style).unwrap();
+        let mut second = ImageRendererOptions::new().build_tile_renderer();
+        second
+            .set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+
+        // Renders of the renderer created first must not wait on the run loop of the second one
+        assert_eq!(decode(&first.render_static().unwrap())[0], [255, 0, 0, 255]);
+        assert!(!second.render_tile(1, 1, 0).unwrap().is_empty());
+        assert_eq!(decode(&first.render_static().unwrap())[0], [255, 0, 0, 255]);
+
+        // Dropping a renderer keeps the run loop of the others alive
+        drop(second);
+        assert_eq!(decode(&first.render_static().unwrap())[0], [255, 0, 0, 255]);
+        let mut third = ImageRendererOptions::new().build_static_renderer();
+        third.set_style_json(style).unwrap();
+        drop(first);
+        assert_eq!(decode(&third.render_static().unwrap())[0], [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn into_vec() {
+        fn len(data: impl AsRef<[u8]>) -> usize {
+            data.as_ref().len()
+        }
+        let mut map = ImageRendererOptions::new().build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        let image = map.render_static().unwrap();
+        let expected = image.as_slice().to_vec();
+        assert_eq!(len(&image), expected.len());
+        let data: Vec<u8> = image.into();
+        assert_eq!(data, expected);
+        // The bytes outlive the renderer
+        drop(map);
+        assert!(data.starts_with(b"\x89PNG"));
+
+        let image = Image(ImageData::Vec(vec![1, 2, 3]));
+        assert_eq!(image.into_vec(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn dimensions() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(96, 48).with_pixel_ratio(2.0);
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        let image = map.render_static().unwrap();
+        assert!(!image.is_empty());
+        assert_eq!(image.len(), image.as_slice().len());
+        assert_eq!(image.dimensions(), Some((192, 96)));
+
+        for format in supported_output_formats() {
+            let format = match format {
+                OutputFormat::Png => ImageFormat::Png,
+                OutputFormat::Jpeg => ImageFormat::Jpeg { quality: 90 },
+                OutputFormat::WebP => ImageFormat::Webp { quality: 90 },
+            };
+            let image = map.render_static_with_format(format).unwrap();
+            assert_eq!(image.dimensions(), Some((192, 96)), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn image_formats() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64);
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        let raw = map.render_static_raw().unwrap();
+        let png = raw.encode(ImageFormat::Png).unwrap();
+        assert_eq!(decode(&png).concat(), raw.as_rgba());
+
+        let formats = [
+            (ImageFormat::Jpeg { quality: 80 }, &b"\xFF\xD8\xFF"[..]),
+            (ImageFormat::Webp { quality: 80 }, &b"RIFF"[..]),
+        ];
+        for (format, magic) in formats {
+            let image = map.render_static_with_format(format).unwrap();
+            assert!(image.as_slice().starts_with(magic), "{format:?}");
+        }
+
+        // WebP is lossless
+        let webp = raw.encode(ImageFormat::Webp { quality: 80 }).unwrap();
+        let decoded = image_webp::WebPDecoder::new(std::io::Cursor::new(webp.as_slice()))
+            .map(|mut decoder| {
+                let mut pixels = vec![0; decoder.output_buffer_size().unwrap()];
+                decoder.read_image(&mut pixels).unwrap();
+                pixels
+            })
+            .unwrap();
+        assert_eq!(decoded, raw.as_rgba());
+    }
+
+    #[test]
+    fn continuous_frames() {
+        let mut map = ImageRendererOptions::new().build_continuous_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_camera(0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+        let first = map.render_frame().unwrap();
+        map.set_camera(90.0, 45.0, 3.0, 0.0, 0.0).unwrap();
+        let second = map.render_frame().unwrap();
+        assert_ne!(decode(&first), decode(&second));
+    }
+
+    #[test]
+    fn style_url_scheme() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_uri_scheme_alias("custom");
+        let mut map = opts.build_static_renderer();
+        assert!(map.set_style_url("HTTPS://example.com/style.json").is_ok());
+        assert!(map.set_style_url("custom://maps/streets").is_ok());
+        assert_eq!(
+            map.set_style_url("style.json").err(),
+            Some(StyleUrlError {
+                url: "style.json".to_string(),
+                scheme: None,
+            })
+        );
+        assert_eq!(
+            map.set_style_url("ftp://example.com/style.json")
+                .err()
+                .and_then(|e| e.scheme)
+                .as_deref(),
+            Some("ftp")
+        );
+        assert_eq!(
+            map.set_style_url("/a/b://c").err().map(|e| e.scheme),
+            Some(None)
+        );
+    }
+
+    #[test]
+    fn style_json() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64);
+        let mut map = opts.build_static_renderer();
+        assert!(map.set_style_json("").is_err());
+        assert!(map.set_style_json("  \n").is_err());
+        assert!(map.set_style_json(r#"{"version": 8,"#).is_err());
+        assert!(map.set_style_json("[8]").is_err());
+
+        map.set_style_json(
+            r##"{
+                "version": 8,
+                "sources": {},
+                "layers": [
+                    {"id": "bg", "type": "background", "paint": {"background-color": "#f0f"}}
+                ]
+            }"##,
+        )
+        .unwrap();
+        let pixels = decode(&map.render_static().unwrap());
+        assert!(pixels.iter().all(|px| *px == [255, 0, 255, 255]));
+    }
+
+    #[test]
+    fn bad_style_url() {
+        let mut map = ImageRendererOptions::new().build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/missing-style.json")
+            .unwrap();
+        assert!(matches!(
+            map.render_static(),
+            Err(RenderError::StyleLoad(_))
+        ));
+    }
+
+    #[test]
+    fn letterbox() {
+        let color = Rgba {
+            r: 255,
+            g: 0,
+            b: 255,
+            a: 255,
+        };
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(1024, 512)
+            .with_constrain_mode(ConstrainMode::WidthAndHeight)
+            .with_letterbox_color(color);
+        let mut map = opts.clone().build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_camera(0.0, 0.0, 0.0, 0.0, 0.0).unwrap();
+        let pixels = decode(&map.render_static().unwrap());
+
+        // At zoom 0 the world is 512px wide, leaving 256px bars on each side
+        let color = [color.r, color.g, color.b, color.a];
+        for row in pixels.chunks_exact(1024) {
+            assert!(row[..256].iter().all(|px| *px == color));
+            assert!(row[768..].iter().all(|px| *px == color));
+            assert_ne!(row[512], color);
+        }
+
+        // The bars follow the pixel ratio of the image, not the one used to pick resources
+        opts.with_pixel_ratio(2.0).with_resource_pixel_ratio(1.0);
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_camera(0.0, 0.0, 0.0, 0.0, 0.0).unwrap();
+        let pixels = decode(&map.render_static().unwrap());
+        for row in pixels.chunks_exact(2048) {
+            assert!(row[..512].iter().all(|px| *px == color));
+            assert!(row[1536..].iter().all(|px| *px == color));
+            assert_ne!(row[1024], color);
+        }
+    }
+
+    #[test]
+    fn transparent_background() {
+        let style = r##"{
+            "version": 8,
+            "sources": {
+                "point": {"type": "geojson", "data": {"type": "Point", "coordinates": [0, 0]}}
+            },
+            "layers": [
+                {"id": "bg", "type": "background", "paint": {"background-color": "#ffffff"}},
+                {
+                    "id": "circle",
+                    "type": "circle",
+                    "source": "point",
+                    "paint": {"circle-radius": 8, "circle-color": "#ff0000", "circle-opacity": 0.5}
+                }
+            ]
+        }"##;
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64).with_transparent_background(true);
+        let mut map = opts.clone().build_static_renderer();
+        map.set_style_json(style).unwrap();
+        map.set_camera(0.0, 0.0, 0.0, 0.0, 0.0).unwrap();
+        let pixels = decode(&map.render_static().unwrap());
+        assert_eq!(pixels[0], [0, 0, 0, 0]);
+        // The partially transparent circle keeps its color and alpha through the PNG encoding
+        let [r, g, b, a] = pixels[32 * 64 + 32];
+        assert!(r > 240 && g < 15 && b < 15, "{:?}", pixels[32 * 64 + 32]);
+        assert!(a.abs_diff(128) <= 2, "{a}");
+
+        opts.with_transparent_background(false);
+        let mut map = opts.build_static_renderer();
+        map.set_style_json(style).unwrap();
+        assert_eq!(
+            decode(&map.render_static().unwrap())[0],
+            [255, 255, 255, 255]
+        );
+    }
+
+    #[test]
+    fn background_color() {
+        // Only a half-transparent red circle, nothing is drawn elsewhere
+        let style = r##"{
+            "version": 8,
+            "sources": {
+                "point": {"type": "geojson", "data": {"type": "Point", "coordinates": [0, 0]}}
+            },
+            "layers": [{
+                "id": "circle",
+                "type": "circle",
+                "source": "point",
+                "paint": {"circle-radius": 8, "circle-color": "#ff0000", "circle-opacity": 0.5}
+            }]
+        }"##;
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64).with_background_color(Rgba {
+            r: 0,
+            g: 0,
+            b: 255,
+            a: 255,
+        });
+        let mut map = opts.build_static_renderer();
+        map.set_style_json(style).unwrap();
+        map.set_camera(0.0, 0.0, 0.0, 0.0, 0.0).unwrap();
+        let pixels = decode(&map.render_static().unwrap());
+        assert_eq!(pixels[0], [0, 0, 255, 255]);
+        // The circle is blended over the background color
+        let [r, g, b, a] = pixels[32 * 64 + 32];
+        assert!(
+            r.abs_diff(128) <= 2 && g < 5 && b.abs_diff(127) <= 2,
+            "{r} {g} {b}"
+        );
+        assert_eq!(a, 255);
+    }
+
+    #[test]
+    fn flush_cache() {
+        let cache = std::env::temp_dir().join("mln_flush_cache.sqlite");
+        let _ = std::fs::remove_file(&cache);
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256).with_cache_path(&cache);
+
+        let mut map = opts.clone().build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_camera(0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+        map.render_static().unwrap();
+        map.flush().unwrap();
+        let data = std::fs::read(&cache).unwrap();
+        assert!(data.starts_with(b"SQLite format 3\0"));
+        drop(map);
+
+        // The closed database can be opened again by a new renderer
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_camera(0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+        map.render_static().unwrap();
+        drop(map);
+        assert!(std::fs::metadata(&cache).unwrap().len() > 0);
+    }
+
+    #[test]
+    fn in_memory_cache() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256).with_in_memory_cache();
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_camera(0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+        assert!(!map.render_static().unwrap().is_empty());
+        map.flush().unwrap();
+        drop(map);
+        // SQLite treats this name as an in-memory database, not as a file
+        assert!(!Path::new(":memory:").exists());
+    }
+
+    #[test]
+    fn render_batch() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(128, 128).with_in_memory_cache();
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        let cameras = [
+            CameraState {
+                lon: 0.0,
+                lat: 0.0,
+                zoom: 1.0,
+                ..CameraState::default()
+            },
+            CameraState {
+                lon: 10.0,
+                lat: 50.0,
+                zoom: 4.0,
+                bearing: 30.0,
+                pitch: 20.0,
+            },
+        ];
+        let images = map.render_batch(&cameras);
+        assert_eq!(images.len(), 2);
+        let images: Vec<_> = images.into_iter().map(Result::unwrap).collect();
+        assert_ne!(decode(&images[0]), decode(&images[1]));
+        assert!((map.get_camera().zoom - 4.0).abs() < 1e-9);
+        assert!(map.render_batch(&[]).is_empty());
+
+        // An invalid camera fails on its own, without stopping the batch
+        let invalid = CameraState {
+            lat: 100.0,
+            ..CameraState::default()
+        };
+        let images = map.render_batch(&[invalid, cameras[0]]);
+        assert!(matches!(&images[0], Err(RenderError::InvalidValue(e)) if e.name == "latitude"));
+        assert!(images[1].is_ok());
+    }
+
+    #[test]
+    fn request_timeout() {
+        // A server that accepts connections but never responds
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let connections: Vec<_> = listener.incoming().collect();
+            drop(connections);
+        });
+
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64)
+            .with_in_memory_cache()
+            .with_request_timeout(Duration::from_millis(500));
+        let mut map = opts.clone().build_static_renderer();
+        // A renderer built later with a longer timeout must not change the timeout of the first one
+        opts.with_request_timeout(Duration::from_secs(60));
+        let _other = opts.build_static_renderer();
+        map.set_style_url(&format!("http://127.0.0.1:{port}/style.json"))
+            .unwrap();
+        let start = std::time::Instant::now();
+        let result = map.render_static();
+        assert!(
+            matches!(result, Err(RenderError::Timeout(_))),
+            "{:?}",
+            result.err()
+        );
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn provider_keys_per_renderer() {
+        // A server that records the request line of every request, and answers with a 404
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut line = String::new();
+                std::io::BufRead::read_line(&mut std::io::BufReader::new(&stream), &mut line)
+                    .unwrap();
+                recorded.lock().unwrap().push(line);
+                let response =
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+                std::io::Write::write_all(&mut stream, response.as_bytes()).unwrap();
+            }
+        });
+
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64).with_in_memory_cache();
+        let mut keyed_opts = opts.clone();
+        keyed_opts.with_provider_key("127.0.0.1", "key", "secret");
+        let mut keyed = keyed_opts.build_static_renderer();
+        let mut other = opts.build_static_renderer();
+        keyed
+            .set_style_url(&format!("http://127.0.0.1:{port}/keyed.json"))
+            .unwrap();
+        other
+            .set_style_url(&format!("http://127.0.0.1:{port}/other.json"))
+            .unwrap();
+        assert!(keyed.render_static().is_err());
+        assert!(other.render_static().is_err());
+
+        let requests = requests.lock().unwrap();
+        assert!(requests
+            .iter()
+            .any(|line| line.contains("/keyed.json?key=secret")));
+        assert!(requests.iter().any(|line| line.contains("/other.json")));
+        assert!(requests
+            .iter()
+            .all(|line| !line.contains("/other.json?key")));
+    }
+
+    #[test]
+    fn frame_timeout() {
+        // A server that accepts connections but never responds
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let connections: Vec<_> = listener.incoming().collect();
+            drop(connections);
+        });
+
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64)
+            .with_in_memory_cache()
+            .with_frame_timeout(Duration::from_millis(500));
+        let mut map = opts.build_continuous_renderer();
+        map.set_style_url(&format!("http://127.0.0.1:{port}/style.json"))
+            .unwrap();
+        let start = std::time::Instant::now();
+        let result = map.render_frame();
+        assert!(
+            matches!(result, Err(RenderError::Timeout(_))),
+            "{:?}",
+            result.err()
+        );
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn try_build() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64)
+            .with_offscreen_context(OffscreenContext::Default);
+        let mut map = opts.try_build_static_renderer().unwrap();
+        map.set_style_json(r#"{"version": 8, "sources": {}, "layers": []}"#)
+            .unwrap();
+        assert!(!map.render_static().unwrap().is_empty());
+    }
+
+    #[test]
+    fn set_size() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256).with_pixel_ratio(2.0);
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_camera(0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+        let small = map.render_static().unwrap();
+        assert_eq!(small.dimensions(), Some((512, 512)));
+
+        map.set_size(512, 384).unwrap();
+        let large = map.render_static().unwrap();
+        assert_eq!(large.dimensions(), Some((1024, 768)));
+        assert_eq!(map.render_static_raw().unwrap().width(), 1024);
+        assert!((map.get_camera().zoom - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn clamp_tile_ranges() {
+        assert_eq!(clamp_tile_range(&(..), 4), 0..4);
+        assert_eq!(clamp_tile_range(&(1..=2), 4), 1..3);
+        assert_eq!(clamp_tile_range(&(2..10), 4), 2..4);
+        assert_eq!(clamp_tile_range(&(5..), 4), 4..4);
+        assert_eq!(clamp_tile_range(&(..=u32::MAX), u32::MAX), 0..u32::MAX);
+    }
+
+    #[test]
+    fn tile_range() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256);
+        let mut map = opts.build_tile_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+
+        let tiles: Vec<_> = map.render_tile_range(1, .., 1..5).collect();
+        let coords: Vec<_> = tiles.iter().map(|(x, y, _)| (*x, *y)).collect();
+        assert_eq!(coords, [(0, 1), (1, 1)]);
+        for (_, _, image) in tiles {
+            assert_eq!(image.unwrap().dimensions(), Some((256, 256)));
+        }
+        assert_eq!(map.render_tile_range(1, 2.., ..).count(), 0);
+    }
+
+    #[test]
+    fn padding() {
+        let style = r#"{
+            "version": 8,
+            "sources": {
+                "point": {
+                    "type": "geojson",
+                    "data": {"type": "Point", "coordinates": [20.0, 10.0]}
+                }
+            },
+            "layers": [{"id": "point", "type": "circle", "source": "point"}]
+        }"#;
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256);
+        let mut map = opts.build_static_renderer();
+        map.set_style_json(style).unwrap();
+        map.set_camera(20.0, 10.0, 3.0, 0.0, 0.0).unwrap();
+        let centered = map.render_static().unwrap();
+
+        let sidebar = Padding {
+            left: 100.0,
+            ..Padding::default()
+        };
+        map.set_padding(sidebar)
+            .unwrap()
+            .set_camera(20.0, 10.0, 3.0, 0.0, 0.0)
+            .unwrap();
+        let padded = map.render_static().unwrap();
+        assert_ne!(decode(&centered), decode(&padded));
+
+        // The circle moves from the image center to the middle of the area right of the sidebar
+        let alpha = |image: &Image, x: usize| decode(image)[128 * 256 + x][3];
+        assert_eq!(alpha(&centered, 128), 255);
+        assert_eq!(alpha(&padded, 128), 0);
+        assert_eq!(alpha(&padded, 178), 255);
+        let camera = map.get_camera();
+        assert!((camera.lon - 20.0).abs() < 1e-6, "{camera:?}");
+
+        map.set_padding(Padding::default()).unwrap();
+        assert_eq!(decode(&map.render_static().unwrap()), decode(&centered));
+    }
+
+    #[test]
+    fn camera_anchor() {
+        let style = r#"{
+            "version": 8,
+            "sources": {
+                "point": {
+                    "type": "geojson",
+                    "data": {"type": "Point", "coordinates": [20.0, 10.0]}
+                }
+            },
+            "layers": [{"id": "point", "type": "circle", "source": "point"}]
+        }"#;
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256);
+        let mut map = opts.build_static_renderer();
+        map.set_style_json(style).unwrap();
+        let camera = CameraState {
+            lon: 20.0,
+            lat: 10.0,
+            zoom: 3.0,
+            ..CameraState::default()
+        };
+        map.set_camera_with(camera, Padding::default(), None)
+            .unwrap();
+        let centered = map.render_static().unwrap();
+        map.set_camera(20.0, 10.0, 3.0, 0.0, 0.0).unwrap();
+        assert_eq!(decode(&map.render_static().unwrap()), decode(&centered));
+
+        map.set_camera_with(camera, Padding::default(), Some((200.0, 128.0)))
+            .unwrap();
+        let anchored = map.render_static().unwrap();
+        assert_ne!(decode(&centered), decode(&anchored));
+        let alpha = |image: &Image, x: usize| decode(image)[128 * 256 + x][3];
+        assert_eq!(alpha(&centered, 128), 255);
+        assert_eq!(alpha(&anchored, 128), 0);
+        assert_eq!(alpha(&anchored, 200), 255);
+        // The middle of the viewport now shows a position west of the anchored center
+        assert!(map.get_camera().lon < 20.0, "{:?}", map.get_camera());
+
+        // Extra padding only applies to this camera change, like the anchor
+        let sidebar = Padding {
+            left: 144.0,
+            ..Padding::default()
+        };
+        map.set_camera_with(camera, sidebar, None).unwrap();
+        assert_eq!(decode(&map.render_static().unwrap()), decode(&anchored));
+        map.set_camera(20.0, 10.0, 3.0, 0.0, 0.0).unwrap();
+        assert_eq!(decode(&map.render_static().unwrap()), decode(&centered));
+    }
 }
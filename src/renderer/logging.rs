@@ -0,0 +1,94 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Once;
+
+use log::Level;
+
+use crate::renderer::bridge::ffi::{self, LogSeverity};
+
+/// Target of all records forwarded from MapLibre Native.
+const LOG_TARGET: &str = "maplibre_native";
+
+/// Forward MapLibre Native's internal log messages to the [`log`] crate.
+///
+/// Without this, MapLibre's messages are discarded. Records use the `maplibre_native` target,
+/// and are prefixed with MapLibre's event category, e.g. `Style` or `OpenGL`.
+/// Calling this more than once has no further effect.
+pub fn init_logging() {
+    static INIT: Once = Once::new();
+    INIT.call_once(ffi::installLogObserver);
+}
+
+/// Called by the C++ log observer for every record.
+///
+/// MapLibre logs from its own worker threads, so this may run on threads not started by Rust.
+pub fn log_record(severity: LogSeverity, event: &str, code: i64, message: &str) {
+    let level = match severity {
+        LogSeverity::Debug => Level::Debug,
+        LogSeverity::Info => Level::Info,
+        LogSeverity::Warning => Level::Warn,
+        _ => Level::Error,
+    };
+    // A panicking logger must not unwind into C++, which would abort the process
+    let _ = catch_unwind(AssertUnwindSafe(|| {
+        // MapLibre uses -1 for records without a code
+        if code < 0 {
+            log::log!(target: LOG_TARGET, level, "{event}: {message}");
+        } else {
+            log::log!(target: LOG_TARGET, level, "{event} ({code}): {message}");
+        }
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    use log::{LevelFilter, Log, Metadata, Record};
+
+    use super::*;
+    use crate::renderer::ImageRendererOptions;
+
+    static RECORDS: Mutex<Vec<(Level, String)>> = Mutex::new(Vec::new());
+
+    struct Capture;
+
+    impl Log for Capture {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            metadata.target() == LOG_TARGET
+        }
+
+        fn log(&self, record: &Record<'_>) {
+            if self.enabled(record.metadata()) {
+                let message = record.args().to_string();
+                RECORDS.lock().unwrap().push((record.level(), message));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn forward_records() {
+        log::set_logger(&Capture).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+        init_logging();
+        init_logging();
+
+        let mut map = ImageRendererOptions::new().build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/missing-style.json")
+            .unwrap();
+        assert!(map.render_static().is_err());
+
+        // MapLibre may write its records from a separate thread
+        let start = Instant::now();
+        while RECORDS.lock().unwrap().is_empty() && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        let records = RECORDS.lock().unwrap();
+        assert!(
+            records.iter().any(|(level, _)| *level <= Level::Warn),
+            "{records:?}"
+        );
+    }
+}
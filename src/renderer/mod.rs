@@ -1,7 +1,53 @@
+mod animation;
+mod assets;
 mod bridge;
+mod camera;
+mod cancel;
+mod compare;
+mod debug_flags;
+mod dimensions;
+mod graticule;
 mod image_renderer;
+mod logging;
+mod offline;
+mod one_shot;
 mod options;
+mod parse;
+mod provider_keys;
+mod resources;
+mod retile;
+mod scheme;
+#[cfg(feature = "serde")]
+mod serde_impls;
+mod session;
+mod style;
+mod style_source;
 
-pub use bridge::ffi::{MapDebugOptions, MapMode};
-pub use image_renderer::{Image, ImageRenderer, Static, Tile};
-pub use options::ImageRendererOptions;
+pub use animation::{encode_animation, AnimationError, AnimationFormat};
+pub use bridge::ffi::{
+    CameraState, CollisionBox, CollisionBoxKind, ConstrainMode, FeatureInfo, MapDebugOptions,
+    MapMode, Padding, RegionStats, ResourceEvent, ResourceKind, ResourceStatus, Rgba, ScreenRect,
+};
+pub(crate) use camera::check_center;
+pub use camera::{Bearing, CameraValueError, LatLng, Pitch, Zoom};
+pub use cancel::CancellationToken;
+pub use compare::CompareError;
+pub use debug_flags::MapDebugFlags;
+pub(crate) use image_renderer::tile_center;
+pub use image_renderer::{
+    BufferedTile, Continuous, Image, ImageRenderer, RawImage, RenderError, Static, StyleUrlError,
+    Tile, TileCrop,
+};
+pub use logging::init_logging;
+pub use one_shot::render_static_image;
+pub use options::{
+    set_max_concurrent_requests, BuildError, ImageRendererOptions, OffscreenContext,
+    StyleTemplates, TileSize,
+};
+pub use parse::ParseEnumError;
+pub(crate) use retile::to_tile_coords;
+pub use retile::{LatLngBounds, RetileError, TileCoord};
+pub use scheme::ResolvedResource;
+pub use session::{StyleSession, StyleSnapshot};
+pub use style::StyleError;
+pub use style_source::{SetStyleError, StyleSource};
@@ -0,0 +1,95 @@
+use crate::renderer::bridge::ffi;
+use crate::renderer::image_renderer::check_status;
+use crate::renderer::{ImageRenderer, LatLngBounds, RegionStats, RenderError};
+
+impl<S> ImageRenderer<S> {
+    /// Download all resources needed to render `bounds` from `min_zoom` to `max_zoom`
+    /// into the cache database, so that the region can later be rendered without network access.
+    ///
+    /// The region is defined for the style set by [`set_style_url`](Self::set_style_url),
+    /// and includes its tiles, sprites, and glyphs at the configured resource pixel ratio.
+    /// Resources of offline regions are never evicted from the cache. Each call stores
+    /// a new region, but resources shared with other regions are only stored once.
+    /// This blocks until the download is complete, which may take a long time for large regions.
+    ///
+    /// # Errors
+    /// Returns [`RenderError::StyleLoad`] if the style was not set by URL,
+    /// [`RenderError::Network`] if a resource could not be fetched,
+    /// or [`RenderError::Cache`] if the region could not be stored.
+    pub fn download_region(
+        &mut self,
+        bounds: LatLngBounds,
+        min_zoom: u8,
+        max_zoom: u8,
+    ) -> Result<RegionStats, RenderError> {
+        assert!(
+            bounds.west <= bounds.east && bounds.south <= bounds.north,
+            "Invalid bounds {bounds:?}"
+        );
+        assert!(
+            min_zoom <= max_zoom,
+            "Minimum zoom {min_zoom} is above maximum zoom {max_zoom}"
+        );
+        let mut region_stats = RegionStats::default();
+        let mut error = String::new();
+        let status = ffi::MapRenderer_downloadRegion(
+            self.0.pin_mut(),
+            bounds.west,
+            bounds.south,
+            bounds.east,
+            bounds.north,
+            f64::from(min_zoom),
+            f64::from(max_zoom),
+            &mut region_stats,
+            &mut error,
+        );
+        check_status(status, error)?;
+        Ok(region_stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::ImageRendererOptions;
+
+    #[test]
+    fn download_region() {
+        let cache = std::env::temp_dir().join("mln_offline_region.sqlite");
+        let _ = std::fs::remove_file(&cache);
+        let mut opts = ImageRendererOptions::new();
+        opts.with_cache_path(&cache);
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+
+        let bounds = LatLngBounds {
+            west: 5.0,
+            south: 45.0,
+            east: 15.0,
+            north: 55.0,
+        };
+        let stats = map.download_region(bounds, 0, 2).unwrap();
+        assert!(stats.tiles > 0, "{stats:?}");
+        assert!(stats.resources > stats.tiles, "{stats:?}");
+        assert!(stats.bytes > 0, "{stats:?}");
+        map.flush().unwrap();
+    }
+
+    #[test]
+    fn download_region_without_style_url() {
+        let mut map = ImageRendererOptions::new().build_static_renderer();
+        map.set_style_json(r#"{"version": 8, "sources": {}, "layers": []}"#)
+            .unwrap();
+        let bounds = LatLngBounds {
+            west: -10.0,
+            south: -10.0,
+            east: 10.0,
+            north: 10.0,
+        };
+        assert!(matches!(
+            map.download_region(bounds, 0, 1),
+            Err(RenderError::StyleLoad(_))
+        ));
+    }
+}
@@ -0,0 +1,59 @@
+use crate::renderer::{CameraState, Image, ImageRendererOptions, StyleSource};
+use crate::Error;
+
+/// Render a single image of `style` with a default renderer, e.g. in scripts and tests.
+///
+/// This builds a static renderer from [`ImageRendererOptions::new`] with the given `size`
+/// in logical pixels, loads the style, moves the camera, and renders once.
+/// The renderer is dropped afterwards, so use the builder directly to render several images,
+/// or to change any other option such as the cache path, which defaults to `cache.sqlite`
+/// in the working directory.
+///
+/// # Errors
+/// Returns an error if the graphics context cannot be created, if the style could not be set,
+/// see [`ImageRenderer::set_style`](crate::ImageRenderer::set_style), if the camera center is invalid,
+/// or if rendering failed,
+/// see [`ImageRenderer::render_static`](crate::ImageRenderer::render_static).
+pub fn render_static_image(
+    style: impl Into<StyleSource>,
+    camera: CameraState,
+    size: (u32, u32),
+) -> Result<Image, Error> {
+    let mut opts = ImageRendererOptions::new();
+    opts.with_size(size.0, size.1);
+    let mut map = opts.try_build_static_renderer()?;
+    map.set_style(style)?;
+    map.set_camera(
+        camera.lon,
+        camera.lat,
+        camera.zoom,
+        camera.bearing,
+        camera.pitch,
+    )?;
+    Ok(map.render_static()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::image_renderer::tests::decode;
+
+    #[test]
+    fn one_shot() {
+        let image = render_static_image(
+            r##"{"version": 8, "sources": {}, "layers": [
+                {"id": "bg", "type": "background", "paint": {"background-color": "#00ff00"}}
+            ]}"##,
+            CameraState::default(),
+            (32, 16),
+        )
+        .unwrap();
+        assert_eq!(image.dimensions(), Some((32, 16)));
+        assert_eq!(decode(&image)[0], [0, 255, 0, 255]);
+
+        assert!(matches!(
+            render_static_image("style.json", CameraState::default(), (32, 16)),
+            Err(Error::InvalidStyleUrl(_))
+        ));
+    }
+}
@@ -1,32 +1,211 @@
+use std::collections::HashMap;
+use std::fmt;
 use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 use cxx::UniquePtr;
 
+use crate::renderer::assets::EmbeddedAssets;
 use crate::renderer::bridge::ffi;
-use crate::renderer::{ImageRenderer, MapMode, Static, Tile};
+use crate::renderer::graticule::graticule_geojson;
+use crate::renderer::{ConstrainMode, Continuous, ImageRenderer, MapMode, Rgba, Static, Tile};
+use crate::{active_backend, Error};
 
+/// SQLite database name for a cache that is never written to disk.
+const IN_MEMORY_CACHE: &str = ":memory:";
+
+/// Limit set by [`set_max_concurrent_requests`], zero to keep MapLibre's default.
+static MAX_CONCURRENT_REQUESTS: AtomicU32 = AtomicU32::new(0);
+
+/// Limit the number of network requests running at the same time, 20 by default.
+///
+/// Requests beyond the limit are queued, so raising it can greatly speed up seeding
+/// many tiles from a fast server, while lowering it avoids overloading a slow one.
+/// MapLibre's HTTP client reuses connections on its own, and negotiates HTTP/2 where
+/// the server and the linked curl support it, neither of which can be configured.
+///
+/// The limit is global, as MapLibre shares its network file source between renderers.
+/// It applies to all renderers built afterwards, on any thread. Renderers built before keep
+/// their limit unless they share the network file source of a later one, so call this once
+/// before building the first renderer.
+/// Run `cargo bench --bench max_concurrent_requests` to compare values for a server.
+///
+/// # Panics
+/// Panics if `max` is zero.
+pub fn set_max_concurrent_requests(max: usize) {
+    assert!(max > 0, "At least one request must be allowed");
+    MAX_CONCURRENT_REQUESTS.store(u32::try_from(max).unwrap_or(u32::MAX), Ordering::Relaxed);
+}
+
+/// How the OpenGL backend creates its offscreen context on Linux, see
+/// [`ImageRendererOptions::with_offscreen_context`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum OffscreenContext {
+    /// Let EGL pick its platform, which usually requires a display server
+    #[default]
+    Default,
+    /// Render without any display, using Mesa's surfaceless platform, e.g. in Docker or CI
+    Surfaceless,
+    /// Render on a GPU enumerated directly by EGL, without any display
+    Device,
+}
+
+impl OffscreenContext {
+    /// Value of the `EGL_PLATFORM` environment variable read by Mesa, empty to leave it unchanged.
+    fn egl_platform(self) -> &'static str {
+        match self {
+            Self::Default => "",
+            Self::Surfaceless => "surfaceless",
+            Self::Device => "device",
+        }
+    }
+}
+
+/// Size of the tiles produced by [`ImageRenderer::render_tile`], see [`ImageRendererOptions::with_tile_size`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum TileSize {
+    /// Standard slippy map tiles, where the whole world fits into a single 256px tile at zoom 0
+    #[default]
+    Px256,
+    /// Tiles as used by MapLibre styles, where the whole world fits into a single 512px tile at zoom 0
+    Px512,
+}
+
+impl TileSize {
+    /// Width and height of a tile in logical pixels.
+    #[must_use]
+    pub fn pixels(self) -> u32 {
+        match self {
+            Self::Px256 => 256,
+            Self::Px512 => 512,
+        }
+    }
+}
+
+/// How MapLibre resolves URLs of the [URI scheme alias](Self::uri_scheme_alias) into requests
+/// to the tile server, see [`ImageRendererOptions::with_templates`].
+///
+/// Each template is appended to the [base URL](Self::base_url) for URLs of one kind,
+/// filling in these placeholders:
+/// - `{domain}`: the source name of `maplibre://tiles/{domain}` source URLs
+/// - `{path}`: the rest of the URL after the kind, e.g. `basic` for `maplibre://maps/basic`
+/// - `{scale}`: `@2x` for sprites at a resource pixel ratio above 1, or nothing
+/// - `{format}`: `json` or `png` for the two files of a sprite
+/// - `{fontstack}`, `{start}`, `{end}`: the font names and the glyph range of a glyphs request
+///
+/// The defaults serve the MapLibre demo tiles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct StyleTemplates {
+    /// URL of the tile server all templates are relative to
+    pub base_url: String,
+    /// Scheme of the URLs resolved with the templates, e.g. `maplibre` for `maplibre://maps/basic`
+    pub uri_scheme_alias: String,
+    /// Query parameter carrying the API key, empty if the server needs no key
+    pub api_key_parameter_name: String,
+    /// Template of TileJSON URLs for `maplibre://tiles/{domain}`, using `{domain}`
+    pub source: String,
+    /// Template of style URLs for `maplibre://maps/{path}`, using `{path}`
+    pub style: String,
+    /// Template of sprite URLs for `maplibre://sprites/{path}`, using `{path}`, `{scale}`, and `{format}`
+    pub sprites: String,
+    /// Template of glyph URLs for `maplibre://fonts/{fontstack}/{start}-{end}.pbf`
+    pub glyphs: String,
+    /// Template of tile URLs for `maplibre://tiles/{path}`, using `{path}`
+    pub tile: String,
+}
+
+impl Default for StyleTemplates {
+    fn default() -> Self {
+        Self {
+            base_url: "https://demotiles.maplibre.org".to_string(),
+            uri_scheme_alias: "maplibre".to_string(),
+            api_key_parameter_name: String::new(),
+            source: "/tiles/{domain}.json".to_string(),
+            style: "{path}.json".to_string(),
+            sprites: "/{path}/sprite{scale}.{format}".to_string(),
+            glyphs: "/font/{fontstack}/{start}-{end}.pbf".to_string(),
+            tile: "/{path}".to_string(),
+        }
+    }
+}
+
+/// Invalid value or combination of [`ImageRendererOptions`], found before a renderer is built,
+/// see [`ImageRendererOptions::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildError {
+    /// Name of the offending option, e.g. `api_key` for [`ImageRendererOptions::with_api_key`]
+    pub field: &'static str,
+    /// Why the value cannot be used
+    pub reason: String,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid renderer option {}: {}", self.field, self.reason)
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Settings of a renderer, configured with the `with_*` setters and consumed by the `build_*` methods.
+///
+/// With the `serde` feature, the options can be loaded from a configuration file,
+/// using the names of the setters without their prefix, e.g. `pixel_ratio`, and defaults for the rest.
+/// [Embedded assets](Self::with_embedded_assets) are not serialized.
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct ImageRendererOptions {
     width: u32,
     height: u32,
     pixel_ratio: f32,
-    // FIXME: can we make this an Option<PathBuf>
-    cache_path: String,
-    // FIXME: can we make this an Option<PathBuf>
-    asset_root: String,
+    resource_pixel_ratio: Option<f32>,
+    /// `None` to keep the cache in memory
+    cache_path: Option<PathBuf>,
+    asset_root: PathBuf,
     // TODO: remove?
     api_key: String,
 
-    base_url: String,
-    uri_scheme_alias: String,
-    api_key_parameter_name: String,
-    source_template: String,
-    style_template: String,
-    sprites_template: String,
-    glyphs_template: String,
-    tile_template: String,
+    templates: StyleTemplates,
     default_style_url: String,
     requires_api_key: bool,
+    provider_keys: Vec<ffi::ProviderKey>,
+    label_density: f64,
+    locale: Option<String>,
+    fade_duration: Option<Duration>,
+    request_timeout: Option<Duration>,
+    frame_timeout: Duration,
+    constrain_mode: ConstrainMode,
+    local_ideograph_font_family: Option<String>,
+    letterbox_color: Option<Rgba>,
+    background_color: Option<Rgba>,
+    transparent_background: bool,
+    graticule: Option<(f64, Rgba)>,
+    offscreen_context: OffscreenContext,
+    tile_size: TileSize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    embedded_assets: Option<Arc<HashMap<String, Vec<u8>>>>,
 }
 
 impl Default for ImageRendererOptions {
@@ -42,19 +221,28 @@ impl ImageRendererOptions {
             width: 512,
             height: 512,
             pixel_ratio: 1.0,
-            cache_path: "cache.sqlite".to_string(),
-            asset_root: ".".to_string(),
+            resource_pixel_ratio: None,
+            cache_path: Some(PathBuf::from("cache.sqlite")),
+            asset_root: PathBuf::from("."),
             api_key: String::new(),
-            base_url: "https://demotiles.maplibre.org".to_string(),
-            uri_scheme_alias: "maplibre".to_string(),
-            api_key_parameter_name: String::new(),
-            source_template: "/tiles/{domain}.json".to_string(),
-            style_template: "{path}.json".to_string(),
-            sprites_template: "/{path}/sprite{scale}.{format}".to_string(),
-            glyphs_template: "/font/{fontstack}/{start}-{end}.pbf".to_string(),
-            tile_template: "/{path}".to_string(),
+            templates: StyleTemplates::default(),
             default_style_url: String::from("https://demotiles.maplibre.org/style.json"),
             requires_api_key: false,
+            provider_keys: Vec::new(),
+            label_density: 1.0,
+            locale: None,
+            fade_duration: None,
+            request_timeout: None,
+            frame_timeout: Duration::from_secs(60),
+            constrain_mode: ConstrainMode::HeightOnly,
+            local_ideograph_font_family: None,
+            letterbox_color: None,
+            background_color: None,
+            transparent_background: false,
+            graticule: None,
+            offscreen_context: OffscreenContext::Default,
+            tile_size: TileSize::Px256,
+            embedded_assets: None,
         }
     }
 
@@ -69,63 +257,112 @@ impl ImageRendererOptions {
         self
     }
 
-    pub fn with_cache_path(&mut self, cache_path: String) -> &mut Self {
-        self.cache_path = cache_path;
+    /// Use a different pixel ratio for resources than for the output image,
+    /// e.g. to render a 1x image with 2x sprites.
+    ///
+    /// This ratio selects the sprites, glyphs, and raster tiles, e.g. for the `{scale}` placeholder
+    /// of the sprites template, while the image size still follows [`with_pixel_ratio`](Self::with_pixel_ratio).
+    /// Defaults to the image pixel ratio.
+    pub fn with_resource_pixel_ratio(&mut self, pixel_ratio: f32) -> &mut Self {
+        self.resource_pixel_ratio = Some(pixel_ratio);
         self
     }
 
-    pub fn with_asset_root(&mut self, asset_root: String) -> &mut Self {
-        self.asset_root = asset_root;
+    pub fn with_cache_path(&mut self, cache_path: impl Into<PathBuf>) -> &mut Self {
+        self.cache_path = Some(cache_path.into());
         self
     }
 
-    pub fn with_api_key(&mut self, api_key: String) -> &mut Self {
-        self.api_key = api_key;
+    /// Keep the cache in memory instead of a database file at the cache path,
+    /// e.g. on a read-only file system. The cache is discarded once all renderers are dropped.
+    pub fn with_in_memory_cache(&mut self) -> &mut Self {
+        self.cache_path = None;
         self
     }
 
-    pub fn with_base_url(&mut self, base_url: String) -> &mut Self {
-        self.base_url = base_url;
+    pub fn with_asset_root(&mut self, asset_root: impl Into<PathBuf>) -> &mut Self {
+        self.asset_root = asset_root.into();
         self
     }
 
-    pub fn with_uri_scheme_alias(&mut self, uri_scheme_alias: String) -> &mut Self {
-        self.uri_scheme_alias = uri_scheme_alias;
+    /// Serve `asset://` URLs from memory instead of the [asset root](Self::with_asset_root),
+    /// e.g. a style with its sprites and glyphs embedded into the binary with `include_bytes!`.
+    ///
+    /// Keys are paths relative to the scheme, e.g. `styles/basic.json` for `asset://styles/basic.json`.
+    /// Requests for paths not in the map fail as not found. Renderers built from the same options
+    /// share the assets without copying them.
+    pub fn with_embedded_assets(&mut self, assets: HashMap<String, Vec<u8>>) -> &mut Self {
+        self.embedded_assets = Some(Arc::new(assets));
         self
     }
 
-    pub fn with_api_key_parameter_name(&mut self, api_key_parameter_name: String) -> &mut Self {
-        self.api_key_parameter_name = api_key_parameter_name;
+    pub fn with_api_key(&mut self, api_key: impl Into<String>) -> &mut Self {
+        self.api_key = api_key.into();
         self
     }
 
-    pub fn with_source_template(&mut self, source_template: String) -> &mut Self {
-        self.source_template = source_template;
+    /// Replace the tile server URL, the scheme alias, and all URL templates at once.
+    ///
+    /// Setting them together avoids mixing the templates of different providers.
+    /// The individual setters below change a single value of the current templates.
+    pub fn with_templates(&mut self, templates: StyleTemplates) -> &mut Self {
+        self.templates = templates;
         self
     }
 
-    pub fn with_style_template(&mut self, style_template: String) -> &mut Self {
-        self.style_template = style_template;
+    /// See [`StyleTemplates::base_url`].
+    pub fn with_base_url(&mut self, base_url: impl Into<String>) -> &mut Self {
+        self.templates.base_url = base_url.into();
         self
     }
 
-    pub fn with_sprites_template(&mut self, sprites_template: String) -> &mut Self {
-        self.sprites_template = sprites_template;
+    /// See [`StyleTemplates::uri_scheme_alias`].
+    pub fn with_uri_scheme_alias(&mut self, uri_scheme_alias: impl Into<String>) -> &mut Self {
+        self.templates.uri_scheme_alias = uri_scheme_alias.into();
         self
     }
 
-    pub fn with_glyphs_template(&mut self, glyphs_template: String) -> &mut Self {
-        self.glyphs_template = glyphs_template;
+    /// See [`StyleTemplates::api_key_parameter_name`].
+    pub fn with_api_key_parameter_name(
+        &mut self,
+        api_key_parameter_name: impl Into<String>,
+    ) -> &mut Self {
+        self.templates.api_key_parameter_name = api_key_parameter_name.into();
         self
     }
 
-    pub fn with_tile_template(&mut self, tile_template: String) -> &mut Self {
-        self.tile_template = tile_template;
+    /// See [`StyleTemplates::source`].
+    pub fn with_source_template(&mut self, source_template: impl Into<String>) -> &mut Self {
+        self.templates.source = source_template.into();
         self
     }
 
-    pub fn with_default_style_url(&mut self, default_style_url: String) -> &mut Self {
-        self.default_style_url = default_style_url;
+    /// See [`StyleTemplates::style`].
+    pub fn with_style_template(&mut self, style_template: impl Into<String>) -> &mut Self {
+        self.templates.style = style_template.into();
+        self
+    }
+
+    /// See [`StyleTemplates::sprites`].
+    pub fn with_sprites_template(&mut self, sprites_template: impl Into<String>) -> &mut Self {
+        self.templates.sprites = sprites_template.into();
+        self
+    }
+
+    /// See [`StyleTemplates::glyphs`].
+    pub fn with_glyphs_template(&mut self, glyphs_template: impl Into<String>) -> &mut Self {
+        self.templates.glyphs = glyphs_template.into();
+        self
+    }
+
+    /// See [`StyleTemplates::tile`].
+    pub fn with_tile_template(&mut self, tile_template: impl Into<String>) -> &mut Self {
+        self.templates.tile = tile_template.into();
+        self
+    }
+
+    pub fn with_default_style_url(&mut self, default_style_url: impl Into<String>) -> &mut Self {
+        self.default_style_url = default_style_url.into();
         self
     }
 
@@ -134,42 +371,457 @@ impl ImageRendererOptions {
         self
     }
 
+    /// Append `param=key` to every request sent to a host matching `host_pattern`.
+    ///
+    /// Unlike the single `api_key`, this allows composite styles to reference several providers,
+    /// each expecting its own key parameter. The pattern is either an exact host name,
+    /// or a `*.example.com` wildcard matching any of its subdomains.
+    /// Can be called multiple times to register several providers.
+    ///
+    /// Query parameters are the only way to authenticate requests, as MapLibre Native
+    /// does not support adding custom HTTP headers to them.
+    pub fn with_provider_key(
+        &mut self,
+        host_pattern: impl Into<String>,
+        param: impl Into<String>,
+        key: impl Into<String>,
+    ) -> &mut Self {
+        self.provider_keys.push(ffi::ProviderKey {
+            host_pattern: host_pattern.into(),
+            param: param.into(),
+            key: key.into(),
+        });
+        self
+    }
+
+    /// Tune how densely labels are placed, e.g. for very wide or tall images.
+    ///
+    /// MapLibre has no label density setting, so this is approximated once the style is loaded
+    /// by dividing the `symbol-spacing` and `text-padding` of all symbol layers by `density`.
+    /// Values above `1.0` place more labels, values below place fewer.
+    /// Only constant property values are scaled, expressions are left as is.
+    pub fn with_label_density(&mut self, density: f64) -> &mut Self {
+        assert!(density > 0.0, "Label density must be positive");
+        self.label_density = density;
+        self
+    }
+
+    /// Show labels in the language `locale`, e.g. `de` or `zh-Hant`, where the data has names in it.
+    ///
+    /// MapLibre Native has no localization of its own, so once the style is loaded, every label
+    /// showing the `name` property is changed to show `name:{locale}` instead, following the
+    /// OpenStreetMap and OpenMapTiles convention. Features without a name in that language
+    /// keep showing `name`. Labels built from other properties, e.g. `name:latin` or `ref`,
+    /// are left as is.
+    pub fn with_locale(&mut self, locale: impl Into<String>) -> &mut Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
+    /// Override the duration of symbol fades and style transitions.
+    ///
+    /// By default, the style's `transition` property is used, which MapLibre defaults to 300ms.
+    /// A zero duration disables fades entirely, so that captured frames never show
+    /// partially transparent labels.
+    pub fn with_fade_duration(&mut self, duration: Duration) -> &mut Self {
+        self.fade_duration = Some(duration);
+        self
+    }
+
+    /// Fail resource requests that take longer than `timeout`, e.g. because a tile server stalls,
+    /// so that the render returns [`RenderError::Timeout`](crate::RenderError::Timeout) instead of waiting indefinitely.
+    ///
+    /// The limit applies to each request, including the time spent reading the cache,
+    /// and only to the requests of the renderers built from these options.
+    /// By default, requests never time out.
+    pub fn with_request_timeout(&mut self, timeout: Duration) -> &mut Self {
+        assert!(!timeout.is_zero(), "Request timeout must not be zero");
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Limit how long a [`Continuous`](crate::Continuous) renderer waits for the map to be fully loaded
+    /// before rendering a frame, one minute by default.
+    ///
+    /// Continuous maps are not told about failed requests the way still renders are, so a stalled
+    /// request would otherwise block [`render_frame`](crate::ImageRenderer::render_frame) forever.
+    /// Once the limit is reached, the render returns [`RenderError::Timeout`](crate::RenderError::Timeout).
+    pub fn with_frame_timeout(&mut self, timeout: Duration) -> &mut Self {
+        assert!(!timeout.is_zero(), "Frame timeout must not be zero");
+        self.frame_timeout = timeout;
+        self
+    }
+
+    /// Limit how far the camera may move past the edges of the world.
+    /// Defaults to [`ConstrainMode::HeightOnly`].
+    pub fn with_constrain_mode(&mut self, constrain_mode: ConstrainMode) -> &mut Self {
+        self.constrain_mode = constrain_mode;
+        self
+    }
+
+    /// Render CJK ideographs with a locally installed font family instead of downloading them.
+    ///
+    /// When set, glyphs in the CJK Unified Ideographs, Hiragana, Katakana, and Hangul ranges
+    /// are rasterized locally, and only the other ranges are fetched from the glyphs template.
+    /// This reduces downloads for CJK-heavy maps, and keeps those scripts working offline.
+    /// Local rasterization is implemented by MapLibre only on some platforms, e.g. Apple ones.
+    /// Elsewhere, this setting has no effect and all glyphs are fetched as usual.
+    pub fn with_local_ideograph_font_family(&mut self, family: impl Into<String>) -> &mut Self {
+        self.local_ideograph_font_family = Some(family.into());
+        self
+    }
+
+    /// Paint the areas left and right of the world with a solid color.
+    ///
+    /// When the viewport is wider than the world, e.g. a 2:1 image at zoom 0, the side areas
+    /// would otherwise show copies of the world. The letterbox assumes a north-up map.
+    pub fn with_letterbox_color(&mut self, color: Rgba) -> &mut Self {
+        self.letterbox_color = Some(color);
+        self
+    }
+
+    /// Show `color` wherever the style draws nothing, instead of leaving the image transparent there.
+    ///
+    /// This acts like the clear color of the framebuffer: it is independent of the style's
+    /// `background` layers, which cover it where they are drawn, and it shows through
+    /// semi-transparent layers. Combined with [`with_transparent_background`](Self::with_transparent_background),
+    /// it replaces the style's background, e.g. to make transparent areas visible while debugging.
+    pub fn with_background_color(&mut self, color: Rgba) -> &mut Self {
+        self.background_color = Some(color);
+        self
+    }
+
+    /// Leave the map transparent wherever no other layer is drawn, e.g. to composite it as an overlay.
+    ///
+    /// The `background` layers of every loaded style are hidden, so the framebuffer is cleared to
+    /// transparent instead of filled with their color. Without a background layer, this is already
+    /// the case. PNG images keep the alpha channel, while formats without one, like JPEG, do not.
+    /// The [letterbox](Self::with_letterbox_color) is still painted if set.
+    pub fn with_transparent_background(&mut self, transparent: bool) -> &mut Self {
+        self.transparent_background = transparent;
+        self
+    }
+
+    /// Draw lines of latitude and longitude every `spacing_degrees` over the map.
+    ///
+    /// Once the style is loaded, a generated GeoJSON source and a line layer are added on top of
+    /// all other layers. Meridians end at the Web Mercator latitude limit, and the poles are skipped.
+    pub fn with_graticule(&mut self, spacing_degrees: f64, color: Rgba) -> &mut Self {
+        assert!(
+            spacing_degrees > 0.0 && spacing_degrees <= 180.0,
+            "Graticule spacing must be in (0, 180] degrees"
+        );
+        self.graticule = Some((spacing_degrees, color));
+        self
+    }
+
+    /// Select how the graphics context is created on headless Linux servers.
+    ///
+    /// This sets the `EGL_PLATFORM` environment variable of the whole process before
+    /// the renderer is built, so it only affects the OpenGL backend with Mesa's EGL.
+    /// The variable is only set by the first renderer built with a non-default context,
+    /// all renderers built later keep using the same platform, whatever their setting.
+    pub fn with_offscreen_context(&mut self, context: OffscreenContext) -> &mut Self {
+        self.offscreen_context = context;
+        self
+    }
+
+    /// Set the size of the tiles rendered by a tile renderer, 256px by default.
+    ///
+    /// Tile renderers always render square tiles of this size, ignoring [`with_size`](Self::with_size),
+    /// so that tiles rendered at the same zoom line up when stitched together.
+    /// The pixel ratio still applies, e.g. a ratio of 2 produces 512px images of 256px tiles.
+    pub fn with_tile_size(&mut self, tile_size: TileSize) -> &mut Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    /// Width of the map in logical pixels, see [`with_size`](Self::with_size).
+    #[must_use]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the map in logical pixels, see [`with_size`](Self::with_size).
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Image pixels per logical pixel, see [`with_pixel_ratio`](Self::with_pixel_ratio).
+    #[must_use]
+    pub fn pixel_ratio(&self) -> f32 {
+        self.pixel_ratio
+    }
+
+    /// Pixel ratio used to select resources, which defaults to the [image pixel ratio](Self::pixel_ratio),
+    /// see [`with_resource_pixel_ratio`](Self::with_resource_pixel_ratio).
+    #[must_use]
+    pub fn resource_pixel_ratio(&self) -> f32 {
+        self.resource_pixel_ratio.unwrap_or(self.pixel_ratio)
+    }
+
+    /// See [`with_tile_size`](Self::with_tile_size).
+    #[must_use]
+    pub fn tile_size(&self) -> TileSize {
+        self.tile_size
+    }
+
+    /// Tile server URL, scheme alias, and URL templates, see [`with_templates`](Self::with_templates).
+    #[must_use]
+    pub fn templates(&self) -> &StyleTemplates {
+        &self.templates
+    }
+
+    /// Check the options for values MapLibre cannot use, which would otherwise produce blank
+    /// or failing renders. This is done by all `build_*` methods, so calling it is only needed
+    /// to report a misconfiguration before building, e.g. while parsing a configuration file.
+    ///
+    /// # Errors
+    /// Returns the first offending option, e.g. a zero size, a pixel ratio that is not positive,
+    /// a missing API key when [one is required](Self::set_requires_api_key),
+    /// or a cache path or asset root that is not valid UTF-8.
+    pub fn validate(&self) -> Result<(), BuildError> {
+        let error = |field, reason: &str| {
+            Err(BuildError {
+                field,
+                reason: reason.to_string(),
+            })
+        };
+        if self.width == 0 || self.height == 0 {
+            return error("size", "width and height must not be zero");
+        }
+        if !(self.pixel_ratio.is_finite() && self.pixel_ratio > 0.0) {
+            return error("pixel_ratio", "must be a positive number");
+        }
+        if let Some(ratio) = self.resource_pixel_ratio {
+            if !(ratio.is_finite() && ratio > 0.0) {
+                return error("resource_pixel_ratio", "must be a positive number");
+            }
+        }
+        if self.requires_api_key && self.api_key.is_empty() {
+            return error("api_key", "an API key is required, but none was set");
+        }
+        if self
+            .cache_path
+            .as_ref()
+            .is_some_and(|p| p.to_str().is_none())
+        {
+            return error("cache_path", "path is not valid UTF-8");
+        }
+        if self.asset_root.to_str().is_none() {
+            return error("asset_root", "path is not valid UTF-8");
+        }
+        Ok(())
+    }
+
+    /// Build a renderer for still images of an arbitrary viewport.
+    ///
+    /// # Panics
+    /// Panics if the options are [invalid](Self::validate), or if the graphics context
+    /// cannot be created, see [`try_build_static_renderer`](Self::try_build_static_renderer).
     #[must_use]
     pub fn build_static_renderer(self) -> ImageRenderer<Static> {
         // TODO: Should the width/height be passed in here, or have another `build_static_with_size` method?
-        ImageRenderer::new(MapMode::Static, &self)
+        self.try_build_static_renderer()
+            .unwrap_or_else(|e| panic!("{e}"))
     }
 
+    /// Build a renderer for single tiles.
+    ///
+    /// # Panics
+    /// Panics if the options are [invalid](Self::validate), or if the graphics context
+    /// cannot be created, see [`try_build_tile_renderer`](Self::try_build_tile_renderer).
     #[must_use]
     pub fn build_tile_renderer(self) -> ImageRenderer<Tile> {
-        // TODO: Is the width/height used for this mode?
+        self.try_build_tile_renderer()
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Build a renderer for a continuously updating map, see [`ImageRenderer::render_frame`].
+    ///
+    /// # Panics
+    /// Panics if the options are [invalid](Self::validate), or if the graphics context
+    /// cannot be created, see [`try_build_continuous_renderer`](Self::try_build_continuous_renderer).
+    #[must_use]
+    pub fn build_continuous_renderer(self) -> ImageRenderer<Continuous> {
+        self.try_build_continuous_renderer()
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Same as [`build_static_renderer`](Self::build_static_renderer),
+    /// but returns an error if the options are invalid or the graphics context cannot be created.
+    ///
+    /// Use this in tools that should explain a misconfiguration or a missing driver instead of panicking.
+    /// Some drivers abort the process on failure instead of reporting it, which cannot be caught.
+    ///
+    /// # Errors
+    /// Returns [`Error::Build`] naming the offending option if the options are [invalid](Self::validate),
+    /// and [`Error::BackendUnavailable`] if no GPU or driver is available
+    /// for the [`active_backend`](crate::active_backend), e.g. in a container without access
+    /// to the host's devices.
+    pub fn try_build_static_renderer(self) -> Result<ImageRenderer<Static>, Error> {
+        ImageRenderer::new(MapMode::Static, &self)
+    }
+
+    /// Same as [`build_tile_renderer`](Self::build_tile_renderer),
+    /// but returns an error if the options are invalid or the graphics context cannot be created.
+    ///
+    /// # Errors
+    /// See [`try_build_static_renderer`](Self::try_build_static_renderer).
+    pub fn try_build_tile_renderer(mut self) -> Result<ImageRenderer<Tile>, Error> {
+        let size = self.tile_size.pixels();
+        self.with_size(size, size);
         ImageRenderer::new(MapMode::Tile, &self)
     }
+
+    /// Same as [`build_continuous_renderer`](Self::build_continuous_renderer),
+    /// but returns an error if the options are invalid or the graphics context cannot be created.
+    ///
+    /// # Errors
+    /// See [`try_build_static_renderer`](Self::try_build_static_renderer).
+    pub fn try_build_continuous_renderer(self) -> Result<ImageRenderer<Continuous>, Error> {
+        ImageRenderer::new(MapMode::Continuous, &self)
+    }
 }
 
 impl<S> ImageRenderer<S> {
     /// Private constructor.
-    fn new(map_mode: MapMode, opts: &ImageRendererOptions) -> Self {
-        let map = ffi::MapRenderer_new(
+    fn new(map_mode: MapMode, opts: &ImageRendererOptions) -> Result<Self, Error> {
+        opts.validate()?;
+        let mut error = String::new();
+        let mut map = ffi::MapRenderer_new(
             map_mode,
             opts.width,
             opts.height,
             opts.pixel_ratio,
-            &opts.cache_path,
-            &opts.asset_root,
+            opts.resource_pixel_ratio.unwrap_or(opts.pixel_ratio),
+            opts.cache_path.as_deref().map_or(IN_MEMORY_CACHE, |path| {
+                path.to_str().expect("Cache path was validated")
+            }),
+            opts.asset_root.to_str().expect("Asset root was validated"),
             &opts.api_key,
-            &opts.base_url,
-            &opts.uri_scheme_alias,
-            &opts.api_key_parameter_name,
-            &opts.source_template,
-            &opts.style_template,
-            &opts.sprites_template,
-            &opts.glyphs_template,
-            &opts.tile_template,
+            &opts.templates.base_url,
+            &opts.templates.uri_scheme_alias,
+            &opts.templates.api_key_parameter_name,
+            &opts.templates.source,
+            &opts.templates.style,
+            &opts.templates.sprites,
+            &opts.templates.glyphs,
+            &opts.templates.tile,
             &opts.default_style_url,
             opts.requires_api_key,
+            &opts.provider_keys,
+            opts.label_density,
+            opts.constrain_mode,
+            opts.local_ideograph_font_family
+                .as_deref()
+                .unwrap_or_default(),
+            opts.offscreen_context.egl_platform(),
+            Box::new(EmbeddedAssets(
+                opts.embedded_assets.clone().unwrap_or_default(),
+            )),
+            opts.embedded_assets.is_some(),
+            MAX_CONCURRENT_REQUESTS.load(Ordering::Relaxed),
+            &mut error,
         );
+        if map.is_null() {
+            return Err(Error::BackendUnavailable {
+                backend: active_backend(),
+                detail: error,
+            });
+        }
+        if let Some(locale) = &opts.locale {
+            ffi::MapRenderer_setLabelLocale(map.pin_mut(), locale);
+        }
+        if let Some(duration) = opts.fade_duration {
+            let micros = u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+            ffi::MapRenderer_setFadeDuration(map.pin_mut(), micros);
+        }
+        if let Some(timeout) = opts.request_timeout {
+            let millis = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+            ffi::MapRenderer_setRequestTimeout(map.pin_mut(), millis.max(1));
+        }
+        let millis = u64::try_from(opts.frame_timeout.as_millis()).unwrap_or(u64::MAX);
+        ffi::MapRenderer_setFrameTimeout(map.pin_mut(), millis.max(1));
+        if let Some(color) = opts.letterbox_color {
+            ffi::MapRenderer_setLetterboxColor(map.pin_mut(), color);
+        }
+        if let Some(color) = opts.background_color {
+            ffi::MapRenderer_setClearColor(map.pin_mut(), color);
+        }
+        if opts.transparent_background {
+            ffi::MapRenderer_setTransparentBackground(map.pin_mut(), true);
+        }
+        if let Some((spacing, color)) = opts.graticule {
+            ffi::MapRenderer_setGraticule(map.pin_mut(), &graticule_geojson(spacing), color)
+                .expect("Generated graticule must be valid GeoJSON");
+        }
+
+        Ok(Self(
+            map,
+            PhantomData,
+            opts.templates.uri_scheme_alias.clone(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        Self(map, PhantomData)
+    #[test]
+    fn getters() {
+        let mut opts = ImageRendererOptions::new();
+        assert_eq!((opts.width(), opts.height()), (512, 512));
+        assert_eq!(opts.templates(), &StyleTemplates::default());
+
+        opts.with_size(800, 600)
+            .with_pixel_ratio(2.0)
+            .with_tile_size(TileSize::Px512)
+            .with_base_url("https://tiles.example.com");
+        assert_eq!((opts.width(), opts.height()), (800, 600));
+        assert!((opts.pixel_ratio() - 2.0).abs() < f32::EPSILON);
+        assert!((opts.resource_pixel_ratio() - 2.0).abs() < f32::EPSILON);
+        assert_eq!(opts.tile_size(), TileSize::Px512);
+        assert_eq!(opts.templates().base_url, "https://tiles.example.com");
+        assert_eq!(opts.templates().uri_scheme_alias, "maplibre");
+
+        opts.with_resource_pixel_ratio(1.0);
+        assert!((opts.resource_pixel_ratio() - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn validate() {
+        let field = |opts: &ImageRendererOptions| opts.validate().err().map(|e| e.field);
+        let mut opts = ImageRendererOptions::new();
+        assert_eq!(field(&opts), None);
+
+        opts.set_requires_api_key(true);
+        assert_eq!(field(&opts), Some("api_key"));
+        let err = opts.clone().try_build_static_renderer().err().unwrap();
+        assert!(matches!(&err, Error::Build(e) if e.field == "api_key"));
+        assert_eq!(
+            err.to_string(),
+            "Invalid renderer option api_key: an API key is required, but none was set"
+        );
+        opts.with_api_key("secret");
+        assert_eq!(field(&opts), None);
+
+        assert_eq!(field(opts.clone().with_size(0, 100)), Some("size"));
+        assert_eq!(
+            field(opts.clone().with_pixel_ratio(0.0)),
+            Some("pixel_ratio")
+        );
+        assert_eq!(
+            field(opts.clone().with_resource_pixel_ratio(f32::NAN)),
+            Some("resource_pixel_ratio")
+        );
+        // Tile renderers always use the tile size, so the map size does not matter
+        opts.with_size(0, 0);
+        assert!(!matches!(
+            opts.try_build_tile_renderer(),
+            Err(Error::Build(_))
+        ));
     }
 }
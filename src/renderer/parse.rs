@@ -0,0 +1,138 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::renderer::{MapDebugOptions, MapMode};
+
+const MAP_MODE_NAMES: &[&str] = &["continuous", "static", "tile"];
+const MAP_MODES: &[MapMode] = &[MapMode::Continuous, MapMode::Static, MapMode::Tile];
+
+const DEBUG_OPTION_NAMES: &[&str] = &[
+    "no-debug",
+    "tile-borders",
+    "parse-status",
+    "timestamps",
+    "collision",
+    "overdraw",
+    "stencil-clip",
+    "depth-buffer",
+];
+const DEBUG_OPTIONS: &[MapDebugOptions] = &[
+    MapDebugOptions::NoDebug,
+    MapDebugOptions::TileBorders,
+    MapDebugOptions::ParseStatus,
+    MapDebugOptions::Timestamps,
+    MapDebugOptions::Collision,
+    MapDebugOptions::Overdraw,
+    MapDebugOptions::StencilClip,
+    MapDebugOptions::DepthBuffer,
+];
+
+/// Error returned when parsing an unknown [`MapMode`] or [`MapDebugOptions`] name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseEnumError {
+    /// Name of the type that was being parsed
+    pub type_name: &'static str,
+    /// The value that could not be parsed
+    pub value: String,
+    /// All accepted kebab-case names
+    pub expected: &'static [&'static str],
+}
+
+impl fmt::Display for ParseEnumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unknown {} {:?}, expected one of: {}",
+            self.type_name,
+            self.value,
+            self.expected.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ParseEnumError {}
+
+fn parse<T: Copy>(
+    type_name: &'static str,
+    names: &'static [&'static str],
+    values: &[T],
+    value: &str,
+) -> Result<T, ParseEnumError> {
+    names
+        .iter()
+        .position(|name| *name == value)
+        .map(|idx| values[idx])
+        .ok_or_else(|| ParseEnumError {
+            type_name,
+            value: value.to_string(),
+            expected: names,
+        })
+}
+
+impl FromStr for MapMode {
+    type Err = ParseEnumError;
+
+    /// Parse a kebab-case map mode name, e.g. `static`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse("map mode", MAP_MODE_NAMES, MAP_MODES, s)
+    }
+}
+
+impl TryFrom<&str> for MapMode {
+    type Error = ParseEnumError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl FromStr for MapDebugOptions {
+    type Err = ParseEnumError;
+
+    /// Parse a kebab-case debug option name, e.g. `tile-borders`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse("debug option", DEBUG_OPTION_NAMES, DEBUG_OPTIONS, s)
+    }
+}
+
+impl TryFrom<&str> for MapDebugOptions {
+    type Error = ParseEnumError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_names() {
+        assert_eq!("static".parse(), Ok(MapMode::Static));
+        assert_eq!(MapMode::try_from("tile"), Ok(MapMode::Tile));
+        assert_eq!(MapMode::try_from("continuous"), Ok(MapMode::Continuous));
+        assert_eq!(
+            MapDebugOptions::try_from("tile-borders"),
+            Ok(MapDebugOptions::TileBorders)
+        );
+        assert_eq!("depth-buffer".parse(), Ok(MapDebugOptions::DepthBuffer));
+        for (name, value) in DEBUG_OPTION_NAMES.iter().zip(DEBUG_OPTIONS) {
+            assert_eq!(MapDebugOptions::try_from(*name).as_ref(), Ok(value));
+        }
+    }
+
+    #[test]
+    fn invalid_names() {
+        let err = MapMode::try_from("Static").unwrap_err();
+        assert_eq!(err.value, "Static");
+        assert_eq!(
+            err.to_string(),
+            r#"Unknown map mode "Static", expected one of: continuous, static, tile"#
+        );
+        let err = MapDebugOptions::try_from("tile_borders").unwrap_err();
+        assert_eq!(err.type_name, "debug option");
+        assert!(err.to_string().contains("tile-borders"));
+        assert!("".parse::<MapDebugOptions>().is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt::Write as _;
+
+use crate::renderer::bridge::ffi::ProviderKey;
+
+/// Append the key query parameters of all providers matching the URL's host.
+///
+/// Called by the C++ resource loader for every request of a renderer with provider keys.
+/// A host pattern is either an exact host name like `tiles.example.com`,
+/// or a wildcard like `*.example.com` that matches any subdomain of `example.com`.
+/// Parameter names and keys are percent-encoded, parameters already present in the URL are left untouched.
+pub fn apply_provider_keys(keys: &[ProviderKey], url: &str) -> String {
+    let Some(host) = url_host(url) else {
+        return url.to_string();
+    };
+
+    let (base, fragment) = match url.find('#') {
+        Some(pos) => url.split_at(pos),
+        None => (url, ""),
+    };
+    let mut result = base.to_string();
+    for key in keys {
+        let param = percent_encode(&key.param);
+        if !host_matches(&key.host_pattern, host) || has_query_param(&result, &param) {
+            continue;
+        }
+        result.push(if result.contains('?') { '&' } else { '?' });
+        result.push_str(&param);
+        result.push('=');
+        result.push_str(&percent_encode(&key.key));
+    }
+    result.push_str(fragment);
+    result
+}
+
+/// Extract the host portion of a URL, without the user info and the port.
+fn url_host(url: &str) -> Option<&str> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = host.split(':').next().unwrap_or_default();
+    (!host.is_empty()).then_some(host)
+}
+
+fn host_matches(pattern: &str, host: &str) -> bool {
+    if let Some(domain) = pattern.strip_prefix("*.") {
+        let (host, domain) = (host.as_bytes(), domain.as_bytes());
+        host.len() > domain.len()
+            && host[host.len() - domain.len()..].eq_ignore_ascii_case(domain)
+            && host[host.len() - domain.len() - 1] == b'.'
+    } else {
+        pattern.eq_ignore_ascii_case(host)
+    }
+}
+
+/// Encode all bytes except the unreserved characters of RFC 3986.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
+fn has_query_param(url: &str, param: &str) -> bool {
+    url.split_once('?').is_some_and(|(_, query)| {
+        query
+            .split('&')
+            .any(|pair| pair.split('=').next() == Some(param))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(host_pattern: &str, param: &str, key: &str) -> ProviderKey {
+        ProviderKey {
+            host_pattern: host_pattern.to_string(),
+            param: param.to_string(),
+            key: key.to_string(),
+        }
+    }
+
+    #[test]
+    fn keys_per_host() {
+        let keys = [
+            key("tiles.example.com", "api_key", "abc"),
+            key("*.other.org", "token", "xyz"),
+        ];
+        assert_eq!(
+            apply_provider_keys(&keys, "https://tiles.example.com/v1/0/0/0.pbf"),
+            "https://tiles.example.com/v1/0/0/0.pbf?api_key=abc"
+        );
+        assert_eq!(
+            apply_provider_keys(&keys, "https://a.other.org/tiles.json?v=2#frag"),
+            "https://a.other.org/tiles.json?v=2&token=xyz#frag"
+        );
+        assert_eq!(
+            apply_provider_keys(&keys, "https://other.org/tiles.json"),
+            "https://other.org/tiles.json"
+        );
+        assert_eq!(
+            apply_provider_keys(&keys, "https://demotiles.maplibre.org/style.json"),
+            "https://demotiles.maplibre.org/style.json"
+        );
+    }
+
+    #[test]
+    fn keep_existing_param() {
+        let keys = [key("TILES.example.com", "api_key", "abc")];
+        assert_eq!(
+            apply_provider_keys(&keys, "https://user@tiles.example.com:8080/a?api_key=mine"),
+            "https://user@tiles.example.com:8080/a?api_key=mine"
+        );
+        assert_eq!(apply_provider_keys(&keys, "not a url"), "not a url");
+    }
+
+    #[test]
+    fn encode_param_and_key() {
+        let keys = [key("tiles.example.com", "api key", "a+b&c=d/é")];
+        assert_eq!(
+            apply_provider_keys(&keys, "https://tiles.example.com/a"),
+            "https://tiles.example.com/a?api%20key=a%2Bb%26c%3Dd%2F%C3%A9"
+        );
+        assert_eq!(
+            apply_provider_keys(&keys, "https://tiles.example.com/a?api%20key=mine"),
+            "https://tiles.example.com/a?api%20key=mine"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::renderer::bridge::ffi;
+use crate::renderer::{ImageRenderer, ResourceEvent};
+
+/// Callback registered with [`ImageRenderer::set_resource_observer`], owned by the C++ renderer.
+pub struct ResourceObserver(Box<dyn Fn(ResourceEvent)>);
+
+/// Called by the C++ file source for every response received by a renderer with an observer.
+pub fn notify_resource_observer(observer: &ResourceObserver, event: ResourceEvent) {
+    // A panicking observer must not unwind into C++, which would abort the process
+    let _ = catch_unwind(AssertUnwindSafe(|| (observer.0)(event)));
+}
+
+impl<S> ImageRenderer<S> {
+    /// Call `observer` with every response received for a resource requested by the map,
+    /// e.g. to find out why a render is blank.
+    ///
+    /// Events are reported on the renderer's thread while a render is loading its resources,
+    /// and when a style is set. A resource may be reported twice, once when loaded from the cache,
+    /// and again once revalidated with the server. Only the requests of this renderer are reported,
+    /// even if other renderers are built with the same options. Replaces any previously set observer.
+    /// A panic of the observer is caught and ignored, as unwinding into MapLibre aborts the process.
+    pub fn set_resource_observer(
+        &mut self,
+        observer: impl Fn(ResourceEvent) + 'static,
+    ) -> &mut Self {
+        ffi::MapRenderer_setResourceObserver(
+            self.0.pin_mut(),
+            Box::new(ResourceObserver(Box::new(observer))),
+        );
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::renderer::{ImageRendererOptions, ResourceKind, ResourceStatus};
+
+    #[test]
+    fn resource_observer() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256).with_in_memory_cache();
+        let mut map = opts.build_static_renderer();
+        let sink = Rc::clone(&events);
+        map.set_resource_observer(move |event| sink.borrow_mut().push(event));
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_camera(0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+        map.render_static().unwrap();
+
+        let events = events.borrow();
+        assert!(events.iter().any(|e| e.kind == ResourceKind::Style
+            && e.status == ResourceStatus::Ok
+            && e.url == "https://demotiles.maplibre.org/style.json"));
+        let tiles: Vec<_> = events
+            .iter()
+            .filter(|e| e.kind == ResourceKind::Tile)
+            .collect();
+        assert!(!tiles.is_empty(), "{events:?}");
+        assert!(tiles.iter().all(|e| e.url.contains("/tiles/")), "{tiles:?}");
+        assert!(events.iter().any(|e| e.kind == ResourceKind::Glyphs));
+    }
+
+    #[test]
+    fn resource_not_found() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut map = ImageRendererOptions::new().build_static_renderer();
+        let sink = Rc::clone(&events);
+        map.set_resource_observer(move |event| sink.borrow_mut().push(event));
+        map.set_style_url("https://demotiles.maplibre.org/missing-style.json")
+            .unwrap();
+        assert!(map.render_static().is_err());
+
+        let events = events.borrow();
+        let style = events
+            .iter()
+            .find(|e| e.kind == ResourceKind::Style)
+            .unwrap();
+        assert_eq!(style.status, ResourceStatus::NotFound, "{style:?}");
+        assert!(!style.message.is_empty());
+    }
+
+    #[test]
+    fn observer_per_renderer() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let opts = ImageRendererOptions::new();
+        let mut observed = opts.clone().build_static_renderer();
+        let mut other = opts.build_static_renderer();
+        let sink = Rc::clone(&events);
+        observed.set_resource_observer(move |event| sink.borrow_mut().push(event));
+        other
+            .set_style_url("https://demotiles.maplibre.org/missing-style.json")
+            .unwrap();
+        assert!(other.render_static().is_err());
+        assert!(events.borrow().is_empty(), "{:?}", events.borrow());
+    }
+
+    #[test]
+    fn panicking_observer() {
+        let mut map = ImageRendererOptions::new().build_static_renderer();
+        map.set_resource_observer(|event| panic!("Unexpected resource {}", event.url));
+        map.set_style_url("https://demotiles.maplibre.org/missing-style.json")
+            .unwrap();
+        assert!(map.render_static().is_err());
+    }
+}
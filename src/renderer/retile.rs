@@ -0,0 +1,263 @@
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::renderer::image_renderer::ImageData;
+use crate::renderer::Image;
+
+/// Maximum distance, in tiles, of a bounds edge from a tile boundary to still be considered aligned.
+const ALIGNMENT_TOLERANCE: f64 = 1e-6;
+
+/// Geographic bounding box, in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatLngBounds {
+    pub west: f64,
+    pub south: f64,
+    pub east: f64,
+    pub north: f64,
+}
+
+/// Slippy map tile coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileCoord {
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+/// Errors returned by [`Image::to_tiles`].
+#[derive(Debug)]
+pub enum RetileError {
+    /// The bounds edges do not fall on the tile boundaries of the requested zoom.
+    NotAligned(LatLngBounds),
+    /// The image size is not an exact multiple of the number of tiles in each direction,
+    /// or the resulting tiles are not square.
+    SizeMismatch {
+        width: u32,
+        height: u32,
+        tiles_x: u32,
+        tiles_y: u32,
+    },
+    /// The image could not be decoded as an RGBA PNG.
+    Decode(String),
+    /// A tile could not be encoded as PNG.
+    Encode(String),
+}
+
+impl fmt::Display for RetileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAligned(bounds) => {
+                write!(f, "Bounds {bounds:?} are not aligned with tile boundaries")
+            }
+            Self::SizeMismatch {
+                width,
+                height,
+                tiles_x,
+                tiles_y,
+            } => write!(
+                f,
+                "Image of {width}x{height} pixels cannot be split into {tiles_x}x{tiles_y} square tiles"
+            ),
+            Self::Decode(e) => write!(f, "Unable to decode image: {e}"),
+            Self::Encode(e) => write!(f, "Unable to encode tile: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RetileError {}
+
+impl Image {
+    /// Slice a rendered image into tiles of the given zoom level.
+    ///
+    /// `bounds` is the geographic extent of the image, and must be aligned with the tile boundaries
+    /// of `zoom`. Tile size is derived from the image size, e.g. a 512x512 image covering 2x2 tiles
+    /// produces four 256px tiles. Each tile is re-encoded as PNG.
+    /// Tiles are returned row by row, starting from the north-west corner.
+    ///
+    /// # Errors
+    /// Returns an error if the bounds are not aligned with the tile grid,
+    /// if the image size does not split evenly into square tiles, or if the PNG cannot be processed.
+    pub fn to_tiles(
+        &self,
+        bounds: LatLngBounds,
+        zoom: u8,
+    ) -> Result<Vec<(TileCoord, Image)>, RetileError> {
+        let (west, north) = to_tile_coords(bounds.west, bounds.north, zoom);
+        let (east, south) = to_tile_coords(bounds.east, bounds.south, zoom);
+        let (west, north, east, south) =
+            match (aligned(west), aligned(north), aligned(east), aligned(south)) {
+                (Some(w), Some(n), Some(e), Some(s)) if w < e && n < s => (w, n, e, s),
+                _ => return Err(RetileError::NotAligned(bounds)),
+            };
+
+        let (pixels, width, height) = decode_rgba(self.as_slice())?;
+        let (tiles_x, tiles_y) = (east - west, south - north);
+        let tile_size = width / tiles_x;
+        if width % tiles_x != 0 || height % tiles_y != 0 || height / tiles_y != tile_size {
+            return Err(RetileError::SizeMismatch {
+                width,
+                height,
+                tiles_x,
+                tiles_y,
+            });
+        }
+
+        let row_len = width as usize * 4;
+        let tile_row_len = tile_size as usize * 4;
+        let mut tiles = Vec::with_capacity((tiles_x * tiles_y) as usize);
+        for ty in 0..tiles_y {
+            for tx in 0..tiles_x {
+                let mut tile = Vec::with_capacity(tile_row_len * tile_size as usize);
+                for row in 0..tile_size {
+                    let start =
+                        (ty * tile_size + row) as usize * row_len + tx as usize * tile_row_len;
+                    tile.extend_from_slice(&pixels[start..start + tile_row_len]);
+                }
+                let coord = TileCoord {
+                    z: zoom,
+                    x: west + tx,
+                    y: north + ty,
+                };
+                let data = encode_rgba(&tile, tile_size, tile_size)
+                    .map_err(|e| RetileError::Encode(e.to_string()))?;
+                tiles.push((coord, Image(ImageData::Vec(data))));
+            }
+        }
+        Ok(tiles)
+    }
+}
+
+/// Convert a longitude and latitude into fractional Web Mercator tile coordinates.
+pub(crate) fn to_tile_coords(lon: f64, lat: f64, zoom: u8) -> (f64, f64) {
+    let zz = 2_f64.powi(i32::from(zoom));
+    let x = (lon + 180.0) / 360.0 * zz;
+    let lat = lat.to_radians();
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * zz;
+    (x, y)
+}
+
+/// Return the tile index if the fractional coordinate lies on a tile boundary.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn aligned(value: f64) -> Option<u32> {
+    let rounded = value.round();
+    ((value - rounded).abs() < ALIGNMENT_TOLERANCE && rounded >= 0.0).then_some(rounded as u32)
+}
+
+/// Decode a PNG into 8-bit RGBA pixels, with its width and height.
+pub(crate) fn decode_rgba(data: &[u8]) -> Result<(Vec<u8>, u32, u32), RetileError> {
+    let decoder = png::Decoder::new(data);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| RetileError::Decode(e.to_string()))?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buf)
+        .map_err(|e| RetileError::Decode(e.to_string()))?;
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        return Err(RetileError::Decode(format!(
+            "expected 8-bit RGBA, got {:?} {:?}",
+            info.bit_depth, info.color_type
+        )));
+    }
+    buf.truncate(info.buffer_size());
+    Ok((buf, info.width, info.height))
+}
+
+/// Encode 8-bit RGBA pixels as PNG.
+pub(crate) fn encode_rgba(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, png::EncodingError> {
+    let mut data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(pixels))?;
+    Ok(data)
+}
+
+/// Encode 8-bit RGBA pixels as JPEG, dropping the alpha channel.
+/// `quality` ranges from `0` to `100`, larger values are treated as `100`.
+pub(crate) fn encode_jpeg(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    quality: u8,
+) -> Result<Vec<u8>, String> {
+    let size = |value: u32| {
+        u16::try_from(value)
+            .map_err(|_| format!("JPEG images are limited to 65535 pixels per side, got {value}"))
+    };
+    let (width, height) = (size(width)?, size(height)?);
+    let mut data = Vec::new();
+    jpeg_encoder::Encoder::new(&mut data, quality.clamp(1, 100))
+        .encode(pixels, width, height, jpeg_encoder::ColorType::Rgba)
+        .map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+/// Encode 8-bit RGBA pixels as lossless WebP.
+pub(crate) fn encode_webp(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    image_webp::WebPEncoder::new(&mut data)
+        .encode(pixels, width, height, image_webp::ColorType::Rgba8)
+        .map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Latitude at which Web Mercator tiles end
+    const MAX_LAT: f64 = 85.051_128_779_806_59;
+
+    const WORLD: LatLngBounds = LatLngBounds {
+        west: -180.0,
+        south: -MAX_LAT,
+        east: 180.0,
+        north: MAX_LAT,
+    };
+
+    #[test]
+    fn four_tiles() {
+        // Each quadrant of a 512x512 image gets its own color
+        let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [0; 4]];
+        let mut pixels = Vec::new();
+        for y in 0..512 {
+            for x in 0..512 {
+                pixels.extend_from_slice(&colors[y / 256 * 2 + x / 256]);
+            }
+        }
+        let image = Image(ImageData::Vec(encode_rgba(&pixels, 512, 512).unwrap()));
+
+        let tiles = image.to_tiles(WORLD, 1).unwrap();
+        let coords: Vec<_> = tiles.iter().map(|(c, _)| (c.z, c.x, c.y)).collect();
+        assert_eq!(coords, [(1, 0, 0), (1, 1, 0), (1, 0, 1), (1, 1, 1)]);
+        for ((_, tile), color) in tiles.iter().zip(colors) {
+            let (pixels, width, height) = decode_rgba(tile.as_slice()).unwrap();
+            assert_eq!((width, height), (256, 256));
+            assert!(pixels.chunks_exact(4).all(|px| px == color));
+        }
+    }
+
+    #[test]
+    fn misaligned() {
+        let image = Image(ImageData::Vec(encode_rgba(&[0; 4 * 100], 10, 10).unwrap()));
+        let bounds = LatLngBounds {
+            west: -170.0,
+            ..WORLD
+        };
+        assert!(matches!(
+            image.to_tiles(bounds, 1),
+            Err(RetileError::NotAligned(_))
+        ));
+        assert!(matches!(
+            image.to_tiles(WORLD, 2),
+            Err(RetileError::SizeMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,171 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::renderer::bridge::ffi::{self, ResolveStatus};
+use crate::renderer::ImageRenderer;
+
+/// Response of a resolver registered with [`ImageRenderer::set_scheme_resolver`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolvedResource {
+    /// The content of the resource, e.g. a tile, a style, or a sprite
+    Data(Vec<u8>),
+    /// Load the resource from another URL instead, which may use any scheme, including a resolved one
+    Redirect(String),
+}
+
+type ResolveFn = dyn Fn(&str) -> Option<ResolvedResource>;
+
+/// Callback registered with [`ImageRenderer::set_scheme_resolver`], owned by the C++ renderer.
+pub struct SchemeResolver(Box<ResolveFn>);
+
+/// Called by the C++ resource loader for every request of a URL with a resolved scheme.
+pub fn resolve_scheme(
+    resolver: &SchemeResolver,
+    url: &str,
+    redirect: &mut String,
+    data: &mut Vec<u8>,
+) -> ResolveStatus {
+    // A panicking resolver must not unwind into C++, which would abort the process
+    match catch_unwind(AssertUnwindSafe(|| (resolver.0)(url))) {
+        Ok(Some(ResolvedResource::Data(value))) => {
+            *data = value;
+            ResolveStatus::Data
+        }
+        Ok(Some(ResolvedResource::Redirect(url))) => {
+            *redirect = url;
+            ResolveStatus::Redirect
+        }
+        Ok(None) | Err(_) => ResolveStatus::NotFound,
+    }
+}
+
+impl<S> ImageRenderer<S> {
+    /// Serve all requests for URLs of `scheme`, e.g. `db` for `db://tiles/1/0/0.pbf`,
+    /// with `resolver` instead of loading them from the network or a file.
+    ///
+    /// The resolver receives the full URL, and returns the content of the resource,
+    /// a URL to load it from instead, or `None` if there is no such resource.
+    /// This allows serving styles, tiles, sprites, and glyphs from a database,
+    /// object storage, or an embedded store. Resolved resources are not cached.
+    /// The resolver is called on the renderer's thread, and blocks the render while it runs.
+    /// It only serves the requests of this renderer, even if other renderers are built with the same options.
+    /// Replaces any resolver previously set for the same scheme, which is case-insensitive.
+    pub fn set_scheme_resolver(
+        &mut self,
+        scheme: &str,
+        resolver: impl Fn(&str) -> Option<ResolvedResource> + 'static,
+    ) -> &mut Self {
+        assert!(
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')),
+            "Invalid URL scheme {scheme:?}"
+        );
+        ffi::MapRenderer_setSchemeResolver(
+            self.0.pin_mut(),
+            scheme,
+            Box::new(SchemeResolver(Box::new(resolver))),
+        );
+        self
+    }
+
+    /// Whether URLs of `scheme` are served by a resolver, see [`set_scheme_resolver`](Self::set_scheme_resolver).
+    pub(crate) fn has_scheme_resolver(&self, scheme: &str) -> bool {
+        ffi::MapRenderer_hasSchemeResolver(&self.0, scheme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::renderer::image_renderer::tests::decode;
+    use crate::renderer::retile::encode_rgba;
+    use crate::renderer::ImageRendererOptions;
+
+    const STYLE: &str = r#"{
+        "version": 8,
+        "sources": {
+            "db": {"type": "raster", "tiles": ["db://tiles/{z}/{x}/{y}.png"], "tileSize": 256}
+        },
+        "layers": [
+            {"id": "background", "type": "background", "paint": {"background-color": "white"}},
+            {"id": "tiles", "type": "raster", "source": "db"}
+        ]
+    }"#;
+
+    #[test]
+    fn db_scheme() {
+        let tile = encode_rgba(&[0, 128, 0, 255].repeat(256 * 256), 256, 256).unwrap();
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256);
+        let mut map = opts.build_static_renderer();
+        map.set_scheme_resolver("db", move |url| {
+            if url == "db://alias.json" {
+                Some(ResolvedResource::Redirect("DB://style.json".to_string()))
+            } else if url == "DB://style.json" {
+                Some(ResolvedResource::Data(STYLE.as_bytes().to_vec()))
+            } else if url.starts_with("db://tiles/") {
+                Some(ResolvedResource::Data(tile.clone()))
+            } else {
+                None
+            }
+        });
+        map.set_style_url("db://alias.json").unwrap();
+        map.set_camera(0.0, 0.0, 0.0, 0.0, 0.0).unwrap();
+        let pixels = decode(&map.render_static().unwrap());
+        assert_eq!(pixels[128 * 256 + 128], [0, 128, 0, 255]);
+
+        map.set_style_url("db://missing.json").unwrap();
+        assert!(map.render_static().is_err());
+        // Only schemes with a resolver are accepted
+        assert!(map.set_style_url("other://style.json").is_err());
+    }
+
+    #[test]
+    fn resolver_per_renderer() {
+        let calls = Rc::new(Cell::new(0));
+        let opts = ImageRendererOptions::new();
+        let mut resolving = opts.clone().build_static_renderer();
+        let mut other = opts.build_static_renderer();
+        let counter = Rc::clone(&calls);
+        resolving.set_scheme_resolver("db", move |_| {
+            counter.set(counter.get() + 1);
+            None
+        });
+        other.set_style_json(STYLE).unwrap();
+        other.set_camera(0.0, 0.0, 0.0, 0.0, 0.0).unwrap();
+        let _ = other.render_static();
+        assert_eq!(calls.get(), 0);
+    }
+
+    #[test]
+    fn resolve() {
+        let resolver = SchemeResolver(Box::new(|url| {
+            assert_ne!(url, "db://panic");
+            url.strip_prefix("db://redirect/")
+                .map(|rest| ResolvedResource::Redirect(format!("https://{rest}")))
+        }));
+        let (mut redirect, mut data) = (String::new(), Vec::new());
+        assert_eq!(
+            resolve_scheme(
+                &resolver,
+                "db://redirect/example.com/a",
+                &mut redirect,
+                &mut data
+            ),
+            ResolveStatus::Redirect
+        );
+        assert_eq!(redirect, "https://example.com/a");
+        assert_eq!(
+            resolve_scheme(&resolver, "db://other", &mut redirect, &mut data),
+            ResolveStatus::NotFound
+        );
+        assert_eq!(
+            resolve_scheme(&resolver, "db://panic", &mut redirect, &mut data),
+            ResolveStatus::NotFound
+        );
+    }
+}
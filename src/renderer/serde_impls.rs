@@ -0,0 +1,234 @@
+//! Serde support for configuration-driven rendering, enabled by the `serde` feature.
+//!
+//! [`ImageRendererOptions`](crate::ImageRendererOptions) derives its implementations,
+//! with field names matching the `with_*` setters, e.g. `pixel_ratio` for `with_pixel_ratio`,
+//! and defaults for all missing fields. The types shared with C++ cannot derive them through
+//! the bridge without a required dependency, so they are implemented here instead.
+//! Values that are checked by their constructor, like [`LatLng`] and [`TileId`],
+//! are checked when deserialized as well.
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::renderer::bridge::ffi::ProviderKey;
+use crate::renderer::{CameraState, ConstrainMode, LatLng, Rgba};
+use crate::tile::TileId;
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "CameraState")]
+struct CameraStateDef {
+    #[serde(default)]
+    lon: f64,
+    #[serde(default)]
+    lat: f64,
+    #[serde(default)]
+    zoom: f64,
+    #[serde(default)]
+    bearing: f64,
+    #[serde(default)]
+    pitch: f64,
+}
+
+impl Serialize for CameraState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        CameraStateDef::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CameraState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        CameraStateDef::deserialize(deserializer)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Rgba")]
+struct RgbaDef {
+    r: u8,
+    g: u8,
+    b: u8,
+    #[serde(default = "opaque")]
+    a: u8,
+}
+
+fn opaque() -> u8 {
+    255
+}
+
+impl Serialize for Rgba {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RgbaDef::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rgba {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        RgbaDef::deserialize(deserializer)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "ProviderKey")]
+struct ProviderKeyDef {
+    host_pattern: String,
+    param: String,
+    key: String,
+}
+
+impl Serialize for ProviderKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ProviderKeyDef::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProviderKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ProviderKeyDef::deserialize(deserializer)
+    }
+}
+
+/// Kebab-case names, like the ones used for [`MapMode`](crate::MapMode) on the command line.
+const CONSTRAIN_MODE_NAMES: &[&str] = &["none", "height-only", "width-and-height"];
+
+impl Serialize for ConstrainMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = match *self {
+            Self::None => CONSTRAIN_MODE_NAMES[0],
+            Self::HeightOnly => CONSTRAIN_MODE_NAMES[1],
+            Self::WidthAndHeight => CONSTRAIN_MODE_NAMES[2],
+            _ => return Err(serde::ser::Error::custom("Unknown constrain mode")),
+        };
+        serializer.serialize_str(name)
+    }
+}
+
+impl<'de> Deserialize<'de> for ConstrainMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        match name.as_str() {
+            "none" => Ok(Self::None),
+            "height-only" => Ok(Self::HeightOnly),
+            "width-and-height" => Ok(Self::WidthAndHeight),
+            _ => Err(D::Error::unknown_variant(&name, CONSTRAIN_MODE_NAMES)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct LatLngDef {
+    lon: f64,
+    lat: f64,
+}
+
+impl Serialize for LatLng {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        LatLngDef {
+            lon: self.lon(),
+            lat: self.lat(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LatLng {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let LatLngDef { lon, lat } = LatLngDef::deserialize(deserializer)?;
+        Self::new(lon, lat).map_err(D::Error::custom)
+    }
+}
+
+/// Tiles are written as `z/x/y`, like in tile URLs.
+impl Serialize for TileId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TileId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::parse(&value).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::renderer::{ImageRendererOptions, TileSize};
+
+    #[test]
+    fn options() {
+        let opts: ImageRendererOptions = serde_json::from_str(
+            r#"{
+                "width": 800,
+                "height": 600,
+                "pixel_ratio": 2.0,
+                "cache_path": null,
+                "constrain_mode": "width-and-height",
+                "tile_size": "px512",
+                "fade_duration": {"secs": 0, "nanos": 0},
+                "letterbox_color": {"r": 255, "g": 0, "b": 255},
+                "provider_keys": [{"host_pattern": "*.example.com", "param": "key", "key": "secret"}],
+                "templates": {"base_url": "https://tiles.example.com"}
+            }"#,
+        )
+        .unwrap();
+        let mut expected = ImageRendererOptions::new();
+        expected
+            .with_size(800, 600)
+            .with_pixel_ratio(2.0)
+            .with_in_memory_cache()
+            .with_constrain_mode(ConstrainMode::WidthAndHeight)
+            .with_tile_size(TileSize::Px512)
+            .with_fade_duration(Duration::ZERO)
+            .with_letterbox_color(Rgba {
+                r: 255,
+                g: 0,
+                b: 255,
+                a: 255,
+            })
+            .with_provider_key("*.example.com", "key", "secret")
+            .with_base_url("https://tiles.example.com");
+        let json = |opts: &ImageRendererOptions| serde_json::to_value(opts).unwrap();
+        assert_eq!(json(&opts), json(&expected));
+        assert_eq!(opts.templates().uri_scheme_alias, "maplibre");
+
+        let round_trip: ImageRendererOptions = serde_json::from_value(json(&expected)).unwrap();
+        assert_eq!(json(&round_trip), json(&expected));
+        assert_eq!(
+            json(&ImageRendererOptions::new())["cache_path"],
+            serde_json::json!(PathBuf::from("cache.sqlite"))
+        );
+        assert!(
+            serde_json::from_str::<ImageRendererOptions>(r#"{"constrain_mode": "all"}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn values() {
+        let camera: CameraState = serde_json::from_str(r#"{"lon": 13.4, "zoom": 10}"#).unwrap();
+        assert_eq!(
+            camera,
+            CameraState {
+                lon: 13.4,
+                zoom: 10.0,
+                ..CameraState::default()
+            }
+        );
+
+        let position: LatLng = serde_json::from_str(r#"{"lon": 190, "lat": 52.5}"#).unwrap();
+        assert_eq!(position, LatLng::new(-170.0, 52.5).unwrap());
+        assert!(serde_json::from_str::<LatLng>(r#"{"lon": 0, "lat": 91}"#).is_err());
+
+        let tile = TileId::new(10, 550, 335).unwrap();
+        assert_eq!(serde_json::to_string(&tile).unwrap(), r#""10/550/335""#);
+        assert_eq!(
+            serde_json::from_str::<TileId>(r#""10/550/335""#).unwrap(),
+            tile
+        );
+        assert!(serde_json::from_str::<TileId>(r#""1/2/0""#).is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use crate::renderer::{Image, ImageRenderer, RenderError, Static, StyleError};
+
+/// The result of [`StyleSession::commit`].
+pub struct StyleSnapshot {
+    /// The map rendered with all mutations applied
+    pub image: Image,
+    /// The style document including all mutations, see [`ImageRenderer::style_json`]
+    pub style_json: String,
+}
+
+/// Batch of style mutations, rendered and serialized together once committed.
+///
+/// Created by [`ImageRenderer::style_session`]. Each mutation is applied to the renderer
+/// immediately, so a failed mutation can be handled right away, and the session can be
+/// previewed at any point. Mutations are not rolled back if the session is dropped
+/// without committing, as MapLibre has no style transactions.
+/// The same mutations can also be applied directly on the [`ImageRenderer`] without a session.
+pub struct StyleSession<'a> {
+    renderer: &'a mut ImageRenderer<Static>,
+}
+
+impl ImageRenderer<Static> {
+    /// Start a [`StyleSession`] to mutate the loaded style.
+    ///
+    /// The style must be loaded, e.g. by rendering once, before its layers can be modified.
+    pub fn style_session(&mut self) -> StyleSession<'_> {
+        StyleSession { renderer: self }
+    }
+}
+
+impl StyleSession<'_> {
+    /// See [`ImageRenderer::add_source`].
+    ///
+    /// # Errors
+    /// Returns an error if the definition is invalid, or if a source with this ID already exists.
+    pub fn add_source(&mut self, source_id: &str, json: &str) -> Result<&mut Self, StyleError> {
+        self.renderer.add_source(source_id, json)?;
+        Ok(self)
+    }
+
+    /// See [`ImageRenderer::add_geojson_source`].
+    ///
+    /// # Errors
+    /// Returns an error if the GeoJSON is invalid, or if a source with this ID already exists.
+    pub fn add_geojson_source(
+        &mut self,
+        source_id: &str,
+        geojson: &str,
+    ) -> Result<&mut Self, StyleError> {
+        self.renderer.add_geojson_source(source_id, geojson)?;
+        Ok(self)
+    }
+
+    /// See [`ImageRenderer::update_geojson_source`].
+    ///
+    /// # Errors
+    /// Returns an error if the GeoJSON is invalid,
+    /// or if the source does not exist or is not a GeoJSON source.
+    pub fn update_geojson_source(
+        &mut self,
+        source_id: &str,
+        geojson: &str,
+    ) -> Result<&mut Self, StyleError> {
+        self.renderer.update_geojson_source(source_id, geojson)?;
+        Ok(self)
+    }
+
+    /// See [`ImageRenderer::add_layer`].
+    ///
+    /// # Errors
+    /// Returns an error if the definition is invalid, if a layer with the same ID already exists,
+    /// or if `before_layer_id` does not exist.
+    pub fn add_layer(
+        &mut self,
+        json: &str,
+        before_layer_id: Option<&str>,
+    ) -> Result<&mut Self, StyleError> {
+        self.renderer.add_layer(json, before_layer_id)?;
+        Ok(self)
+    }
+
+    /// See [`ImageRenderer::remove_layer`].
+    ///
+    /// # Errors
+    /// Returns an error if the layer does not exist.
+    pub fn remove_layer(&mut self, layer_id: &str) -> Result<&mut Self, StyleError> {
+        self.renderer.remove_layer(layer_id)?;
+        Ok(self)
+    }
+
+    /// See [`ImageRenderer::set_property`].
+    ///
+    /// # Errors
+    /// Returns an error if the layer does not exist, or if the property or its value is invalid.
+    pub fn set_property(
+        &mut self,
+        layer_id: &str,
+        name: &str,
+        json: &str,
+    ) -> Result<&mut Self, StyleError> {
+        self.renderer.set_property(layer_id, name, json)?;
+        Ok(self)
+    }
+
+    /// See [`ImageRenderer::set_filter`].
+    ///
+    /// # Errors
+    /// Returns an error if the layer does not exist, or if the filter is invalid.
+    pub fn set_filter(&mut self, layer_id: &str, json: &str) -> Result<&mut Self, StyleError> {
+        self.renderer.set_filter(layer_id, json)?;
+        Ok(self)
+    }
+
+    /// See [`ImageRenderer::set_layer_visibility`].
+    ///
+    /// # Errors
+    /// Returns an error if the layer does not exist.
+    pub fn set_layer_visibility(
+        &mut self,
+        layer_id: &str,
+        visible: bool,
+    ) -> Result<&mut Self, StyleError> {
+        self.renderer.set_layer_visibility(layer_id, visible)?;
+        Ok(self)
+    }
+
+    /// Render the style with the mutations applied so far, without ending the session.
+    ///
+    /// # Errors
+    /// Returns an error if the render fails, see [`ImageRenderer::render_static`].
+    pub fn preview(&mut self) -> Result<Image, RenderError> {
+        self.renderer.render_static()
+    }
+
+    /// End the session, rendering the final image and serializing the mutated style.
+    ///
+    /// # Errors
+    /// Returns an error if the render fails, see [`ImageRenderer::render_static`].
+    pub fn commit(self) -> Result<StyleSnapshot, RenderError> {
+        let image = self.renderer.render_static()?;
+        let style_json = self.renderer.style_json();
+        Ok(StyleSnapshot { image, style_json })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::image_renderer::tests::decode;
+    use crate::renderer::ImageRendererOptions;
+
+    #[test]
+    fn session_mutations() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(128, 128);
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_camera(0.0, 0.0, 2.0, 0.0, 0.0).unwrap();
+        map.render_static().unwrap();
+
+        let mut session = map.style_session();
+        session
+            .add_source(
+                "marker",
+                r#"{"type": "geojson", "data": {"type": "Point", "coordinates": [0, 0]}}"#,
+            )
+            .unwrap()
+            .add_layer(
+                r#"{"id": "marker", "type": "circle", "source": "marker"}"#,
+                None,
+            )
+            .unwrap()
+            .set_property("marker", "circle-radius", "30")
+            .unwrap()
+            .set_property("marker", "circle-color", r##""#ff00ff""##)
+            .unwrap()
+            .set_filter("marker", r#"["==", "$type", "Point"]"#)
+            .unwrap();
+        assert!(session
+            .set_property("marker", "no-such-property", "1")
+            .is_err());
+        assert!(!session.preview().unwrap().as_slice().is_empty());
+
+        let snapshot = session.commit().unwrap();
+        let center = decode(&snapshot.image)[64 * 128 + 64];
+        assert_eq!(center, [255, 0, 255, 255]);
+
+        let json = snapshot.style_json;
+        assert!(json.contains(r#""marker":{"type":"geojson""#), "{json}");
+        assert!(json.contains(r#""id":"marker""#), "{json}");
+        assert!(json.contains("circle-radius"), "{json}");
+        assert!(json.contains("$type"), "{json}");
+    }
+}
@@ -0,0 +1,779 @@
+use std::fmt;
+
+use crate::renderer::bridge::ffi::{self, LayerStatus};
+use crate::renderer::{Image, ImageRenderer, ImageRendererOptions, RenderError, Static};
+
+/// Errors returned when modifying the loaded style at runtime.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StyleError {
+    /// The style has no layer with this ID. The style may not have finished loading yet.
+    LayerNotFound(String),
+    /// The style has no source with this ID. The style may not have finished loading yet.
+    SourceNotFound(String),
+    /// The layer exists, but is not of the type required by the change.
+    WrongLayerType {
+        layer_id: String,
+        expected: &'static str,
+    },
+    /// A dash array must have an even number of positive lengths.
+    InvalidDashArray(Vec<f64>),
+    /// The RGBA data of an image does not have 4 bytes for each of its pixels.
+    InvalidImageSize { width: u32, height: u32, len: usize },
+    /// MapLibre rejected a source, layer, or property definition, e.g. because of invalid JSON,
+    /// an unknown property, or a duplicate ID.
+    Invalid(String),
+}
+
+impl fmt::Display for StyleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LayerNotFound(id) => write!(f, "Layer {id:?} was not found in the style"),
+            Self::SourceNotFound(id) => write!(f, "Source {id:?} was not found in the style"),
+            Self::WrongLayerType { layer_id, expected } => {
+                write!(f, "Layer {layer_id:?} is not a {expected} layer")
+            }
+            Self::InvalidDashArray(dashes) => write!(
+                f,
+                "Dash array {dashes:?} must have an even number of positive lengths"
+            ),
+            Self::InvalidImageSize { width, height, len } => write!(
+                f,
+                "Image data of {len} bytes does not match the RGBA size of {width}x{height} pixels"
+            ),
+            Self::Invalid(e) => write!(f, "Invalid style definition: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for StyleError {}
+
+impl StyleError {
+    fn check(status: LayerStatus, layer_id: &str, expected: &'static str) -> Result<(), Self> {
+        match status {
+            LayerStatus::Ok => Ok(()),
+            LayerStatus::NotFound => Err(Self::LayerNotFound(layer_id.to_string())),
+            LayerStatus::WrongType => Err(Self::WrongLayerType {
+                layer_id: layer_id.to_string(),
+                expected,
+            }),
+            v => unreachable!("Unexpected layer status {v:?}"),
+        }
+    }
+
+    /// Same as [`check`](Self::check), but also accepts [`LayerStatus::Invalid`].
+    fn check_invalid(status: LayerStatus, layer_id: &str, error: String) -> Result<(), Self> {
+        if status == LayerStatus::Invalid {
+            Err(Self::Invalid(error))
+        } else {
+            Self::check(status, layer_id, "")
+        }
+    }
+}
+
+impl ImageRendererOptions {
+    /// Check a style document without building a renderer or loading any of its resources.
+    ///
+    /// All sources and layers are converted by MapLibre's style parser, which would otherwise
+    /// skip invalid ones with a log message, and layers must refer to a source of the style.
+    /// Problems with the resources themselves, e.g. an unreachable tile URL, are only found by rendering.
+    ///
+    /// # Errors
+    /// Returns all problems found, [`StyleError::SourceNotFound`] for each missing source,
+    /// and [`StyleError::Invalid`] for everything else.
+    pub fn validate_style(style: &str) -> Result<(), Vec<StyleError>> {
+        let mut missing_sources = Vec::new();
+        let errors: Vec<_> = ffi::validateStyle(style, &mut missing_sources)
+            .into_iter()
+            .map(StyleError::Invalid)
+            .chain(missing_sources.into_iter().map(StyleError::SourceNotFound))
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl<S> ImageRenderer<S> {
+    /// IDs of all layers of the loaded style, in draw order from bottom to top.
+    ///
+    /// Returns an empty list if the style has not finished loading yet.
+    #[must_use]
+    pub fn layer_ids(&self) -> Vec<String> {
+        ffi::MapRenderer_layerIds(&self.0)
+    }
+
+    /// Whether a layer's `visibility` layout property is `visible`.
+    ///
+    /// # Errors
+    /// Returns an error if the layer does not exist.
+    pub fn layer_visibility(&self, layer_id: &str) -> Result<bool, StyleError> {
+        let mut visible = false;
+        let status = ffi::MapRenderer_layerVisibility(&self.0, layer_id, &mut visible);
+        StyleError::check(status, layer_id, "")?;
+        Ok(visible)
+    }
+
+    /// Show or hide a layer by setting its `visibility` layout property.
+    ///
+    /// # Errors
+    /// Returns an error if the layer does not exist.
+    pub fn set_layer_visibility(
+        &mut self,
+        layer_id: &str,
+        visible: bool,
+    ) -> Result<(), StyleError> {
+        let status = ffi::MapRenderer_setLayerVisibility(self.0.pin_mut(), layer_id, visible);
+        StyleError::check(status, layer_id, "")
+    }
+
+    /// Show only the given layers, and hide all others, e.g. to render just the labels
+    /// or the water of a style for compositing.
+    ///
+    /// An empty list hides all layers, which renders a transparent image.
+    /// Use [`set_layer_visibility`](Self::set_layer_visibility) to show them again.
+    ///
+    /// # Errors
+    /// Returns an error if one of the layers does not exist, without changing any visibility.
+    pub fn set_visible_layers(&mut self, layer_ids: &[&str]) -> Result<(), StyleError> {
+        let layers = self.layer_ids();
+        if let Some(missing) = layer_ids.iter().find(|id| !layers.iter().any(|l| l == *id)) {
+            return Err(StyleError::LayerNotFound((*missing).to_string()));
+        }
+        for id in &layers {
+            self.set_layer_visibility(id, layer_ids.contains(&id.as_str()))?;
+        }
+        Ok(())
+    }
+
+    /// Add a source to the loaded style, given its JSON definition as in a style document.
+    ///
+    /// # Errors
+    /// Returns an error if the definition is invalid, or if a source with this ID already exists.
+    pub fn add_source(&mut self, source_id: &str, json: &str) -> Result<(), StyleError> {
+        let mut error = String::new();
+        let status = ffi::MapRenderer_addSource(self.0.pin_mut(), source_id, json, &mut error);
+        StyleError::check_invalid(status, source_id, error)
+    }
+
+    /// Add a GeoJSON source to the loaded style, e.g. to render a dynamic data overlay.
+    ///
+    /// Unlike [`add_source`](Self::add_source), this only takes the GeoJSON data,
+    /// i.e. a geometry, a feature, or a feature collection.
+    ///
+    /// # Errors
+    /// Returns an error if the GeoJSON is invalid, or if a source with this ID already exists.
+    pub fn add_geojson_source(&mut self, source_id: &str, geojson: &str) -> Result<(), StyleError> {
+        let mut error = String::new();
+        let status =
+            ffi::MapRenderer_addGeoJsonSource(self.0.pin_mut(), source_id, geojson, &mut error);
+        StyleError::check_invalid(status, source_id, error)
+    }
+
+    /// Replace the data of a GeoJSON source, keeping its other options and the layers using it.
+    ///
+    /// # Errors
+    /// Returns an error if the GeoJSON is invalid,
+    /// or if the source does not exist or is not a GeoJSON source.
+    pub fn update_geojson_source(
+        &mut self,
+        source_id: &str,
+        geojson: &str,
+    ) -> Result<(), StyleError> {
+        let mut error = String::new();
+        let status =
+            ffi::MapRenderer_updateGeoJsonSource(self.0.pin_mut(), source_id, geojson, &mut error);
+        match status {
+            LayerStatus::NotFound => Err(StyleError::SourceNotFound(source_id.to_string())),
+            LayerStatus::WrongType => Err(StyleError::Invalid(format!(
+                "Source {source_id:?} is not a GeoJSON source"
+            ))),
+            status => StyleError::check_invalid(status, source_id, error),
+        }
+    }
+
+    /// Set the state of a feature, e.g. `{"highlight": true}`, for `feature-state` expressions in the style.
+    ///
+    /// `source_layer` is required for vector sources. Numeric feature IDs are given in their
+    /// decimal representation. The state is merged into any state previously set for the feature,
+    /// and applies from the next render. It is cleared when a new style is loaded.
+    ///
+    /// # Errors
+    /// Returns an error if the source does not exist, or if the state is not a JSON object.
+    pub fn set_feature_state(
+        &mut self,
+        source_id: &str,
+        source_layer: Option<&str>,
+        feature_id: &str,
+        state_json: &str,
+    ) -> Result<(), StyleError> {
+        let mut error = String::new();
+        let status = ffi::MapRenderer_setFeatureState(
+            self.0.pin_mut(),
+            source_id,
+            source_layer.unwrap_or_default(),
+            feature_id,
+            state_json,
+            &mut error,
+        );
+        match status {
+            LayerStatus::NotFound => Err(StyleError::SourceNotFound(source_id.to_string())),
+            status => StyleError::check_invalid(status, source_id, error),
+        }
+    }
+
+    /// Add a layer to the loaded style, given its JSON definition as in a style document.
+    ///
+    /// The layer is drawn below `before_layer_id` if given, otherwise on top of all other layers.
+    ///
+    /// # Errors
+    /// Returns an error if the definition is invalid, if a layer with the same ID already exists,
+    /// or if `before_layer_id` does not exist.
+    pub fn add_layer(
+        &mut self,
+        json: &str,
+        before_layer_id: Option<&str>,
+    ) -> Result<(), StyleError> {
+        let before = before_layer_id.unwrap_or_default();
+        let mut error = String::new();
+        let status = ffi::MapRenderer_addLayer(self.0.pin_mut(), json, before, &mut error);
+        StyleError::check_invalid(status, before, error)
+    }
+
+    /// Remove a layer from the loaded style. Its source is kept, even if no other layer uses it.
+    ///
+    /// # Errors
+    /// Returns an error if the layer does not exist.
+    pub fn remove_layer(&mut self, layer_id: &str) -> Result<(), StyleError> {
+        let status = ffi::MapRenderer_removeLayer(self.0.pin_mut(), layer_id);
+        StyleError::check(status, layer_id, "")
+    }
+
+    /// Set a paint or layout property of a layer, given its value as JSON.
+    ///
+    /// The value may be a constant like `"#ff0000"` or `2`, or an expression.
+    ///
+    /// # Errors
+    /// Returns an error if the layer does not exist, or if the property or its value is invalid.
+    pub fn set_property(
+        &mut self,
+        layer_id: &str,
+        name: &str,
+        json: &str,
+    ) -> Result<(), StyleError> {
+        let mut error = String::new();
+        let status =
+            ffi::MapRenderer_setLayerProperty(self.0.pin_mut(), layer_id, name, json, &mut error);
+        StyleError::check_invalid(status, layer_id, error)
+    }
+
+    /// Set the filter of a layer, given as a JSON filter expression, e.g. `["==", "type", "park"]`.
+    ///
+    /// # Errors
+    /// Returns an error if the layer does not exist, or if the filter is invalid.
+    pub fn set_filter(&mut self, layer_id: &str, json: &str) -> Result<(), StyleError> {
+        self.set_property(layer_id, "filter", json)
+    }
+
+    /// Serialize the loaded style, including all changes made at runtime, as a JSON document.
+    ///
+    /// Layers are serialized from their current state. MapLibre cannot serialize sources,
+    /// so they are copied from the original style document, or from [`add_source`](Self::add_source).
+    /// Loading the document into another renderer reproduces the same map, e.g. to cache
+    /// the result of runtime changes, except for images added with [`add_image`](Self::add_image),
+    /// which are not part of it. The [graticule](crate::ImageRendererOptions::with_graticule)
+    /// is left out, as it is added by the renderer itself.
+    #[must_use]
+    pub fn style_json(&self) -> String {
+        ffi::MapRenderer_styleJson(&self.0)
+    }
+
+    /// Set the `line-dasharray` of a line layer, overriding the value from the style.
+    ///
+    /// `dashes` alternates dash and gap lengths, in multiples of the line width,
+    /// e.g. `[2.0, 1.0]` draws dashes twice as long as the gaps between them.
+    /// An empty slice makes the line solid again.
+    /// The style must be loaded, e.g. by rendering once, before its layers can be modified.
+    ///
+    /// # Errors
+    /// Returns an error if the dash array is invalid,
+    /// or if the layer does not exist or is not a line layer.
+    pub fn set_line_dash(&mut self, layer_id: &str, dashes: &[f64]) -> Result<(), StyleError> {
+        if dashes.len() % 2 != 0 || !dashes.iter().all(|v| v.is_finite() && *v > 0.0) {
+            return Err(StyleError::InvalidDashArray(dashes.to_vec()));
+        }
+        let status = ffi::MapRenderer_setLineDash(self.0.pin_mut(), layer_id, dashes);
+        StyleError::check(status, layer_id, "line")
+    }
+
+    /// Add an image to the loaded style, so that layers can use it by its ID,
+    /// e.g. a custom marker with `"icon-image": "marker"`, without a sprite sheet.
+    ///
+    /// `rgba` holds the pixels row by row, with straight (not premultiplied) alpha,
+    /// e.g. as decoded from a PNG. The image is `pixel_ratio` times larger than it is drawn
+    /// at `icon-size` 1, e.g. `2.0` for an icon made for high-DPI screens.
+    /// If `sdf` is set, the alpha channel is read as a signed distance field, so that the icon
+    /// is drawn in the layer's `icon-color`, with an optional `icon-halo-color`.
+    /// An image with the same ID, e.g. from the sprite, is replaced.
+    /// Images are cleared when a new style is loaded.
+    ///
+    /// # Errors
+    /// Returns an error if `rgba` does not contain exactly `width * height` pixels.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_image(
+        &mut self,
+        id: &str,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        pixel_ratio: f32,
+        sdf: bool,
+    ) -> Result<(), StyleError> {
+        assert!(pixel_ratio > 0.0, "Pixel ratio must be positive");
+        let expected = u64::from(width) * u64::from(height) * 4;
+        if width == 0 || height == 0 || u64::try_from(rgba.len()) != Ok(expected) {
+            return Err(StyleError::InvalidImageSize {
+                width,
+                height,
+                len: rgba.len(),
+            });
+        }
+        ffi::MapRenderer_addImage(self.0.pin_mut(), id, rgba, width, height, pixel_ratio, sdf);
+        Ok(())
+    }
+}
+
+impl ImageRenderer<Static> {
+    /// Render the style cumulatively, one layer at a time, to debug the layer order.
+    ///
+    /// For each layer in draw order, renders the image with all layers up to and including it,
+    /// and returns it together with the layer ID. Layers hidden by the style stay hidden.
+    /// This performs one full render per layer, so it is slow for styles with many layers.
+    /// The original visibility of all layers is restored afterwards.
+    /// The style must be loaded, e.g. by rendering once, otherwise the result is empty.
+    ///
+    /// # Errors
+    /// Returns the first render error. The layer visibility is restored regardless.
+    pub fn render_onion_skin(&mut self) -> Result<Vec<(String, Image)>, RenderError> {
+        let layers: Vec<(String, bool)> = self
+            .layer_ids()
+            .into_iter()
+            .map(|id| {
+                let visible = self.layer_visibility(&id).unwrap_or(false);
+                (id, visible)
+            })
+            .collect();
+        for (id, _) in &layers {
+            let _ = self.set_layer_visibility(id, false);
+        }
+
+        let mut images = Vec::with_capacity(layers.len());
+        let mut error = None;
+        for (id, visible) in &layers {
+            let _ = self.set_layer_visibility(id, *visible);
+            // Keep restoring the visibility of the remaining layers after a failed render
+            if error.is_none() {
+                match self.render_static() {
+                    Ok(image) => images.push((id.clone(), image)),
+                    Err(e) => error = Some(e),
+                }
+            }
+        }
+        error.map_or(Ok(images), Err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::image_renderer::tests::decode;
+
+    #[test]
+    fn validate_style() {
+        ImageRendererOptions::validate_style(
+            r#"{
+                "version": 8,
+                "sources": {"points": {"type": "geojson", "data": {"type": "FeatureCollection", "features": []}}},
+                "layers": [
+                    {"id": "bg", "type": "background"},
+                    {"id": "points", "type": "circle", "source": "points"}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let errors = ImageRendererOptions::validate_style(
+            r#"{
+                "version": 8,
+                "sources": {"bad": {"type": "nope"}},
+                "layers": [
+                    {"id": "typo", "type": "cirlce", "source": "points"},
+                    {"id": "a", "type": "circle", "source": "points"},
+                    {"id": "b", "type": "line", "source": "points"},
+                    {"id": "c", "type": "fill", "source": "bad"}
+                ]
+            }"#,
+        )
+        .unwrap_err();
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(matches!(&errors[0], StyleError::Invalid(e) if e.starts_with("Source \"bad\"")));
+        assert!(matches!(&errors[1], StyleError::Invalid(e) if e.starts_with("Layer \"typo\"")));
+        assert_eq!(errors[2], StyleError::SourceNotFound("points".to_string()));
+
+        let errors = ImageRendererOptions::validate_style(r#"{"version": 8,"#).unwrap_err();
+        assert!(matches!(&errors[..], [StyleError::Invalid(e)] if e.starts_with("Invalid JSON")));
+        let errors =
+            ImageRendererOptions::validate_style(r#"{"version": 7, "layers": []}"#).unwrap_err();
+        assert_eq!(errors.len(), 1, "{errors:?}");
+    }
+
+    #[test]
+    fn line_dash() {
+        let style = r##"{
+            "version": 8,
+            "sources": {
+                "line": {
+                    "type": "geojson",
+                    "data": {
+                        "type": "LineString",
+                        "coordinates": [[-20.0, 0.0], [20.0, 0.0]]
+                    }
+                }
+            },
+            "layers": [
+                {"id": "bg", "type": "background", "paint": {"background-color": "#fff"}},
+                {
+                    "id": "line",
+                    "type": "line",
+                    "source": "line",
+                    "paint": {"line-color": "#000", "line-width": 4}
+                }
+            ]
+        }"##;
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(256, 256);
+        let mut map = opts.build_static_renderer();
+        map.set_style_json(style).unwrap();
+        map.set_camera(0.0, 0.0, 2.0, 0.0, 0.0).unwrap();
+
+        // Count white pixels along the middle of the line
+        let gaps = |map: &mut ImageRenderer<Static>| {
+            let pixels = decode(&map.render_static().unwrap());
+            pixels[128 * 256 + 32..128 * 256 + 224]
+                .iter()
+                .filter(|px| px[0] > 200)
+                .count()
+        };
+        assert_eq!(gaps(&mut map), 0);
+        map.set_line_dash("line", &[2.0, 2.0]).unwrap();
+        assert!(gaps(&mut map) > 0);
+        map.set_line_dash("line", &[]).unwrap();
+        assert_eq!(gaps(&mut map), 0);
+
+        assert!(matches!(
+            map.set_line_dash("line", &[1.0]),
+            Err(StyleError::InvalidDashArray(_))
+        ));
+        assert!(matches!(
+            map.set_line_dash("line", &[1.0, -1.0]),
+            Err(StyleError::InvalidDashArray(_))
+        ));
+        assert_eq!(
+            map.set_line_dash("missing", &[1.0, 1.0]),
+            Err(StyleError::LayerNotFound("missing".to_string()))
+        );
+        assert!(matches!(
+            map.set_line_dash("bg", &[1.0, 1.0]),
+            Err(StyleError::WrongLayerType { .. })
+        ));
+    }
+
+    #[test]
+    fn geojson_source() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(128, 128);
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_camera(0.0, 0.0, 2.0, 0.0, 0.0).unwrap();
+        let base = decode(&map.render_static().unwrap());
+
+        let point = r#"{"type": "Point", "coordinates": [0, 0]}"#;
+        map.add_geojson_source("overlay", point).unwrap();
+        map.add_layer(
+            r##"{
+                "id": "overlay",
+                "type": "circle",
+                "source": "overlay",
+                "paint": {"circle-radius": 10, "circle-color": "#f0f"}
+            }"##,
+            None,
+        )
+        .unwrap();
+        let overlay = decode(&map.render_static().unwrap());
+        assert_ne!(base, overlay);
+        assert_eq!(overlay[64 * 128 + 64], [255, 0, 255, 255]);
+
+        // Moving the point away from the center reveals the base map again
+        let moved = r#"{"type": "Point", "coordinates": [60, 40]}"#;
+        map.update_geojson_source("overlay", moved).unwrap();
+        let updated = decode(&map.render_static().unwrap());
+        assert_eq!(updated[64 * 128 + 64], base[64 * 128 + 64]);
+        assert!(map.style_json().contains("[60,40]"));
+
+        assert!(matches!(
+            map.add_geojson_source("overlay", point),
+            Err(StyleError::Invalid(_))
+        ));
+        assert!(matches!(
+            map.add_geojson_source("other", r#"{"type": "Pointless"}"#),
+            Err(StyleError::Invalid(_))
+        ));
+        assert!(matches!(
+            map.update_geojson_source("overlay", "not json"),
+            Err(StyleError::Invalid(_))
+        ));
+        assert_eq!(
+            map.update_geojson_source("missing", point),
+            Err(StyleError::SourceNotFound("missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn style_json_round_trip() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64);
+        let mut map = opts.clone().build_static_renderer();
+        map.set_style_json(
+            r##"{
+                "version": 8,
+                "sources": {},
+                "layers": [
+                    {"id": "bg", "type": "background", "paint": {"background-color": "#ffffff"}},
+                    {"id": "hidden", "type": "background", "paint": {"background-color": "#000000"}}
+                ]
+            }"##,
+        )
+        .unwrap();
+        map.set_camera(0.0, 0.0, 0.0, 0.0, 0.0).unwrap();
+        map.set_layer_visibility("hidden", false).unwrap();
+        map.add_geojson_source("point", r#"{"type": "Point", "coordinates": [0, 0]}"#)
+            .unwrap();
+        map.add_layer(
+            r##"{
+                "id": "circle",
+                "type": "circle",
+                "source": "point",
+                "paint": {"circle-radius": 10, "circle-color": "#ff00ff"}
+            }"##,
+            None,
+        )
+        .unwrap();
+        let image = decode(&map.render_static().unwrap());
+        assert_eq!(image[32 * 64 + 32], [255, 0, 255, 255]);
+
+        let style = map.style_json();
+        let mut reloaded = opts.build_static_renderer();
+        reloaded.set_style_json(&style).unwrap();
+        reloaded.set_camera(0.0, 0.0, 0.0, 0.0, 0.0).unwrap();
+        assert_eq!(decode(&reloaded.render_static().unwrap()), image);
+        assert_eq!(reloaded.layer_ids(), ["bg", "hidden", "circle"]);
+        assert_eq!(reloaded.style_json(), style);
+    }
+
+    #[test]
+    fn feature_state() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64);
+        let mut map = opts.build_static_renderer();
+        map.set_style_json(
+            r##"{
+                "version": 8,
+                "sources": {
+                    "countries": {
+                        "type": "geojson",
+                        "data": {
+                            "type": "Feature",
+                            "id": 3,
+                            "properties": {},
+                            "geometry": {
+                                "type": "Polygon",
+                                "coordinates": [[[-20, -20], [20, -20], [20, 20], [-20, 20], [-20, -20]]]
+                            }
+                        }
+                    }
+                },
+                "layers": [{
+                    "id": "countries",
+                    "type": "fill",
+                    "source": "countries",
+                    "paint": {
+                        "fill-color": [
+                            "case", ["boolean", ["feature-state", "highlight"], false], "#f00", "#00f"
+                        ]
+                    }
+                }]
+            }"##,
+        )
+        .unwrap();
+        map.set_camera(0.0, 0.0, 2.0, 0.0, 0.0).unwrap();
+        let plain = decode(&map.render_static().unwrap());
+        assert_eq!(plain[32 * 64 + 32], [0, 0, 255, 255]);
+
+        map.set_feature_state("countries", None, "3", r#"{"highlight": true}"#)
+            .unwrap();
+        let highlighted = decode(&map.render_static().unwrap());
+        assert_eq!(highlighted[32 * 64 + 32], [255, 0, 0, 255]);
+
+        assert_eq!(
+            map.set_feature_state("missing", None, "3", "{}"),
+            Err(StyleError::SourceNotFound("missing".to_string()))
+        );
+        assert!(matches!(
+            map.set_feature_state("countries", None, "3", "[true]"),
+            Err(StyleError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn add_and_remove_layers() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64);
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.render_static().unwrap();
+        let original = map.layer_ids();
+        let below = original.last().unwrap().clone();
+
+        let highlight = r#"{"id": "highlight", "type": "background"}"#;
+        map.add_layer(highlight, Some(&below)).unwrap();
+        let layers = map.layer_ids();
+        assert_eq!(layers[layers.len() - 2], "highlight");
+        assert_eq!(layers[layers.len() - 1], below);
+
+        assert!(matches!(
+            map.add_layer(highlight, None),
+            Err(StyleError::Invalid(_))
+        ));
+        let other = r#"{"id": "other", "type": "background"}"#;
+        assert_eq!(
+            map.add_layer(other, Some("missing")),
+            Err(StyleError::LayerNotFound("missing".to_string()))
+        );
+
+        map.remove_layer("highlight").unwrap();
+        assert_eq!(map.layer_ids(), original);
+        assert_eq!(
+            map.remove_layer("highlight"),
+            Err(StyleError::LayerNotFound("highlight".to_string()))
+        );
+    }
+
+    #[test]
+    fn add_image() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64);
+        let mut map = opts.build_static_renderer();
+        map.set_style_json(
+            r##"{
+                "version": 8,
+                "sources": {
+                    "point": {"type": "geojson", "data": {"type": "Point", "coordinates": [0, 0]}}
+                },
+                "layers": [
+                    {"id": "bg", "type": "background", "paint": {"background-color": "#ffffff"}},
+                    {
+                        "id": "marker",
+                        "type": "symbol",
+                        "source": "point",
+                        "layout": {"icon-image": "marker", "icon-allow-overlap": true},
+                        "paint": {"icon-color": "#0000ff"}
+                    }
+                ]
+            }"##,
+        )
+        .unwrap();
+        map.set_camera(0.0, 0.0, 0.0, 0.0, 0.0).unwrap();
+        let red = [255, 0, 0, 255].repeat(16 * 16);
+        map.add_image("marker", &red, 16, 16, 1.0, false).unwrap();
+        let pixels = decode(&map.render_static().unwrap());
+        assert_eq!(pixels[32 * 64 + 32], [255, 0, 0, 255]);
+        assert_eq!(pixels[0], [255, 255, 255, 255]);
+
+        // A signed distance field icon takes the color of the layer
+        map.add_image("marker", &red, 16, 16, 1.0, true).unwrap();
+        let pixels = decode(&map.render_static().unwrap());
+        assert_eq!(pixels[32 * 64 + 32], [0, 0, 255, 255]);
+
+        assert_eq!(
+            map.add_image("marker", &red, 16, 15, 1.0, false),
+            Err(StyleError::InvalidImageSize {
+                width: 16,
+                height: 15,
+                len: 1024
+            })
+        );
+    }
+
+    #[test]
+    fn visible_layers() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(8, 8);
+        let mut map = opts.build_static_renderer();
+        map.set_style_json(
+            r##"{"version": 8, "sources": {}, "layers": [
+                {"id": "red", "type": "background", "paint": {"background-color": "#ff0000"}},
+                {"id": "blue", "type": "background", "paint": {"background-color": "#0000ff"}}
+            ]}"##,
+        )
+        .unwrap();
+        assert_eq!(decode(&map.render_static().unwrap())[0], [0, 0, 255, 255]);
+
+        map.set_visible_layers(&["red"]).unwrap();
+        assert_eq!(map.layer_visibility("blue"), Ok(false));
+        assert_eq!(decode(&map.render_static().unwrap())[0], [255, 0, 0, 255]);
+
+        assert_eq!(
+            map.set_visible_layers(&["blue", "missing"]),
+            Err(StyleError::LayerNotFound("missing".to_string()))
+        );
+        assert_eq!(map.layer_visibility("red"), Ok(true));
+
+        map.set_visible_layers(&[]).unwrap();
+        let pixels = decode(&map.render_static().unwrap());
+        assert!(pixels.iter().all(|pixel| pixel[3] == 0));
+    }
+
+    #[test]
+    fn onion_skin() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64);
+        let mut map = opts.build_static_renderer();
+        map.set_style_url("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.render_static().unwrap();
+
+        let layers = map.layer_ids();
+        assert!(!layers.is_empty());
+        let visibility: Vec<_> = layers
+            .iter()
+            .map(|id| map.layer_visibility(id).unwrap())
+            .collect();
+
+        let images = map.render_onion_skin().unwrap();
+        assert_eq!(images.len(), layers.len());
+        for ((id, image), layer) in images.iter().zip(&layers) {
+            assert_eq!(id, layer);
+            assert!(!image.as_slice().is_empty());
+        }
+        for (id, visible) in layers.iter().zip(visibility) {
+            assert_eq!(map.layer_visibility(id), Ok(visible));
+        }
+        assert_eq!(
+            map.set_layer_visibility("missing", true),
+            Err(StyleError::LayerNotFound("missing".to_string()))
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::renderer::image_renderer::check_style_url;
+use crate::renderer::{ImageRenderer, StyleError, StyleUrlError};
+
+/// Where to load a style from, see [`ImageRenderer::set_style`].
+///
+/// Strings are converted into [`Json`](Self::Json) if they start with `{`,
+/// and into [`Url`](Self::Url) otherwise. Paths are always converted into [`Path`](Self::Path).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StyleSource {
+    /// See [`ImageRenderer::set_style_url`]
+    Url(String),
+    /// See [`ImageRenderer::set_style_path`]
+    Path(PathBuf),
+    /// See [`ImageRenderer::set_style_json`]
+    Json(String),
+}
+
+impl From<String> for StyleSource {
+    fn from(value: String) -> Self {
+        if value.trim_start().starts_with('{') {
+            Self::Json(value)
+        } else {
+            Self::Url(value)
+        }
+    }
+}
+
+impl From<&str> for StyleSource {
+    fn from(value: &str) -> Self {
+        value.to_string().into()
+    }
+}
+
+impl From<PathBuf> for StyleSource {
+    fn from(value: PathBuf) -> Self {
+        Self::Path(value)
+    }
+}
+
+impl From<&Path> for StyleSource {
+    fn from(value: &Path) -> Self {
+        Self::Path(value.to_path_buf())
+    }
+}
+
+/// Errors returned by [`ImageRenderer::set_style`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetStyleError {
+    /// The URL scheme is not supported.
+    Url(StyleUrlError),
+    /// The style file does not exist, or its path is not valid UTF-8.
+    File(PathBuf),
+    /// The JSON document is not a valid style.
+    Json(StyleError),
+}
+
+impl fmt::Display for SetStyleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Url(e) => fmt::Display::fmt(e, f),
+            Self::File(path) => write!(f, "Style file {} was not found", path.display()),
+            Self::Json(e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+impl std::error::Error for SetStyleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Url(e) => Some(e),
+            Self::File(_) => None,
+            Self::Json(e) => Some(e),
+        }
+    }
+}
+
+impl From<StyleUrlError> for SetStyleError {
+    fn from(value: StyleUrlError) -> Self {
+        Self::Url(value)
+    }
+}
+
+impl From<StyleError> for SetStyleError {
+    fn from(value: StyleError) -> Self {
+        Self::Json(value)
+    }
+}
+
+impl StyleSource {
+    /// Check the source before it is passed to MapLibre, given the configured URI scheme alias.
+    ///
+    /// JSON documents are only checked to be objects here, they are fully parsed by MapLibre.
+    fn validate(&self, scheme_alias: &str) -> Result<(), SetStyleError> {
+        match self {
+            Self::Url(url) => check_style_url(url, scheme_alias)?,
+            Self::Path(path) => {
+                if !path.is_file() || path.to_str().is_none() {
+                    return Err(SetStyleError::File(path.clone()));
+                }
+            }
+            Self::Json(json) => {
+                if !json.trim_start().starts_with('{') {
+                    return Err(
+                        StyleError::Invalid("Style JSON is not an object".to_string()).into(),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S> ImageRenderer<S> {
+    /// Load a style from a URL, a file, or a JSON document.
+    ///
+    /// # Errors
+    /// Returns an error if the URL scheme is not supported, if the file does not exist,
+    /// or if the JSON document is not a valid style.
+    pub fn set_style(&mut self, style: impl Into<StyleSource>) -> Result<&mut Self, SetStyleError> {
+        let style = style.into();
+        // URLs are checked by set_style_url, which also accepts schemes served by a resolver
+        if !matches!(style, StyleSource::Url(_)) {
+            style.validate(&self.2)?;
+        }
+        match style {
+            StyleSource::Url(url) => self.set_style_url(&url)?,
+            StyleSource::Path(path) => self
+                .set_style_path(&path)
+                .map_err(|_| SetStyleError::File(path.clone()))?,
+            StyleSource::Json(json) => self.set_style_json(&json)?,
+        };
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::ImageRendererOptions;
+
+    #[test]
+    fn conversions() {
+        assert_eq!(
+            StyleSource::from("https://example.com/style.json"),
+            StyleSource::Url("https://example.com/style.json".to_string())
+        );
+        assert_eq!(
+            StyleSource::from(r#"  {"version": 8}"#.to_string()),
+            StyleSource::Json(r#"  {"version": 8}"#.to_string())
+        );
+        assert_eq!(
+            StyleSource::from(Path::new("style.json")),
+            StyleSource::Path(PathBuf::from("style.json"))
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let url = StyleSource::from("maplibre://maps/basic");
+        assert!(url.validate("maplibre").is_ok());
+        assert!(matches!(url.validate(""), Err(SetStyleError::Url(_))));
+        assert!(matches!(
+            StyleSource::from("style.json").validate("maplibre"),
+            Err(SetStyleError::Url(_))
+        ));
+
+        let missing = std::env::temp_dir().join("mln_no_such_style.json");
+        assert_eq!(
+            StyleSource::from(missing.clone()).validate(""),
+            Err(SetStyleError::File(missing))
+        );
+        let existing = std::env::temp_dir().join("mln_style_source.json");
+        std::fs::write(&existing, "{}").unwrap();
+        assert!(StyleSource::from(existing).validate("").is_ok());
+
+        assert!(StyleSource::from(r#"{"version": 8}"#).validate("").is_ok());
+        assert!(matches!(
+            StyleSource::Json("[]".to_string()).validate(""),
+            Err(SetStyleError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn set_style() {
+        let mut map = ImageRendererOptions::new().build_static_renderer();
+        map.set_style("https://demotiles.maplibre.org/style.json")
+            .unwrap();
+        map.set_style(r#"{"version": 8, "sources": {}, "layers": []}"#)
+            .unwrap();
+        assert!(matches!(
+            map.set_style(r#"{"version": 8,"#),
+            Err(SetStyleError::Json(_))
+        ));
+        assert!(matches!(
+            map.set_style(PathBuf::from("no-such-style.json")),
+            Err(SetStyleError::File(_))
+        ));
+    }
+}
@@ -0,0 +1,402 @@
+//! A pool of renderers serving requests from a bounded queue.
+//!
+//! MapLibre renderers are confined to the thread that created them, as each one owns a run loop
+//! bound to that thread. [`RenderService`] therefore spawns one worker thread per renderer,
+//! and each worker builds its own [`ImageRenderer`] from the shared [`ImageRendererOptions`].
+//! Requests are passed to the workers through a single bounded queue, and results are sent back
+//! through a future that does not depend on any particular async runtime.
+//! Since renders run on the worker threads, awaiting the result never blocks the executor.
+//! Unlike the renderers, the service and its futures are `Send`, so requests can be submitted
+//! from any task, including tasks spawned on a multi-threaded runtime such as tokio.
+//!
+//! For synchronous code, e.g. a rayon thread pool, [`RenderWorker`] offers a blocking handle
+//! to a single renderer thread instead. In both cases, the native map objects are created,
+//! used, and destroyed on their worker thread, and never cross a thread boundary.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+
+use crate::renderer::check_center;
+use crate::{CancellationToken, Image, ImageRenderer, ImageRendererOptions, RenderError, Static};
+
+/// A single static render submitted to a [`RenderService`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderRequest {
+    /// Style URL, e.g. `https://demotiles.maplibre.org/style.json`
+    pub style_url: String,
+    pub lon: f64,
+    pub lat: f64,
+    pub zoom: f64,
+    pub bearing: f64,
+    pub pitch: f64,
+}
+
+/// A fixed pool of static renderers fed by a bounded queue.
+///
+/// When the queue is full, [`submit`](Self::submit) rejects new requests with
+/// [`RenderError::QueueFull`] instead of waiting, so that callers such as a web service
+/// can respond immediately, e.g. with `503 Service Unavailable`.
+/// Dropping the service finishes all queued requests, and waits for the workers to exit.
+pub struct RenderService {
+    sender: Option<SyncSender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+struct Job {
+    request: RenderRequest,
+    result: Arc<Mutex<Slot>>,
+    token: CancellationToken,
+}
+
+/// Shared state between a worker and a [`RenderFuture`].
+#[derive(Default)]
+struct Slot {
+    result: Option<Result<Image, RenderError>>,
+    waker: Option<Waker>,
+}
+
+impl Slot {
+    fn complete(slot: &Mutex<Slot>, result: Result<Image, RenderError>) {
+        let mut slot = slot.lock().unwrap();
+        slot.result.get_or_insert(result);
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl RenderService {
+    /// Start `workers` renderers, each built from `options`, accepting up to `queue_size`
+    /// requests waiting for a free renderer.
+    #[must_use]
+    pub fn new(options: &ImageRendererOptions, workers: usize, queue_size: usize) -> Self {
+        assert!(workers > 0, "Render service needs at least one worker");
+        let (sender, receiver) = sync_channel::<Job>(queue_size);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..workers)
+            .map(|idx| {
+                let options = options.clone();
+                let receiver = Arc::clone(&receiver);
+                thread::Builder::new()
+                    .name(format!("mln-render-{idx}"))
+                    .spawn(move || run_worker(&options, &receiver))
+                    .expect("Unable to spawn render worker thread")
+            })
+            .collect();
+        Self {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    /// Queue a render, returning a future that resolves once a worker has rendered it.
+    ///
+    /// The request is queued immediately, even if the future is never polled.
+    /// If the queue is full, the future resolves to [`RenderError::QueueFull`].
+    /// Dropping the future cancels the request, e.g. when the client waiting for it disconnects:
+    /// it is skipped if it is still queued, and its render is stopped if it is in progress.
+    pub fn submit(
+        &self,
+        request: RenderRequest,
+    ) -> impl Future<Output = Result<Image, RenderError>> + Send {
+        let slot = Arc::new(Mutex::new(Slot::default()));
+        let token = CancellationToken::new();
+        let job = Job {
+            request,
+            result: Arc::clone(&slot),
+            token: token.clone(),
+        };
+        let sender = self
+            .sender
+            .as_ref()
+            .expect("Sender is only removed on drop");
+        match sender.try_send(job) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => Slot::complete(&slot, Err(RenderError::QueueFull)),
+            Err(TrySendError::Disconnected(_)) => {
+                Slot::complete(&slot, Err(RenderError::WorkerStopped));
+            }
+        }
+        RenderFuture(slot, token)
+    }
+}
+
+impl Drop for RenderService {
+    fn drop(&mut self) {
+        // Closing the channel lets the workers exit once the queue is drained
+        self.sender.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// A `Send + Sync` handle to a static renderer running on its own thread.
+///
+/// [`render`](Self::render) can be called from any thread, and blocks until the worker
+/// has rendered the request. Concurrent requests wait for their turn, as there is only
+/// one renderer. Dropping the handle finishes the pending requests, and waits for the thread to exit.
+pub struct RenderWorker {
+    sender: Option<Sender<WorkerJob>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+type WorkerJob = (RenderRequest, SyncSender<Result<Image, RenderError>>);
+
+impl RenderWorker {
+    /// Start a worker thread with a renderer built from `options`.
+    #[must_use]
+    pub fn new(options: &ImageRendererOptions) -> Self {
+        let options = options.clone();
+        let (sender, receiver) = channel::<WorkerJob>();
+        let thread = thread::Builder::new()
+            .name("mln-render-worker".to_string())
+            .spawn(move || {
+                let mut renderer = RequestRenderer::new(&options);
+                let token = CancellationToken::new();
+                for (request, reply) in receiver {
+                    // The caller may have given up waiting, so the reply can be ignored
+                    let _ = reply.send(renderer.render(&request, &token));
+                }
+            })
+            .expect("Unable to spawn render worker thread");
+        Self {
+            sender: Some(sender),
+            thread: Some(thread),
+        }
+    }
+
+    /// Render a request on the worker thread, blocking until it is done.
+    ///
+    /// # Errors
+    /// Returns the render error, or [`RenderError::WorkerStopped`] if the worker thread
+    /// stopped, e.g. because a previous render panicked.
+    pub fn render(&self, request: RenderRequest) -> Result<Image, RenderError> {
+        let (reply, result) = sync_channel(1);
+        let sender = self
+            .sender
+            .as_ref()
+            .expect("Sender is only removed on drop");
+        sender
+            .send((request, reply))
+            .map_err(|_| RenderError::WorkerStopped)?;
+        // The reply sender is dropped without a result if the worker panics
+        result.recv().unwrap_or(Err(RenderError::WorkerStopped))
+    }
+}
+
+impl Drop for RenderWorker {
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run_worker(options: &ImageRendererOptions, receiver: &Mutex<Receiver<Job>>) {
+    let mut renderer = RequestRenderer::new(options);
+    loop {
+        // Only hold the lock while waiting for the next job, not while rendering it
+        let Ok(job) = receiver.lock().unwrap().recv() else {
+            return;
+        };
+        // Dropping the job without a result, e.g. during a panic, must still wake the caller
+        let guard = JobGuard(&job.result);
+        Slot::complete(guard.0, renderer.render(&job.request, &job.token));
+    }
+}
+
+/// A static renderer that only reloads the style when it differs from the previous request.
+struct RequestRenderer {
+    renderer: ImageRenderer<Static>,
+    style_url: Option<String>,
+}
+
+impl RequestRenderer {
+    fn new(options: &ImageRendererOptions) -> Self {
+        Self {
+            renderer: options.clone().build_static_renderer(),
+            style_url: None,
+        }
+    }
+
+    fn render(
+        &mut self,
+        request: &RenderRequest,
+        token: &CancellationToken,
+    ) -> Result<Image, RenderError> {
+        if self.style_url.as_ref() != Some(&request.style_url) {
+            self.renderer
+                .set_style_url(&request.style_url)
+                .map_err(|e| RenderError::StyleLoad(e.to_string()))?;
+            self.style_url = Some(request.style_url.clone());
+        }
+        check_center(request.lon, request.lat).map_err(RenderError::InvalidValue)?;
+        self.renderer.set_camera_unchecked(
+            request.lon,
+            request.lat,
+            request.zoom,
+            request.bearing,
+            request.pitch,
+        );
+        self.renderer.render_static_cancellable(token)
+    }
+}
+
+struct JobGuard<'a>(&'a Mutex<Slot>);
+
+impl Drop for JobGuard<'_> {
+    fn drop(&mut self) {
+        // Ignore a poisoned lock, the result is left unset and the caller gets an error
+        if let Ok(mut slot) = self.0.lock() {
+            slot.result.get_or_insert(Err(RenderError::WorkerStopped));
+            if let Some(waker) = slot.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Future returned by [`RenderService::submit`], cancelling its request when dropped.
+struct RenderFuture(Arc<Mutex<Slot>>, CancellationToken);
+
+impl Drop for RenderFuture {
+    fn drop(&mut self) {
+        // Has no effect once the render is complete
+        self.1.cancel();
+    }
+}
+
+impl Future for RenderFuture {
+    type Output = Result<Image, RenderError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.0.lock().unwrap();
+        if let Some(result) = slot.result.take() {
+            Poll::Ready(result)
+        } else {
+            slot.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::Wake;
+    use std::thread::Thread;
+
+    use super::*;
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Minimal executor, to avoid depending on an async runtime
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(result) = future.as_mut().poll(&mut cx) {
+                return result;
+            }
+            thread::park();
+        }
+    }
+
+    fn request(zoom: f64) -> RenderRequest {
+        RenderRequest {
+            style_url: "https://demotiles.maplibre.org/style.json".to_string(),
+            lon: 0.0,
+            lat: 0.0,
+            zoom,
+            bearing: 0.0,
+            pitch: 0.0,
+        }
+    }
+
+    #[test]
+    fn more_requests_than_workers() {
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64);
+        let service = RenderService::new(&opts, 2, 8);
+        let futures: Vec<_> = (0..6)
+            .map(|zoom| service.submit(request(f64::from(zoom))))
+            .collect();
+        for future in futures {
+            let image = block_on(future).unwrap();
+            assert!(!image.as_slice().is_empty());
+        }
+    }
+
+    #[test]
+    fn concurrent_tasks() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let service = RenderService::new(&ImageRendererOptions::new(), 2, 2);
+        assert_send_sync(&service);
+        let world = request(0.0);
+        let europe = RenderRequest {
+            lon: 10.0,
+            lat: 50.0,
+            zoom: 4.0,
+            ..request(0.0)
+        };
+        // Each task waits for its own render, while the other one is rendered concurrently
+        let (world, europe) = thread::scope(|s| {
+            let world = s.spawn(|| block_on(service.submit(world)));
+            let europe = s.spawn(|| block_on(service.submit(europe)));
+            (world.join().unwrap(), europe.join().unwrap())
+        });
+        assert_ne!(world.unwrap().as_slice(), europe.unwrap().as_slice());
+    }
+
+    #[test]
+    fn render_worker() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let mut opts = ImageRendererOptions::new();
+        opts.with_size(64, 64);
+        let worker = RenderWorker::new(&opts);
+        assert_send_sync(&worker);
+        let images: Vec<_> = thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|zoom| {
+                    let worker = &worker;
+                    s.spawn(move || worker.render(request(f64::from(zoom))))
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        for image in images {
+            assert!(!image.unwrap().as_slice().is_empty());
+        }
+
+        let bad = RenderRequest {
+            style_url: "style.json".to_string(),
+            ..request(0.0)
+        };
+        assert!(matches!(worker.render(bad), Err(RenderError::StyleLoad(_))));
+    }
+
+    #[test]
+    fn queue_full() {
+        let service = RenderService::new(&ImageRendererOptions::new(), 1, 1);
+        let futures: Vec<_> = (0..10).map(|_| service.submit(request(0.0))).collect();
+        let results: Vec<_> = futures.into_iter().map(block_on).collect();
+        assert!(results.iter().any(Result::is_ok));
+        assert!(results
+            .iter()
+            .any(|r| matches!(r, Err(RenderError::QueueFull))));
+    }
+}
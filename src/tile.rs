@@ -0,0 +1,290 @@
+//! Conversions between slippy map tile coordinates and geographic positions,
+//! e.g. to find the tiles to pass to [`ImageRenderer::render_tile`](crate::ImageRenderer::render_tile).
+//!
+//! Tiles use the Web Mercator projection, so they cover latitudes up to about ±85.05°.
+
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::renderer::{tile_center, to_tile_coords};
+use crate::{LatLng, LatLngBounds, TileCoord};
+
+/// Latitude of the northern edge of the tile grid, the southern edge is its negation.
+const MAX_LATITUDE: f64 = 85.051_128_779_806_59;
+
+/// A tile that exists at its zoom level, written as `z/x/y` like in tile URLs.
+///
+/// Unlike passing the three numbers separately, e.g. to
+/// [`ImageRenderer::render_tile`](crate::ImageRenderer::render_tile),
+/// the coordinates cannot be mixed up, and are checked once when the identifier is created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TileId {
+    z: u8,
+    x: u32,
+    y: u32,
+}
+
+/// Errors returned when creating or parsing a [`TileId`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TileIdError {
+    /// The value is not three integers separated by slashes.
+    Format(String),
+    /// `x` or `y` is not below `2^z`.
+    OutOfRange { z: u8, x: u32, y: u32 },
+}
+
+impl fmt::Display for TileIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Format(value) => write!(f, "Tile {value:?} is not in the z/x/y format"),
+            Self::OutOfRange { z, x, y } => write!(f, "Tile {z}/{x}/{y} does not exist"),
+        }
+    }
+}
+
+impl std::error::Error for TileIdError {}
+
+impl TileId {
+    /// # Errors
+    /// Returns an error if `x` or `y` is not a tile of zoom `z`.
+    pub fn new(z: u8, x: u32, y: u32) -> Result<Self, TileIdError> {
+        let tiles = 1_u64.checked_shl(u32::from(z)).unwrap_or(u64::MAX);
+        if u64::from(x) < tiles && u64::from(y) < tiles {
+            Ok(Self { z, x, y })
+        } else {
+            Err(TileIdError::OutOfRange { z, x, y })
+        }
+    }
+
+    /// Parse a tile written as `z/x/y`, e.g. `10/550/335`.
+    ///
+    /// # Errors
+    /// Returns an error if the value is not in this format, or if the tile does not exist.
+    pub fn parse(value: &str) -> Result<Self, TileIdError> {
+        let format_error = || TileIdError::Format(value.to_string());
+        let mut parts = value.split('/');
+        let (Some(z), Some(x), Some(y), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(format_error());
+        };
+        Self::new(
+            z.parse().map_err(|_| format_error())?,
+            x.parse().map_err(|_| format_error())?,
+            y.parse().map_err(|_| format_error())?,
+        )
+    }
+
+    #[must_use]
+    pub fn z(self) -> u8 {
+        self.z
+    }
+
+    #[must_use]
+    pub fn x(self) -> u32 {
+        self.x
+    }
+
+    #[must_use]
+    pub fn y(self) -> u32 {
+        self.y
+    }
+
+    /// Center of the tile, see [`center`].
+    #[must_use]
+    pub fn center(self) -> LatLng {
+        center(self.z, self.x, self.y)
+    }
+
+    /// Geographic extent of the tile, see [`bounds`].
+    #[must_use]
+    pub fn bounds(self) -> LatLngBounds {
+        bounds(self.z, self.x, self.y)
+    }
+}
+
+impl fmt::Display for TileId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}/{}", self.z, self.x, self.y)
+    }
+}
+
+impl FromStr for TileId {
+    type Err = TileIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl From<TileId> for TileCoord {
+    fn from(value: TileId) -> Self {
+        Self {
+            z: value.z,
+            x: value.x,
+            y: value.y,
+        }
+    }
+}
+
+impl TryFrom<TileCoord> for TileId {
+    type Error = TileIdError;
+
+    fn try_from(value: TileCoord) -> Result<Self, Self::Error> {
+        Self::new(value.z, value.x, value.y)
+    }
+}
+
+/// Center of tile `zoom`/`x`/`y`.
+///
+/// # Panics
+/// Panics if `x` or `y` is not a tile of `zoom`.
+#[must_use]
+pub fn center(zoom: u8, x: u32, y: u32) -> LatLng {
+    check_tile(zoom, x, y);
+    let (lon, lat) = tile_center(f64::from(zoom), x, y);
+    LatLng::new(lon, lat).expect("Tile centers are valid positions")
+}
+
+/// Geographic extent of tile `zoom`/`x`/`y`.
+///
+/// # Panics
+/// Panics if `x` or `y` is not a tile of `zoom`.
+#[must_use]
+pub fn bounds(zoom: u8, x: u32, y: u32) -> LatLngBounds {
+    let tiles = check_tile(zoom, x, y);
+    let lon = |x: f64| x / tiles * 360.0 - 180.0;
+    let lat = |y: f64| (PI * (1.0 - 2.0 * y / tiles)).sinh().atan().to_degrees();
+    LatLngBounds {
+        west: lon(f64::from(x)),
+        south: lat(f64::from(y) + 1.0),
+        east: lon(f64::from(x) + 1.0),
+        north: lat(f64::from(y)),
+    }
+}
+
+/// Tile of `zoom` containing `position`, as `(x, y)`.
+///
+/// Positions beyond the latitude range of Web Mercator are mapped to the northern-
+/// or southernmost row of tiles.
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn from_lat_lon(position: LatLng, zoom: u8) -> (u32, u32) {
+    let max = 2_f64.powi(i32::from(zoom)) - 1.0;
+    let lat = position.lat().clamp(-MAX_LATITUDE, MAX_LATITUDE);
+    let (x, y) = to_tile_coords(position.lon(), lat, zoom);
+    (
+        x.floor().clamp(0.0, max) as u32,
+        y.floor().clamp(0.0, max) as u32,
+    )
+}
+
+/// Number of tiles along each axis at `zoom`, asserting that `x` and `y` are within them.
+fn check_tile(zoom: u8, x: u32, y: u32) -> f64 {
+    if let Err(e) = TileId::new(zoom, x, y) {
+        panic!("{e}");
+    }
+    2_f64.powi(i32::from(zoom))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_world() {
+        let world = bounds(0, 0, 0);
+        assert!((world.west + 180.0).abs() < 1e-9, "{world:?}");
+        assert!((world.east - 180.0).abs() < 1e-9, "{world:?}");
+        assert!((world.north - MAX_LATITUDE).abs() < 1e-9, "{world:?}");
+        assert!((world.south + MAX_LATITUDE).abs() < 1e-9, "{world:?}");
+
+        let center = center(0, 0, 0);
+        assert!(center.lon().abs() < 1e-9 && center.lat().abs() < 1e-9);
+        assert_eq!(from_lat_lon(center, 0), (0, 0));
+    }
+
+    #[test]
+    fn known_tiles() {
+        // Berlin
+        let berlin = LatLng::new(13.4, 52.52).unwrap();
+        assert_eq!(from_lat_lon(berlin, 10), (550, 335));
+        let tile = bounds(10, 550, 335);
+        assert!(tile.west <= 13.4 && 13.4 < tile.east, "{tile:?}");
+        assert!(tile.south <= 52.52 && 52.52 < tile.north, "{tile:?}");
+
+        // Tile 1/1/0 is the north-eastern quadrant
+        let quadrant = bounds(1, 1, 0);
+        assert!(quadrant.west.abs() < 1e-9 && quadrant.south.abs() < 1e-9);
+        let LatLngBounds { east, north, .. } = quadrant;
+        assert!((east - 180.0).abs() < 1e-9 && (north - MAX_LATITUDE).abs() < 1e-9);
+        assert_eq!(from_lat_lon(LatLng::new(0.0, 0.0).unwrap(), 1), (1, 1));
+    }
+
+    #[test]
+    fn round_trip() {
+        for (z, x, y) in [(0, 0, 0), (3, 7, 0), (12, 2200, 1343), (20, 0, 1_048_575)] {
+            assert_eq!(from_lat_lon(center(z, x, y), z), (x, y), "{z}/{x}/{y}");
+            let tile = bounds(z, x, y);
+            let middle = center(z, x, y);
+            assert!(tile.west < middle.lon() && middle.lon() < tile.east);
+            assert!(tile.south < middle.lat() && middle.lat() < tile.north);
+        }
+    }
+
+    #[test]
+    fn clamped() {
+        let north_pole = LatLng::new(0.0, 90.0).unwrap();
+        assert_eq!(from_lat_lon(north_pole, 2), (2, 0));
+        let south_pole = LatLng::new(-180.0, -90.0).unwrap();
+        assert_eq!(from_lat_lon(south_pole, 2), (0, 3));
+    }
+
+    #[test]
+    fn tile_id() {
+        let tile = TileId::new(10, 550, 335).unwrap();
+        assert_eq!((tile.z(), tile.x(), tile.y()), (10, 550, 335));
+        assert_eq!(tile.to_string(), "10/550/335");
+        assert_eq!(TileId::parse("10/550/335"), Ok(tile));
+        assert_eq!("10/550/335".parse::<TileId>(), Ok(tile));
+        assert_eq!(TileId::try_from(TileCoord::from(tile)), Ok(tile));
+        assert_eq!(tile.center(), center(10, 550, 335));
+
+        assert!(TileId::new(0, 0, 0).is_ok());
+        assert!(TileId::new(32, u32::MAX, u32::MAX).is_ok());
+        assert_eq!(
+            TileId::new(1, 2, 0),
+            Err(TileIdError::OutOfRange { z: 1, x: 2, y: 0 })
+        );
+        assert_eq!(
+            TileId::new(3, 0, 8).unwrap_err().to_string(),
+            "Tile 3/0/8 does not exist"
+        );
+        assert!(TileId::new(31, 1 << 31, 0).is_err());
+        assert!(matches!(
+            TileId::parse("2/4/0"),
+            Err(TileIdError::OutOfRange { .. })
+        ));
+        for invalid in [
+            "",
+            "1/2",
+            "1/0/0/0",
+            "a/0/0",
+            "1/-1/0",
+            "256/0/0",
+            "1/0/0.png",
+        ] {
+            assert_eq!(
+                TileId::parse(invalid),
+                Err(TileIdError::Format(invalid.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Tile 1/2/0 does not exist")]
+    fn invalid_tile() {
+        let _ = bounds(1, 2, 0);
+    }
+}
@@ -0,0 +1,186 @@
+//! Rendering of map tiles for an HTTP server.
+//!
+//! Serving tiles takes the same plumbing in every server: a few renderers on their own threads,
+//! each with the style loaded once, and a queue of tile requests shared between them.
+//! [`TileServer`] provides this plumbing behind a blocking, `Send + Sync` API,
+//! so it can be shared by all handlers of any HTTP framework, e.g. with `spawn_blocking` in tokio.
+//! See the `tileserver` example for a complete server.
+
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::tile::TileId;
+use crate::{Error, ImageFormat, ImageRendererOptions, RenderError, StyleSource};
+
+type TileJob = (TileId, ImageFormat, SyncSender<Result<Vec<u8>, Error>>);
+
+/// A fixed set of tile renderers for one style, each running on its own thread.
+///
+/// Requests are queued until a renderer is free, and [`tile`](Self::tile) blocks until then.
+/// Dropping the server finishes the queued requests, and waits for the threads to exit.
+pub struct TileServer {
+    sender: Option<Sender<TileJob>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl TileServer {
+    /// Start `workers` tile renderers built from `options`, each with `style` loaded.
+    ///
+    /// Tiles have the [`TileSize`](crate::TileSize) of the options, scaled by their pixel ratio.
+    ///
+    /// # Errors
+    /// Returns an error if a renderer could not be built, e.g. without a graphics driver,
+    /// or if the style could not be set, see [`ImageRenderer::set_style`](crate::ImageRenderer::set_style).
+    pub fn new(
+        options: &ImageRendererOptions,
+        style: impl Into<StyleSource>,
+        workers: usize,
+    ) -> Result<Self, Error> {
+        assert!(workers > 0, "Tile server needs at least one worker");
+        let style = style.into();
+        let (sender, receiver) = channel::<TileJob>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let (started, startup) = channel();
+        let workers = (0..workers)
+            .map(|idx| {
+                let options = options.clone();
+                let style = style.clone();
+                let receiver = Arc::clone(&receiver);
+                let started = started.clone();
+                thread::Builder::new()
+                    .name(format!("mln-tile-{idx}"))
+                    .spawn(move || run_worker(options, style, &receiver, &started))
+                    .expect("Unable to spawn tile worker thread")
+            })
+            .collect();
+        let server = Self {
+            sender: Some(sender),
+            workers,
+        };
+        drop(started);
+        // Report the first worker that failed to start, the others exit once the server is dropped
+        for result in startup {
+            result?;
+        }
+        Ok(server)
+    }
+
+    /// Render a tile, and encode it in `format`, blocking until a renderer is free.
+    ///
+    /// # Errors
+    /// Returns the render or encoding error, see [`ImageRenderer::render_tile_with_format`](crate::ImageRenderer::render_tile_with_format),
+    /// or [`RenderError::WorkerStopped`] if the worker thread stopped,
+    /// e.g. because a previous render panicked.
+    pub fn tile(&self, tile: TileId, format: ImageFormat) -> Result<Vec<u8>, Error> {
+        let (reply, result) = sync_channel(1);
+        let sender = self
+            .sender
+            .as_ref()
+            .expect("Sender is only removed on drop");
+        sender
+            .send((tile, format, reply))
+            .map_err(|_| RenderError::WorkerStopped)?;
+        // The reply sender is dropped without a result if the worker panics
+        result
+            .recv()
+            .unwrap_or(Err(RenderError::WorkerStopped.into()))
+    }
+}
+
+impl Drop for TileServer {
+    fn drop(&mut self) {
+        // Closing the channel lets the workers exit once the queue is drained
+        self.sender.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn run_worker(
+    options: ImageRendererOptions,
+    style: StyleSource,
+    receiver: &Mutex<Receiver<TileJob>>,
+    started: &Sender<Result<(), Error>>,
+) {
+    let renderer = options.try_build_tile_renderer().and_then(|mut renderer| {
+        renderer.set_style(style)?;
+        Ok(renderer)
+    });
+    let mut renderer = match renderer {
+        Ok(renderer) => {
+            let _ = started.send(Ok(()));
+            renderer
+        }
+        Err(e) => {
+            let _ = started.send(Err(e));
+            return;
+        }
+    };
+    loop {
+        // Only hold the lock while waiting for the next job, not while rendering it
+        let Ok((tile, format, reply)) = receiver.lock().unwrap().recv() else {
+            return;
+        };
+        let result = renderer
+            .render_tile_with_format(tile.z(), tile.x(), tile.y(), format)
+            .map(crate::Image::into_vec)
+            .map_err(Error::from);
+        // The caller may have given up waiting, so the reply can be ignored
+        let _ = reply.send(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STYLE: &str = r##"{
+        "version": 8,
+        "sources": {
+            "quadrant": {
+                "type": "geojson",
+                "data": {
+                    "type": "Polygon",
+                    "coordinates": [[[0, 0], [180, 0], [180, 89], [0, 89], [0, 0]]]
+                }
+            }
+        },
+        "layers": [
+            {"id": "bg", "type": "background", "paint": {"background-color": "#fff"}},
+            {"id": "quadrant", "type": "fill", "source": "quadrant", "paint": {"fill-color": "#f0f"}}
+        ]
+    }"##;
+
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    #[test]
+    fn serve_tiles() {
+        let server = TileServer::new(&ImageRendererOptions::new(), STYLE, 2).unwrap();
+        assert_send_sync(&server);
+        let tiles: Vec<_> = thread::scope(|s| {
+            let handles: Vec<_> = ["1/1/0", "1/0/0", "0/0/0"]
+                .into_iter()
+                .map(|tile| {
+                    let server = &server;
+                    s.spawn(move || server.tile(TileId::parse(tile).unwrap(), ImageFormat::Png))
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        for tile in tiles {
+            let data = tile.unwrap();
+            let decoder = png::Decoder::new(data.as_slice()).read_info().unwrap();
+            assert_eq!(decoder.info().size(), (256, 256));
+        }
+    }
+
+    #[test]
+    fn invalid_style() {
+        assert!(matches!(
+            TileServer::new(&ImageRendererOptions::new(), "style.json", 2),
+            Err(Error::InvalidStyleUrl(_))
+        ));
+    }
+}
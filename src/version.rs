@@ -0,0 +1,25 @@
+/// Git revision of MapLibre Native that this crate was built from.
+///
+/// This is the pinned upstream commit, or the commit checked out in the `MLN_FROM_SOURCE`
+/// directory if it was set, or `unknown` if that directory is not a git repo.
+/// Include it in bug reports together with the [`active_backend`](crate::active_backend),
+/// as rendering may differ between revisions and graphics backends.
+#[must_use]
+pub fn maplibre_version() -> &'static str {
+    env!("MLN_REVISION")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revision() {
+        let version = maplibre_version();
+        assert!(
+            version == "unknown"
+                || (version.len() == 40 && version.chars().all(|c| c.is_ascii_hexdigit())),
+            "{version}"
+        );
+    }
+}